@@ -1,21 +1,48 @@
+use std::fmt;
 use std::path::{Path, PathBuf};
 use std::pin::pin;
-use std::process::Stdio;
+use std::process::{ExitStatus, Stdio};
 use std::sync::Arc;
 
-use anyhow::{bail, Context, Result};
-use cargo_metadata::Metadata;
+use anyhow::{Context, Result};
 use tokio::io::{AsyncRead, AsyncReadExt, AsyncWriteExt};
+use tokio::process::Command;
 use tokio::sync::OnceCell;
+use tokio::task::{spawn_blocking, JoinHandle};
 use tokio::{fs, spawn};
 
 use crate::env_file::EnvFile;
-use crate::manifest::Manifest;
+use crate::manifest::{Manifest, TargetBuilder};
 use crate::paths::Paths;
+use crate::precompress::precompress_dir;
 use crate::profile::Profile;
 use crate::utils::random_str;
+use crate::workspace::load_metadata;
 use crate::Stctl;
 
+/// The error of a build tool that exited with a failure.
+#[derive(Debug)]
+pub(crate) struct BuildFailed {
+    tool: &'static str,
+    status: ExitStatus,
+    output: String,
+}
+
+impl BuildFailed {
+    /// Returns the output of the build tool.
+    pub fn output(&self) -> &str {
+        &self.output
+    }
+}
+
+impl fmt::Display for BuildFailed {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} failed with status {}", self.tool, self.status)
+    }
+}
+
+impl std::error::Error for BuildFailed {}
+
 #[derive(Debug)]
 pub(crate) struct Builder {
     build_id: String,
@@ -28,8 +55,6 @@ pub(crate) struct Builder {
 
     frontend_build_dir: OnceCell<PathBuf>,
     backend_build_dir: OnceCell<PathBuf>,
-
-    backend_target: Option<String>,
 }
 
 impl Builder {
@@ -46,8 +71,6 @@ impl Builder {
 
             frontend_build_dir: OnceCell::new(),
             backend_build_dir: OnceCell::new(),
-
-            backend_target: None,
         })
     }
 
@@ -58,11 +81,9 @@ impl Builder {
         self
     }
 
-    /// Sets the backend target.
-    pub fn backend_target(mut self, backend_target: Option<String>) -> Self {
-        self.backend_target = backend_target;
-
-        self
+    /// Returns the id of current build.
+    pub fn build_id(&self) -> &str {
+        &self.build_id
     }
 
     /// Returns the frontend build directory of current build.
@@ -98,7 +119,10 @@ impl Builder {
             .map(|m| m.as_ref())
     }
 
-    async fn transfer_to_file<R, P>(source: R, target: P) -> Result<()>
+    /// Transfers the output of a process to a file in the background.
+    ///
+    /// The returned handle completes after all output is written.
+    async fn transfer_to_file<R, P>(source: R, target: P) -> Result<JoinHandle<()>>
     where
         R: 'static + AsyncRead + Send,
         P: Into<PathBuf>,
@@ -124,206 +148,291 @@ impl Builder {
             Ok::<(), anyhow::Error>(())
         };
 
-        spawn(async move {
+        Ok(spawn(async move {
             if let Err(e) = inner
                 .await
                 .with_context(|| format!("failed to transfer logs to: {}", target_path.display()))
             {
                 tracing::error!("{:#?}", e);
             }
-        });
-
-        Ok(())
+        }))
     }
 
-    pub async fn build_frontend(&self) -> Result<&Path> {
-        use tokio::process::Command;
+    /// Runs a build tool, with its output transferred to the log files of current build.
+    ///
+    /// If the build fails, the logs are returned in a [`BuildFailed`] error and printed to the
+    /// console in watch builds.
+    async fn run_tool(&self, mut proc: Command, tool: &'static str, logs_dir: &Path) -> Result<()> {
+        let stdout_path = logs_dir.join(format!("log-stdout-{}", self.build_id));
+        let stderr_path = logs_dir.join(format!("log-stderr-{}", self.build_id));
 
-        let frontend_logs_dir = self.paths.frontend_logs_dir().await?;
+        let mut child = proc.spawn()?;
+        let mut transfers = Vec::new();
+
+        if let Some(m) = child.stdout.take() {
+            transfers.push(Self::transfer_to_file(m, &stdout_path).await?);
+        }
+
+        if let Some(m) = child.stderr.take() {
+            transfers.push(Self::transfer_to_file(m, &stderr_path).await?);
+        }
+
+        let status = child.wait().await?;
+
+        if status.success() {
+            return Ok(());
+        }
+
+        for m in transfers {
+            m.await.context("failed to transfer logs")?;
+        }
+
+        let mut output = String::new();
+        for path in [stdout_path, stderr_path] {
+            if let Ok(m) = fs::read(&path).await {
+                output.push_str(&String::from_utf8_lossy(&m));
+            }
+        }
+
+        if self.is_watch_build {
+            eprintln!("{output}");
+        }
+
+        Err(BuildFailed {
+            tool,
+            status,
+            output,
+        }
+        .into())
+    }
+
+    /// Runs hook commands in order, the remaining commands are skipped if a command fails.
+    ///
+    /// Hooks receive the build profile in `STELLATION_PROFILE` and the frontend build directory of
+    /// current build in `STELLATION_FRONTEND_BUILD_DIR`.
+    pub async fn run_hooks(&self, commands: &[String], tool: &'static str) -> Result<()> {
+        let hooks_logs_dir = self.paths.hooks_logs_dir().await?;
         let frontend_build_dir = self.frontend_build_dir().await?;
         let workspace_dir = self.paths.workspace_dir().await?;
 
-        let create_proc = || {
-            let mut proc = Command::new("trunk");
-            proc.arg("build")
-                .arg("--dist")
-                .arg(frontend_build_dir)
-                .arg(workspace_dir.join("index.html"))
+        for command in commands {
+            let mut proc = if cfg!(windows) {
+                let mut proc = Command::new("cmd");
+                proc.arg("/C");
+                proc
+            } else {
+                let mut proc = Command::new("sh");
+                proc.arg("-c");
+                proc
+            };
+
+            proc.arg(command)
                 .current_dir(workspace_dir)
                 .stdin(Stdio::null())
                 .stdout(Stdio::piped())
-                .stderr(Stdio::piped());
-
-            if let Some(m) = self.profile.to_profile_argument() {
-                proc.arg(m);
-            }
+                .stderr(Stdio::piped())
+                .kill_on_drop(true);
 
             let envs = self.env_file.load(workspace_dir);
-            proc.envs(envs);
+            proc.envs(envs)
+                .env("STELLATION_PROFILE", self.profile.name())
+                .env("STELLATION_FRONTEND_BUILD_DIR", frontend_build_dir);
 
             if !self.is_watch_build {
                 proc.stdout(Stdio::inherit()).stderr(Stdio::inherit());
             }
-            proc
-        };
-
-        let mut child = create_proc().spawn()?;
 
-        if let Some(m) = child.stdout.take() {
-            Self::transfer_to_file(
-                m,
-                frontend_logs_dir.join(format!("log-stdout-{}", self.build_id)),
-            )
-            .await?;
+            self.run_tool(proc, tool, hooks_logs_dir)
+                .await
+                .with_context(|| format!("failed to run `{command}`"))?;
         }
 
-        if let Some(m) = child.stderr.take() {
-            Self::transfer_to_file(
-                m,
-                frontend_logs_dir.join(format!("log-stderr-{}", self.build_id)),
-            )
-            .await?;
+        Ok(())
+    }
+
+    pub async fn build_frontend(&self) -> Result<&Path> {
+        let frontend_logs_dir = self.paths.frontend_logs_dir().await?;
+        let frontend_build_dir = self.frontend_build_dir().await?;
+        let workspace_dir = self.paths.workspace_dir().await?;
+
+        let mut proc = Command::new("trunk");
+        proc.arg("build")
+            .arg("--dist")
+            .arg(frontend_build_dir)
+            .arg("--public-url")
+            .arg(self.manifest.base_path())
+            .arg(self.manifest.index_path(workspace_dir))
+            .current_dir(workspace_dir)
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true);
+
+        if let Some(m) = self.profile.to_profile_argument() {
+            proc.arg(m);
         }
 
-        let status = child.wait().await?;
+        proc.args(&self.manifest.frontend.trunk_args);
 
-        // We try again with logs printed to console.
-        if !status.success() {
-            if !self.is_watch_build {
-                bail!("trunk failed with status {}", status);
-            }
+        let envs = self.env_file.load(workspace_dir);
+        proc.envs(envs);
 
-            let mut proc = create_proc();
+        if !self.is_watch_build {
             proc.stdout(Stdio::inherit()).stderr(Stdio::inherit());
+        }
 
-            let mut child = proc.spawn()?;
-            let status = child.wait().await?;
+        self.run_tool(proc, "trunk", frontend_logs_dir).await?;
 
-            if !status.success() {
-                bail!("trunk failed with status {}", status);
-            }
+        // Development builds are served uncompressed.
+        if !self.is_watch_build {
+            let frontend_build_dir = frontend_build_dir.to_owned();
+
+            spawn_blocking(move || precompress_dir(&frontend_build_dir))
+                .await
+                .context("failed to compress frontend")?
+                .context("failed to compress frontend")?;
         }
 
         Ok(frontend_build_dir)
     }
 
-    pub async fn build_backend(&self) -> Result<PathBuf> {
-        use tokio::process::Command;
-
+    /// Builds the backend binary for a target, `None` for the native target.
+    ///
+    /// Binaries of other targets are placed in a directory named after the target, so binaries of
+    /// multiple targets can be built by the same builder.
+    pub async fn build_backend(&self, target: Option<&str>) -> Result<PathBuf> {
         let frontend_build_dir = self.frontend_build_dir().await?;
         let backend_logs_dir = self.paths.backend_logs_dir().await?;
         let workspace_dir = self.paths.workspace_dir().await?;
         let backend_build_dir = self.backend_build_dir().await?;
 
-        let create_proc = || {
-            let mut proc = Command::new("cargo");
-            proc.arg("build")
-                .arg("--bin")
-                .arg(&self.manifest.dev_server.bin_name)
-                .current_dir(workspace_dir)
-                .stdin(Stdio::null())
-                .stdout(Stdio::piped())
-                .stderr(Stdio::piped())
-                .kill_on_drop(true);
+        let target_builder = target
+            .and_then(|m| self.manifest.backend_target(m))
+            .map(|m| m.builder)
+            .unwrap_or_default();
 
-            if let Some(m) = self.profile.to_profile_argument() {
-                proc.arg(m);
+        let mut proc = match target_builder {
+            TargetBuilder::Cargo => {
+                let mut proc = Command::new("cargo");
+                proc.arg("build");
+                proc
             }
-
-            if let Some(ref m) = self.backend_target {
-                proc.arg(format!("--target={}", m));
+            TargetBuilder::Cross => {
+                let mut proc = Command::new("cross");
+                proc.arg("build");
+                proc
             }
-
-            let envs = self.env_file.load(workspace_dir);
-            proc.envs(envs);
-
-            if !self.is_watch_build {
-                proc.stdout(Stdio::inherit())
-                    .stderr(Stdio::inherit())
-                    .env("RUSTFLAGS", "--cfg stellation_embedded_frontend");
+            TargetBuilder::Zigbuild => {
+                let mut proc = Command::new("cargo");
+                proc.arg("zigbuild");
+                proc
             }
-
-            proc.env("STELLATION_FRONTEND_BUILD_DIR", frontend_build_dir);
-
-            proc
         };
 
-        let mut child = create_proc().spawn()?;
+        proc.arg("--bin")
+            .arg(&self.manifest.dev_server.bin_name)
+            .current_dir(workspace_dir)
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true);
 
-        if let Some(m) = child.stdout.take() {
-            Self::transfer_to_file(
-                m,
-                backend_logs_dir.join(format!("log-stdout-{}", self.build_id)),
-            )
-            .await?;
+        if let Some(m) = self.profile.to_profile_argument() {
+            proc.arg(m);
         }
 
-        if let Some(m) = child.stderr.take() {
-            Self::transfer_to_file(
-                m,
-                backend_logs_dir.join(format!("log-stderr-{}", self.build_id)),
-            )
-            .await?;
+        if let Some(m) = target {
+            proc.arg(format!("--target={m}"));
         }
 
-        let status = child.wait().await?;
-
-        // We try again with logs printed to console.
-        if !status.success() {
-            if !self.is_watch_build {
-                bail!("trunk failed with status {}", status);
+        if let Some(m) = self.manifest.backend_profile(&self.profile) {
+            if !m.features.is_empty() {
+                proc.arg(format!("--features={}", m.features.join(",")));
             }
 
-            let mut proc = create_proc();
-            proc.stdout(Stdio::inherit()).stderr(Stdio::inherit());
+            if m.no_default_features {
+                proc.arg("--no-default-features");
+            }
+        }
 
-            let mut child = proc.spawn()?;
-            let status = child.wait().await?;
+        let envs = self.env_file.load(workspace_dir);
+        proc.envs(envs);
+
+        if let Some((triple, linker)) = target.and_then(|t| {
+            let linker = self.manifest.backend_target(t)?.linker.as_ref()?;
+            Some((target_triple(t), linker))
+        }) {
+            proc.env(
+                format!(
+                    "CARGO_TARGET_{}_LINKER",
+                    triple.to_uppercase().replace(['-', '.'], "_")
+                ),
+                linker,
+            );
+        }
 
-            if !status.success() {
-                bail!("trunk failed with status {}", status);
-            }
+        // Development builds receive the base path from the metadata of the development server.
+        if !self.is_watch_build {
+            proc.stdout(Stdio::inherit())
+                .stderr(Stdio::inherit())
+                .env("RUSTFLAGS", "--cfg stellation_embedded_frontend")
+                .env("STELLATION_BUILD_BASE_PATH", self.manifest.base_path());
         }
 
-        // Copy artifact from target directory.
-        let pkg_meta_output = Command::new("cargo")
-            .arg("metadata")
-            .arg("--format-version=1")
-            .stdin(Stdio::null())
-            .stdout(Stdio::piped())
-            .stderr(Stdio::piped())
-            .current_dir(workspace_dir)
-            .spawn()?
-            .wait_with_output()
-            .await
-            .context("failed to read package metadata")?;
+        proc.env("STELLATION_FRONTEND_BUILD_DIR", frontend_build_dir);
 
-        if !pkg_meta_output.status.success() {
-            bail!(
-                "cargo metadata failed with status {}",
-                pkg_meta_output.status
+        // Cross only forwards selected variables into its container.
+        if target_builder == TargetBuilder::Cross {
+            proc.env(
+                "CROSS_BUILD_ENV_PASSTHROUGH",
+                "STELLATION_FRONTEND_BUILD_DIR",
             );
         }
 
-        let meta: Metadata = serde_json::from_slice(&pkg_meta_output.stdout)
-            .context("failed to parse package metadata")?;
+        self.run_tool(proc, target_builder.tool(), backend_logs_dir)
+            .await?;
 
+        // Copy artifact from target directory.
+        let meta = load_metadata(workspace_dir).await?;
+
+        let bin_file_name = bin_file_name(&self.manifest.dev_server.bin_name, target);
         let mut bin_path = meta.target_directory.into_std_path_buf();
+        let mut backend_bin_path = backend_build_dir.to_owned();
 
-        if let Some(ref m) = self.backend_target {
-            bin_path = bin_path.join(m);
-        }
+        if let Some(m) = target {
+            bin_path = bin_path.join(target_triple(m));
+            backend_bin_path = backend_bin_path.join(m);
 
-        bin_path = bin_path
-            .join(self.profile.name())
-            .join(&self.manifest.dev_server.bin_name);
+            fs::create_dir_all(&backend_bin_path)
+                .await
+                .context("failed to create backend build directory")?;
+        }
 
-        let backend_bin_path = backend_build_dir.join(&self.manifest.dev_server.bin_name);
+        bin_path = bin_path.join(self.profile.name()).join(&bin_file_name);
+        backend_bin_path = backend_bin_path.join(&bin_file_name);
 
-        fs::copy(bin_path, &backend_bin_path)
+        fs::copy(&bin_path, &backend_bin_path)
             .await
-            .context("failed to copy binary")?;
+            .with_context(|| format!("failed to copy binary {}", bin_path.display()))?;
 
         Ok(backend_bin_path)
     }
 }
+
+/// Returns the target triple without the glibc version of zigbuild.
+fn target_triple(target: &str) -> &str {
+    target.split('.').next().unwrap_or(target)
+}
+
+/// Returns the file name of the backend binary for a target, `None` for the native target.
+fn bin_file_name(bin_name: &str, target: Option<&str>) -> String {
+    let is_windows = match target {
+        Some(m) => target_triple(m).contains("windows"),
+        None => cfg!(windows),
+    };
+
+    match is_windows {
+        true => format!("{bin_name}.exe"),
+        false => bin_name.to_owned(),
+    }
+}
@@ -0,0 +1,293 @@
+//! Writes the deployment bundle of `stctl build --bundle`.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+use std::process::Stdio;
+
+use anyhow::{Context, Result};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use tokio::process::Command;
+use tokio::task::spawn_blocking;
+
+/// The port that the backend listens on in containers.
+const CONTAINER_PORT: u16 = 5000;
+
+/// The build information recorded in `manifest.json`.
+#[derive(Debug, Serialize)]
+struct BundleManifest {
+    build_id: String,
+    profile: String,
+    target: String,
+    git_revision: Option<String>,
+    git_dirty: bool,
+    binary: String,
+    binary_sha256: String,
+    frontend_assets: BTreeMap<String, String>,
+}
+
+/// A deployment bundle of a build.
+#[derive(Debug)]
+pub(crate) struct Bundle<'a> {
+    /// The id of the build.
+    pub build_id: &'a str,
+    /// The name of the build profile.
+    pub profile: &'a str,
+    /// The backend target, `None` for the native target.
+    pub target: Option<&'a str>,
+    /// The base path that the backend is served under.
+    pub base_path: &'a str,
+    /// The backend binary.
+    pub bin_path: &'a Path,
+    /// The frontend artifacts, these are embedded into the backend binary.
+    pub frontend_dir: &'a Path,
+}
+
+impl Bundle<'_> {
+    /// Writes the bundle to a directory, the directory is replaced if it exists.
+    pub async fn write_to(&self, workspace_dir: &Path, bundle_dir: &Path) -> Result<()> {
+        let bin_name = self
+            .bin_path
+            .file_name()
+            .context("failed to find backend binary name")?
+            .to_string_lossy()
+            .into_owned();
+
+        let target = match self.target {
+            Some(m) => m.to_owned(),
+            None => host_target().await?,
+        };
+        let git_revision = git_output(workspace_dir, &["rev-parse", "HEAD"]).await;
+        let git_dirty = git_output(workspace_dir, &["status", "--porcelain"])
+            .await
+            .map(|m| !m.is_empty())
+            .unwrap_or(false);
+
+        let bin_path = self.bin_path.to_owned();
+        let frontend_dir = self.frontend_dir.to_owned();
+        let (binary_sha256, frontend_assets) = spawn_blocking(move || -> Result<_> {
+            Ok((sha256_file(&bin_path)?, hash_assets(&frontend_dir)?))
+        })
+        .await??;
+
+        let manifest = BundleManifest {
+            build_id: self.build_id.to_owned(),
+            profile: self.profile.to_owned(),
+            target: target.clone(),
+            git_revision,
+            git_dirty,
+            binary: bin_name.clone(),
+            binary_sha256,
+            frontend_assets,
+        };
+
+        if bundle_dir.exists() {
+            tokio::fs::remove_dir_all(bundle_dir)
+                .await
+                .context("failed to clean past bundle.")?;
+        }
+        tokio::fs::create_dir_all(bundle_dir)
+            .await
+            .context("failed to create bundle directory.")?;
+
+        tokio::fs::copy(self.bin_path, bundle_dir.join(&bin_name))
+            .await
+            .context("failed to copy backend to bundle")?;
+
+        let mut files = vec![(
+            "manifest.json".to_owned(),
+            serde_json::to_string_pretty(&manifest)?,
+        )];
+
+        // Containers and systemd units can only run linux binaries.
+        if target.contains("linux") {
+            files.push(("Dockerfile".to_owned(), self.dockerfile(&bin_name, &target)));
+            files.push((format!("{bin_name}.service"), self.systemd_unit(&bin_name)));
+        }
+
+        for (name, content) in files {
+            let path = bundle_dir.join(name);
+            tokio::fs::write(&path, content)
+                .await
+                .with_context(|| format!("failed to write {}", path.display()))?;
+        }
+
+        Ok(())
+    }
+
+    /// Environment variables of the backend that are shared by all deployments.
+    fn envs(&self) -> Vec<(&'static str, String)> {
+        let mut envs = vec![("STELLATION_LOG_FORMAT", "json".to_owned())];
+
+        if self.base_path != "/" {
+            envs.push(("STELLATION_BASE_PATH", self.base_path.to_owned()));
+        }
+
+        envs
+    }
+
+    fn dockerfile(&self, bin_name: &str, target: &str) -> String {
+        // Statically linked binaries do not need a libc in the image.
+        let (image, user) = match target.contains("musl") {
+            true => ("scratch", "65534:65534"),
+            false => ("gcr.io/distroless/cc-debian12", "nonroot:nonroot"),
+        };
+
+        let from = match docker_platform(target) {
+            Some(m) => format!("--platform={m} {image}"),
+            None => image.to_owned(),
+        };
+
+        let mut dockerfile = format!(
+            "# Generated by stctl, the frontend is embedded into the backend binary.\n\
+             FROM {from}\n\
+             COPY {bin_name} /app/{bin_name}\n\
+             USER {user}\n\
+             ENV STELLATION_LISTEN_ADDR=0.0.0.0:{CONTAINER_PORT}\n"
+        );
+        for (key, value) in self.envs() {
+            dockerfile.push_str(&format!("ENV {key}={value}\n"));
+        }
+        dockerfile.push_str(&format!(
+            "EXPOSE {CONTAINER_PORT}\nENTRYPOINT [\"/app/{bin_name}\"]\n"
+        ));
+
+        dockerfile
+    }
+
+    fn systemd_unit(&self, bin_name: &str) -> String {
+        let mut unit = format!(
+            "# Generated by stctl, install the binary to /opt/{bin_name}/{bin_name} or update \
+             ExecStart.\n\
+             [Unit]\n\
+             Description={bin_name}\n\
+             Wants=network-online.target\n\
+             After=network-online.target\n\
+             \n\
+             [Service]\n\
+             Type=simple\n\
+             ExecStart=/opt/{bin_name}/{bin_name}\n\
+             Environment=STELLATION_LISTEN_ADDR=127.0.0.1:{CONTAINER_PORT}\n"
+        );
+        for (key, value) in self.envs() {
+            unit.push_str(&format!("Environment={key}={value}\n"));
+        }
+        unit.push_str(
+            "Restart=on-failure\n\
+             DynamicUser=yes\n\
+             NoNewPrivileges=yes\n\
+             ProtectSystem=strict\n\
+             ProtectHome=yes\n\
+             PrivateTmp=yes\n\
+             \n\
+             [Install]\n\
+             WantedBy=multi-user.target\n",
+        );
+
+        unit
+    }
+}
+
+/// Returns the docker platform of a target.
+fn docker_platform(target: &str) -> Option<&'static str> {
+    let platform = match target.split('-').next()? {
+        "x86_64" => "linux/amd64",
+        "aarch64" => "linux/arm64",
+        "armv7" => "linux/arm/v7",
+        "i686" => "linux/386",
+        "powerpc64le" => "linux/ppc64le",
+        "riscv64gc" => "linux/riscv64",
+        "s390x" => "linux/s390x",
+        _ => return None,
+    };
+
+    Some(platform)
+}
+
+/// Returns the target triple of the building environment.
+async fn host_target() -> Result<String> {
+    let output = Command::new("rustc")
+        .arg("-vV")
+        .stdin(Stdio::null())
+        .output()
+        .await
+        .context("failed to run rustc")?;
+
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .find_map(|m| m.strip_prefix("host: "))
+        .map(|m| m.trim().to_owned())
+        .context("failed to find the host target of rustc")
+}
+
+/// Returns the trimmed output of a git command, `None` if git is not available.
+async fn git_output(workspace_dir: &Path, args: &[&str]) -> Option<String> {
+    let output = Command::new("git")
+        .args(args)
+        .current_dir(workspace_dir)
+        .stdin(Stdio::null())
+        .stderr(Stdio::null())
+        .output()
+        .await
+        .ok()?;
+
+    output
+        .status
+        .success()
+        .then(|| String::from_utf8_lossy(&output.stdout).trim().to_owned())
+}
+
+fn sha256_file(path: &Path) -> Result<String> {
+    let data = fs::read(path).with_context(|| format!("failed to read {}", path.display()))?;
+
+    Ok(Sha256::digest(data)
+        .iter()
+        .map(|m| format!("{m:02x}"))
+        .collect())
+}
+
+/// Returns the hashes of frontend assets, keyed by their paths relative to the frontend directory.
+///
+/// Precompressed siblings are skipped as they are derived from the asset.
+fn hash_assets(frontend_dir: &Path) -> Result<BTreeMap<String, String>> {
+    let mut assets = BTreeMap::new();
+    let mut dirs = vec![frontend_dir.to_owned()];
+
+    while let Some(dir) = dirs.pop() {
+        let entries =
+            fs::read_dir(&dir).with_context(|| format!("failed to read {}", dir.display()))?;
+
+        for entry in entries {
+            let path = entry?.path();
+
+            if path.is_dir() {
+                dirs.push(path);
+                continue;
+            }
+
+            if is_precompressed(&path) {
+                continue;
+            }
+
+            let relative_path = path
+                .strip_prefix(frontend_dir)?
+                .components()
+                .map(|m| m.as_os_str().to_string_lossy())
+                .collect::<Vec<_>>()
+                .join("/");
+            assets.insert(relative_path, sha256_file(&path)?);
+        }
+    }
+
+    Ok(assets)
+}
+
+fn is_precompressed(path: &Path) -> bool {
+    let is_compressed_ext = path
+        .extension()
+        .map(|m| m == "gz" || m == "br")
+        .unwrap_or(false);
+
+    is_compressed_ext && path.with_extension("").is_file()
+}
@@ -2,7 +2,7 @@ use std::path::PathBuf;
 use std::sync::Arc;
 
 use anyhow::{Context, Result};
-use clap::{Parser, Subcommand};
+use clap::{Args, Parser, Subcommand, ValueEnum};
 use tokio::fs;
 
 use crate::manifest::Manifest;
@@ -12,7 +12,8 @@ pub(crate) struct ServeCommand {
     /// Open browser after the development server is ready.
     #[arg(long)]
     pub open: bool,
-    /// The name of the env profile. [Default: the same name as the build profile]
+    /// The name of the env profile. [Default: `env-profiles.<build profile>` of stellation.toml or
+    /// the name of the build profile]
     #[arg(long)]
     pub env: Option<String>,
 }
@@ -22,13 +23,88 @@ pub(crate) struct BuildCommand {
     /// Build artifacts in release mode, with optimizations.
     #[arg(long)]
     pub release: bool,
-    /// The name of the env profile. [Default: the same name as the build profile]
+    /// The name of the env profile. [Default: `env-profiles.<build profile>` of stellation.toml or
+    /// the name of the build profile]
     #[arg(long)]
     pub env: Option<String>,
-    /// The build target for backend binary. [Default: the native target of the building
+    /// The build targets for backend binary, can be repeated or separated by commas. [Default:
+    /// the targets of the build profile in stellation.toml or the native target of the building
     /// environment]
+    #[arg(long, value_delimiter = ',')]
+    pub backend_target: Vec<String>,
+    /// Write a deployment bundle with a Dockerfile, a systemd unit and a build manifest.
     #[arg(long)]
-    pub backend_target: Option<String>,
+    pub bundle: bool,
+}
+
+#[derive(Parser, Debug)]
+pub(crate) struct CheckCommand {
+    /// Run clippy instead of cargo check.
+    #[arg(long)]
+    pub clippy: bool,
+    /// The name of the env profile. [Default: `env-profiles.<build profile>` of stellation.toml or
+    /// the name of the build profile]
+    #[arg(long)]
+    pub env: Option<String>,
+    /// Arguments passed to clippy, e.g.: `stctl check --clippy -- -D warnings`.
+    #[arg(last = true, requires = "clippy")]
+    pub args: Vec<String>,
+}
+
+#[derive(Parser, Debug)]
+pub(crate) struct TestCommand {
+    /// Skip wasm-bindgen-test tests of frontend crates.
+    #[arg(long)]
+    pub no_wasm: bool,
+    /// The name of the env profile. [Default: `env-profiles.<build profile>` of stellation.toml or
+    /// the name of the build profile]
+    #[arg(long)]
+    pub env: Option<String>,
+    /// Arguments passed to the test binaries, e.g.: `stctl test -- --nocapture`.
+    #[arg(last = true)]
+    pub args: Vec<String>,
+}
+
+/// The server library of a new project.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub(crate) enum BackendFlavour {
+    /// A tower service served by hyper with stellation-backend-cli.
+    Tower,
+    /// A warp filter served by warp.
+    Warp,
+}
+
+#[derive(Args, Debug)]
+pub(crate) struct ScaffoldOptions {
+    /// The server library of the backend.
+    #[arg(long, value_enum, default_value = "tower")]
+    pub backend: BackendFlavour,
+    /// Do not style components with stylist.
+    #[arg(long)]
+    pub no_stylist: bool,
+    /// Do not create the api crate and the bridge between the frontend and the backend.
+    #[arg(long)]
+    pub no_bridge: bool,
+    /// Use stellation crates from a local copy of the stellation repository instead of crates.io.
+    #[arg(long, value_name = "DIR")]
+    pub stellation_path: Option<PathBuf>,
+}
+
+#[derive(Parser, Debug)]
+pub(crate) struct NewCommand {
+    /// The name of the project, the project is created in a directory with this name.
+    pub name: String,
+    #[command(flatten)]
+    pub options: ScaffoldOptions,
+}
+
+#[derive(Parser, Debug)]
+pub(crate) struct InitCommand {
+    /// The name of the project. [Default: the name of the current directory]
+    #[arg(long)]
+    pub name: Option<String>,
+    #[command(flatten)]
+    pub options: ScaffoldOptions,
 }
 
 #[derive(Subcommand, Debug)]
@@ -38,8 +114,16 @@ pub(crate) enum CliCommand {
     Serve(ServeCommand),
     /// Build the server and client for final distribution.
     Build(BuildCommand),
+    /// Check the backend crates natively and the frontend crates for wasm32-unknown-unknown.
+    Check(CheckCommand),
+    /// Run tests of the backend crates and wasm-bindgen-test tests of the frontend crates.
+    Test(TestCommand),
     /// Cleans the artifact generated by stctl, cargo and trunk.
     Clean,
+    /// Create a project in a new directory.
+    New(NewCommand),
+    /// Create a project in the current directory.
+    Init(InitCommand),
 }
 
 #[derive(Parser, Debug)]
@@ -0,0 +1,67 @@
+//! Extracts diagnostics from the output of build tools.
+
+/// The number of lines shown if no diagnostic can be found in the output.
+const FALLBACK_LINES: usize = 50;
+
+/// Removes ANSI escape sequences, which are emitted by tools that print coloured output.
+fn strip_ansi(s: &str) -> String {
+    let mut output = String::with_capacity(s.len());
+    let mut chars = s.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if c != '\u{1b}' {
+            output.push(c);
+            continue;
+        }
+
+        // Control sequences end with a character in the range of `@` to `~`.
+        if chars.next_if_eq(&'[').is_some() {
+            for m in chars.by_ref() {
+                if ('@'..='~').contains(&m) {
+                    break;
+                }
+            }
+        }
+    }
+
+    output
+}
+
+/// Returns `true` if a line starts an error reported by rustc, cargo or trunk.
+fn is_error_start(line: &str) -> bool {
+    let line = line.trim_start();
+
+    line.starts_with("error:") || line.starts_with("error[")
+}
+
+/// Extracts errors from the output of cargo or trunk.
+///
+/// An error continues until an empty line, which is the format used by rustc. If the output does
+/// not contain any error, the last lines of the output are returned.
+pub(crate) fn extract_errors(output: &str) -> String {
+    let output = strip_ansi(output);
+    let mut errors = Vec::new();
+    let mut current: Option<Vec<&str>> = None;
+
+    for line in output.lines() {
+        match current {
+            Some(ref mut m) if !line.trim().is_empty() => m.push(line),
+            Some(_) => errors.extend(current.take().map(|m| m.join("\n"))),
+            None if is_error_start(line) => current = Some(vec![line]),
+            None => {}
+        }
+    }
+    errors.extend(current.map(|m| m.join("\n")));
+
+    // Summaries such as `error: could not compile` do not help if other errors are present.
+    if errors.len() > 1 {
+        errors.retain(|m| !m.starts_with("error: could not compile"));
+    }
+
+    if !errors.is_empty() {
+        return errors.join("\n\n");
+    }
+
+    let lines = output.lines().collect::<Vec<_>>();
+    lines[lines.len().saturating_sub(FALLBACK_LINES)..].join("\n")
+}
@@ -0,0 +1,135 @@
+//! Serves the error of a failed build while the development server is not running.
+
+use std::convert::Infallible;
+use std::net::{SocketAddr, ToSocketAddrs};
+
+use anyhow::{Context, Result};
+use hyper::header::{CACHE_CONTROL, CONTENT_TYPE};
+use hyper::service::{make_service_fn, service_fn};
+use hyper::{Body, Response, Server, StatusCode};
+use tokio::spawn;
+use tokio::task::JoinHandle;
+
+use crate::utils::random_str;
+
+/// The header that marks responses of the error server.
+///
+/// Pages check this header to reload after the development server is running again.
+pub(crate) const BUILD_ERROR_HEADER: &str = "x-stellation-build-error";
+
+const ERROR_PAGE: &str = r#"<!doctype html>
+<html>
+<head>
+    <meta charset="utf-8">
+    <title>Build failed</title>
+    <style>
+        body {
+            margin: 0;
+            padding: 24px;
+            background: #18181b;
+            color: #fafafa;
+            font: 14px/1.5 monospace;
+        }
+        h1 {
+            margin: 0 0 16px;
+            color: #f87171;
+            font: bold 18px sans-serif;
+        }
+        pre {
+            margin: 0;
+            white-space: pre-wrap;
+        }
+    </style>
+</head>
+<body>
+    <h1>Build failed</h1>
+    <pre>%MESSAGE%</pre>
+    <script>
+        (() => {
+            const errorId = '%ERROR_ID%';
+
+            setInterval(async () => {
+                try {
+                    const resp = await fetch(window.location.href, { method: 'HEAD', cache: 'no-store' });
+                    if (resp.headers.get('%HEADER%') !== errorId) {
+                        window.location.reload();
+                    }
+                } catch(e) {
+                    // The development server is starting.
+                }
+            }, 1000);
+        })();
+    </script>
+</body>
+</html>
+"#;
+
+/// Escapes text to be placed in html.
+fn escape_html(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// A server that responds to all requests with the error of a failed build.
+#[derive(Debug)]
+pub(crate) struct ErrorServer {
+    handle: JoinHandle<()>,
+}
+
+impl ErrorServer {
+    /// Starts the server at the listen address of the development server.
+    pub async fn start(listen_addr: &str, message: &str) -> Result<Self> {
+        let addr: SocketAddr = listen_addr
+            .to_socket_addrs()
+            .context("failed to parse address")?
+            .next()
+            .context("failed to parse address")?;
+
+        let error_id = random_str()?;
+        let page = ERROR_PAGE
+            .replace("%ERROR_ID%", &error_id)
+            .replace("%HEADER%", BUILD_ERROR_HEADER)
+            .replace("%MESSAGE%", &escape_html(message));
+
+        let make_svc = make_service_fn(move |_| {
+            let page = page.clone();
+            let error_id = error_id.clone();
+
+            async move {
+                Ok::<_, Infallible>(service_fn(move |_| {
+                    let resp = Response::builder()
+                        .status(StatusCode::SERVICE_UNAVAILABLE)
+                        .header(CONTENT_TYPE, "text/html; charset=utf-8")
+                        .header(CACHE_CONTROL, "no-store")
+                        .header(BUILD_ERROR_HEADER, error_id.as_str())
+                        .body(Body::from(page.clone()));
+
+                    async move { resp }
+                }))
+            }
+        });
+
+        let server = Server::try_bind(&addr)
+            .context("failed to listen for error server")?
+            // Connections are not kept alive, so they are closed with the server.
+            .http1_keepalive(false)
+            .serve(make_svc);
+
+        let handle = spawn(async move {
+            if let Err(e) = server.await {
+                tracing::error!("error server failed: {:?}", e);
+            }
+        });
+
+        Ok(Self { handle })
+    }
+
+    /// Stops the server, so the development server can listen to the address.
+    pub async fn stop(self) {
+        self.handle.abort();
+        // The handle returns a cancellation error after it is aborted.
+        let _ = self.handle.await;
+    }
+}
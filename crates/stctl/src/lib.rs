@@ -13,23 +13,31 @@
 #![cfg_attr(any(releasing, not(debug_assertions)), deny(dead_code, unused_imports))]
 
 mod builder;
+mod bundle;
 mod cli;
+mod diagnostics;
 mod env_file;
+mod error_server;
 mod indicators;
 mod manifest;
 mod paths;
+mod precompress;
 mod profile;
+mod scaffold;
+mod steps;
 mod utils;
+mod watch;
+mod workspace;
 
-use std::path::PathBuf;
-use std::pin::pin;
+use std::path::{Path, PathBuf};
+use std::pin::{pin, Pin};
 use std::process::Stdio;
 use std::sync::Arc;
 use std::time::{Duration, SystemTime};
 
-use anyhow::{Context, Result};
+use anyhow::{bail, Context, Result};
 use clap::Parser;
-use cli::{BuildCommand, Cli, CliCommand, ServeCommand};
+use cli::{BuildCommand, CheckCommand, Cli, CliCommand, ServeCommand, TestCommand};
 use console::{style, Term};
 use env_file::EnvFile;
 use futures::future::ready;
@@ -39,7 +47,7 @@ use manifest::Manifest;
 use notify::{recommended_watcher, Event, RecursiveMode, Watcher};
 use paths::Paths;
 use profile::Profile;
-use stellation_core::dev::StctlMetadata;
+use stellation_core::dev::{BuildStatus, StctlMetadata};
 use tokio::fs;
 use tokio::process::Child;
 use tokio::sync::mpsc::unbounded_channel;
@@ -50,8 +58,14 @@ use tracing::Level;
 use tracing_subscriber::prelude::*;
 use tracing_subscriber::EnvFilter;
 
-use crate::builder::Builder;
+use crate::builder::{BuildFailed, Builder};
+use crate::bundle::Bundle;
+use crate::diagnostics::extract_errors;
+use crate::error_server::ErrorServer;
 use crate::indicators::ServeProgress;
+use crate::steps::Steps;
+use crate::watch::WatchFilter;
+use crate::workspace::{CheckCrates, Rebuild, WorkspaceCrates};
 
 #[derive(Debug)]
 struct ServeArtifact {
@@ -60,6 +74,68 @@ struct ServeArtifact {
     backend_artifact_dir: PathBuf,
 }
 
+impl ServeArtifact {
+    /// Stops the server and removes its artifacts.
+    async fn stop(self) -> Result<()> {
+        let Self {
+            mut child,
+            frontend_artifact_dir,
+            backend_artifact_dir,
+        } = self;
+
+        child.kill().await.context("failed to stop server")?;
+        fs::remove_dir_all(frontend_artifact_dir)
+            .await
+            .context("failed to remove stale frontend artifact")?;
+        fs::remove_dir_all(backend_artifact_dir)
+            .await
+            .context("failed to remove stale backend artifact")?;
+
+        Ok(())
+    }
+}
+
+/// The server listening at the address of the development server.
+#[derive(Debug)]
+enum Serving {
+    /// The application built from the workspace.
+    App(ServeArtifact),
+    /// The error server, which is started if the application cannot be started.
+    Error(ErrorServer),
+}
+
+impl Serving {
+    /// Stops the server.
+    async fn stop(self) -> Result<()> {
+        match self {
+            Self::App(m) => m.stop().await,
+            Self::Error(m) => {
+                m.stop().await;
+                Ok(())
+            }
+        }
+    }
+}
+
+/// The artifacts of a build for the development server.
+#[derive(Debug)]
+struct ServeBuild {
+    frontend_build_dir: PathBuf,
+    backend_build_dir: PathBuf,
+    backend_build_path: PathBuf,
+}
+
+/// The outcome of a build for the development server.
+#[derive(Debug)]
+enum BuildOutcome {
+    Succeeded,
+    Failed,
+    /// The build is cancelled as files are changed, with the paths of changed files.
+    Cancelled(Vec<PathBuf>),
+    /// The workspace is no longer watched.
+    Stopped,
+}
+
 #[derive(Debug)]
 struct Stctl {
     cli: Arc<Cli>,
@@ -82,7 +158,11 @@ impl Stctl {
                     Profile::new_debug()
                 }
             }
-            CliCommand::Clean => Profile::new_debug(),
+            CliCommand::Check(_)
+            | CliCommand::Test(_)
+            | CliCommand::Clean
+            | CliCommand::New(_)
+            | CliCommand::Init(_) => Profile::new_debug(),
         };
 
         let env_name = match cli.command {
@@ -91,12 +171,29 @@ impl Stctl {
             })
             | CliCommand::Serve(ServeCommand {
                 env: Some(ref m), ..
+            })
+            | CliCommand::Check(CheckCommand {
+                env: Some(ref m), ..
+            })
+            | CliCommand::Test(TestCommand {
+                env: Some(ref m), ..
             }) => m,
-            _ => profile.name(),
+            _ => manifest.env_profile(&profile),
         };
 
         let env_file = EnvFile::new(env_name);
         let paths = Paths::new(&cli.manifest_path).await?;
+        let workspace_dir = paths.workspace_dir().await?;
+
+        // Clean only removes the output directory, the rest of the manifest is not used.
+        match cli.command {
+            CliCommand::Clean => manifest.validate_output_dir(workspace_dir),
+            _ => manifest.validate(workspace_dir).await,
+        }
+        .context("invalid stellation.toml")?;
+
+        let output_dir = manifest.output_dir(workspace_dir);
+        let paths = paths.with_output_dir(output_dir);
 
         Ok(Self {
             cli: cli.into(),
@@ -107,7 +204,14 @@ impl Stctl {
         })
     }
 
-    async fn watch_changes(&self) -> Result<impl Stream<Item = SystemTime>> {
+    /// Watches the workspace for changes.
+    ///
+    /// Changes are collected for a short period, each item contains the time after the period and
+    /// the paths changed in that period.
+    async fn watch_changes(
+        &self,
+        filter: Arc<WatchFilter>,
+    ) -> Result<impl Stream<Item = (SystemTime, Vec<PathBuf>)>> {
         let workspace_dir = self.paths.workspace_dir().await?;
         let (tx, rx) = unbounded_channel::<PathBuf>();
 
@@ -127,27 +231,14 @@ impl Stctl {
             .context("failed to watch workspace")?;
 
         let stream = UnboundedReceiverStream::new(rx)
-            .filter(|p| {
-                let p_str = p.as_os_str().to_string_lossy();
-                if p_str.contains("target/") {
-                    return ready(false);
-                }
-                if p_str.contains(".stellation/") {
-                    return ready(false);
-                }
-                if !p_str.contains("src/") {
-                    return ready(false);
-                }
-
-                ready(true)
-            })
+            .filter(move |p| ready(filter.is_watched(p)))
             .boxed();
 
         Ok(unfold(
             (stream, watcher),
             |(mut stream, watcher)| async move {
                 // We wait until first item is available.
-                stream.next().await?;
+                let mut paths = vec![stream.next().await?];
 
                 let mut sleep_fur = pin!(sleep(Duration::from_millis(100)).fuse());
 
@@ -158,11 +249,11 @@ impl Stctl {
 
                     futures::select! {
                         _ = sleep_fur => break,
-                        _ = next_path_fur => {},
+                        m = next_path_fur => paths.extend(m),
                     }
                 }
 
-                Some((SystemTime::now(), (stream, watcher)))
+                Some(((SystemTime::now(), paths), (stream, watcher)))
             },
         ))
     }
@@ -176,33 +267,70 @@ impl Stctl {
         Ok(())
     }
 
-    async fn serve_once(&self) -> Result<ServeArtifact> {
-        use tokio::process::Command;
-
-        let http_listen_addr = format!("http://{}/", self.manifest.dev_server.listen);
-
+    /// Builds the frontend and the backend for the development server.
+    async fn build_server(&self) -> Result<ServeBuild> {
         let builder = Builder::new(self).await?.watch_build(true);
 
         let bar = ServeProgress::new();
 
-        let workspace_dir = self.paths.workspace_dir().await?;
+        builder
+            .run_hooks(&self.manifest.hooks.pre_build, "pre-build hook")
+            .await?;
+
         bar.step_build_frontend();
-        let frontend_build_dir = builder.build_frontend().await?;
+        let frontend_build_dir = builder.build_frontend().await?.to_owned();
 
         bar.step_build_backend();
-        let backend_build_dir = builder.backend_build_dir().await?;
-        let backend_build_path = builder.build_backend().await?;
+        let backend_build_dir = builder.backend_build_dir().await?.to_owned();
+        let backend_build_path = builder.build_backend(None).await?;
+
+        builder
+            .run_hooks(&self.manifest.hooks.post_build, "post-build hook")
+            .await?;
+
+        bar.hide();
+
+        Ok(ServeBuild {
+            frontend_build_dir,
+            backend_build_dir,
+            backend_build_path,
+        })
+    }
+
+    /// Starts the development server and waits until it is ready.
+    async fn start_server(&self, build: ServeBuild) -> Result<ServeArtifact> {
+        use tokio::process::Command;
+
+        let http_listen_addr = format!(
+            "http://{}{}",
+            self.manifest.dev_server.listen,
+            self.manifest.base_path()
+        );
+
+        let bar = ServeProgress::new();
+        bar.step_starting();
+
+        let workspace_dir = self.paths.workspace_dir().await?;
+
+        let frontend_dev_build_file = self.paths.frontend_dev_build_file().await?;
+        fs::write(
+            &frontend_dev_build_file,
+            build.frontend_build_dir.to_string_lossy().as_bytes(),
+        )
+        .await
+        .context("failed to write frontend build directory")?;
 
         let meta = StctlMetadata {
             listen_addr: self.manifest.dev_server.listen.to_string(),
-            frontend_dev_build_dir: frontend_build_dir.to_owned(),
+            frontend_dev_build_dir: build.frontend_build_dir.clone(),
+            frontend_dev_build_file: Some(frontend_dev_build_file),
+            build_status_file: Some(self.paths.build_status_file().await?),
+            base_path: self.manifest.base_path(),
         };
 
-        bar.step_starting();
-
         let envs = self.env_file.load(workspace_dir);
 
-        let server_proc = Command::new(&backend_build_path)
+        let mut server_proc = Command::new(&build.backend_build_path)
             .current_dir(workspace_dir)
             .envs(envs)
             .env(StctlMetadata::ENV_NAME, meta.to_json()?)
@@ -221,6 +349,10 @@ impl Stctl {
             .and_then(|m| m.error_for_status())
             .is_err()
         {
+            if let Some(m) = server_proc.try_wait()? {
+                bail!("development server exited with status {}", m);
+            }
+
             sleep(Duration::from_secs(1)).await;
         }
 
@@ -228,55 +360,274 @@ impl Stctl {
 
         Ok(ServeArtifact {
             child: server_proc,
-            frontend_artifact_dir: frontend_build_dir.to_owned(),
-            backend_artifact_dir: backend_build_dir.to_owned(),
+            frontend_artifact_dir: build.frontend_build_dir,
+            backend_artifact_dir: build.backend_build_dir,
         })
     }
 
+    /// Builds and starts the development server.
+    ///
+    /// The running server is only replaced after the new build succeeds.
+    async fn serve_once(&self, serving: &mut Option<Serving>) -> Result<()> {
+        let build = self.build_server().await?;
+
+        if let Some(m) = serving.take() {
+            m.stop().await?;
+        }
+
+        *serving = Some(Serving::App(self.start_server(build).await?));
+
+        Ok(())
+    }
+
+    /// Returns the artifacts that need to be rebuilt after files are changed.
+    async fn rebuild_for(&self, paths: &[PathBuf]) -> Rebuild {
+        let workspace_dir = match self.paths.workspace_dir().await {
+            Ok(m) => m,
+            Err(_) => return Rebuild::All,
+        };
+
+        match WorkspaceCrates::load(workspace_dir, &self.manifest).await {
+            Ok(m) => m.rebuild_for(paths),
+            Err(e) => {
+                tracing::debug!("failed to locate changed crates: {:?}", e);
+                Rebuild::All
+            }
+        }
+    }
+
+    /// Reloads the files read by build scripts, which are watched in addition to the globs.
+    async fn refresh_build_inputs(&self, filter: &WatchFilter) {
+        if let Err(e) = filter.refresh_build_inputs(&self.profile).await {
+            tracing::debug!("failed to read inputs of build scripts: {:?}", e);
+        }
+    }
+
+    /// Rebuilds the frontend and replaces the frontend served by a running server.
+    ///
+    /// Pages opened in browsers are refreshed by the server when the frontend is replaced.
+    async fn rebuild_frontend(&self, artifact: &mut ServeArtifact) -> Result<()> {
+        let builder = Builder::new(self).await?.watch_build(true);
+
+        let bar = ServeProgress::new();
+
+        builder
+            .run_hooks(&self.manifest.hooks.pre_build, "pre-build hook")
+            .await?;
+
+        bar.step_build_frontend();
+        let frontend_build_dir = builder.build_frontend().await?;
+
+        builder
+            .run_hooks(&self.manifest.hooks.post_build, "post-build hook")
+            .await?;
+
+        bar.hide();
+
+        fs::write(
+            self.paths.frontend_dev_build_file().await?,
+            frontend_build_dir.to_string_lossy().as_bytes(),
+        )
+        .await
+        .context("failed to write frontend build directory")?;
+
+        let stale_dir = std::mem::replace(
+            &mut artifact.frontend_artifact_dir,
+            frontend_build_dir.to_owned(),
+        );
+        fs::remove_dir_all(stale_dir)
+            .await
+            .context("failed to remove stale frontend artifact")?;
+
+        Ok(())
+    }
+
+    /// Rebuilds the artifacts of the development server.
+    async fn rebuild(&self, rebuild: Rebuild, serving: &mut Option<Serving>) -> Result<()> {
+        match (rebuild, serving.as_mut()) {
+            (Rebuild::Frontend, Some(Serving::App(m))) => self.rebuild_frontend(m).await,
+            _ => self.serve_once(serving).await,
+        }
+    }
+
+    /// Reports a failed build to pages opened in browsers.
+    ///
+    /// The error is displayed by the running server, or by an error server if the application is
+    /// not running.
+    async fn report_build_error(
+        &self,
+        error: &anyhow::Error,
+        serving: &mut Option<Serving>,
+    ) -> Result<()> {
+        let message = match error.downcast_ref::<BuildFailed>() {
+            Some(m) => format!("{error:#}\n\n{}", extract_errors(m.output())),
+            None => format!("{error:#}"),
+        };
+
+        self.set_build_status(&BuildStatus::Failed {
+            message: message.clone(),
+        })
+        .await?;
+
+        if let Some(Serving::App(_)) = serving {
+            return Ok(());
+        }
+
+        if let Some(m) = serving.take() {
+            m.stop().await?;
+        }
+
+        match ErrorServer::start(&self.manifest.dev_server.listen, &message).await {
+            Ok(m) => *serving = Some(Serving::Error(m)),
+            Err(e) => tracing::warn!("failed to start error server: {:?}", e),
+        }
+
+        Ok(())
+    }
+
+    /// Updates the build status displayed by the development server.
+    async fn set_build_status(&self, status: &BuildStatus) -> Result<()> {
+        let build_status_file = self.paths.build_status_file().await?;
+        let tmp_file = build_status_file.with_extension("tmp");
+
+        // The file is replaced at once, so the server never reads a partially written status.
+        fs::write(&tmp_file, status.to_json()?)
+            .await
+            .context("failed to write build status")?;
+        fs::rename(&tmp_file, &build_status_file)
+            .await
+            .context("failed to write build status")
+    }
+
+    /// Waits for files changed after a time.
+    ///
+    /// Returns `None` if the workspace is no longer watched.
+    async fn next_change<S>(mut changes: Pin<&mut S>, after: SystemTime) -> Option<Vec<PathBuf>>
+    where
+        S: Stream<Item = (SystemTime, Vec<PathBuf>)>,
+    {
+        loop {
+            let (change_time, paths) = changes.next().await?;
+
+            if change_time > after {
+                return Some(paths);
+            }
+        }
+    }
+
+    fn print_serve_ready(start_time: SystemTime, http_listen_addr: &str) -> Result<()> {
+        let time_taken_in_f64 =
+            f64::try_from(i32::try_from(start_time.elapsed()?.as_millis())?)? / 1000.0;
+
+        Term::stderr().clear_screen()?;
+
+        eprintln!(
+            "{}",
+            style(format!("Built in {time_taken_in_f64:.2}s!"))
+                .green()
+                .bold()
+        );
+        eprintln!("Stellation development server has started!");
+        eprintln!();
+        eprintln!();
+        eprintln!("    Listening at: {http_listen_addr}");
+        eprintln!();
+        eprintln!();
+        eprintln!(
+            "{} This build is not optimised and should not be used in production.",
+            style("Note:").yellow().bold()
+        );
+        eprintln!(
+            "To produce a production build, you can use `{}`.",
+            style("cargo make build").cyan().bold()
+        );
+
+        Ok(())
+    }
+
+    /// Rebuilds the development server, the build is cancelled if files are changed before it
+    /// completes.
+    ///
+    async fn rebuild_until_changed<S>(
+        &self,
+        rebuild: Rebuild,
+        serving: &mut Option<Serving>,
+        changes: Pin<&mut S>,
+        http_listen_addr: &str,
+    ) -> Result<BuildOutcome>
+    where
+        S: Stream<Item = (SystemTime, Vec<PathBuf>)>,
+    {
+        let start_time = SystemTime::now();
+        self.set_build_status(&BuildStatus::Building).await?;
+
+        let result = {
+            let mut build = pin!(self.rebuild(rebuild, serving).fuse());
+            let mut next_change = pin!(Self::next_change(changes, start_time).fuse());
+
+            futures::select! {
+                m = build => m,
+                m = next_change => {
+                    return Ok(m.map(BuildOutcome::Cancelled).unwrap_or(BuildOutcome::Stopped));
+                },
+            }
+        };
+
+        match result {
+            Ok(()) => {
+                self.set_build_status(&BuildStatus::Ready).await?;
+                Self::print_serve_ready(start_time, http_listen_addr)?;
+
+                Ok(BuildOutcome::Succeeded)
+            }
+            Err(e) => {
+                tracing::error!("failed to build development server: {:?}", e);
+                self.report_build_error(&e, serving).await?;
+
+                Ok(BuildOutcome::Failed)
+            }
+        }
+    }
+
     async fn run_serve(&self, cmd_args: &ServeCommand) -> Result<()> {
-        let changes = self.watch_changes().await?;
+        let watch_filter = Arc::new(WatchFilter::new(
+            self.paths.workspace_dir().await?,
+            &self.manifest,
+        )?);
+        self.refresh_build_inputs(&watch_filter).await;
+
+        let changes = self.watch_changes(watch_filter.clone()).await?;
         let mut changes = pin!(changes);
 
+        let http_listen_addr = format!(
+            "http://{}{}",
+            self.manifest.dev_server.listen,
+            self.manifest.base_path()
+        );
+
+        let mut serving = None;
         let mut first_run = true;
+        let mut pending = Some(Rebuild::All);
 
-        'outer: loop {
+        while let Some(rebuild) = pending.take() {
             let start_time = SystemTime::now();
-            let http_listen_addr = format!("http://{}/", self.manifest.dev_server.listen);
-
-            let artifact = match self.serve_once().await {
-                Ok(artifact) => {
-                    let time_taken_in_f64 =
-                        f64::try_from(i32::try_from(start_time.elapsed()?.as_millis())?)? / 1000.0;
-
-                    Term::stderr().clear_screen()?;
-
-                    eprintln!(
-                        "{}",
-                        style(format!("Built in {time_taken_in_f64:.2}s!"))
-                            .green()
-                            .bold()
-                    );
-                    eprintln!("Stellation development server has started!");
-                    eprintln!();
-                    eprintln!();
-                    eprintln!("    Listening at: {http_listen_addr}");
-                    eprintln!();
-                    eprintln!();
-                    eprintln!(
-                        "{} This build is not optimised and should not be used in production.",
-                        style("Note:").yellow().bold()
-                    );
-                    eprintln!(
-                        "To produce a production build, you can use `{}`.",
-                        style("cargo make build").cyan().bold()
-                    );
-
-                    Some(artifact)
-                }
-                Err(e) => {
-                    tracing::error!("failed to build development server: {:?}", e);
+
+            // The artifacts of a failed build are rebuilt with the next change.
+            let failed = match self
+                .rebuild_until_changed(rebuild, &mut serving, changes.as_mut(), &http_listen_addr)
+                .await?
+            {
+                BuildOutcome::Succeeded => {
+                    self.refresh_build_inputs(&watch_filter).await;
                     None
                 }
+                BuildOutcome::Failed => Some(rebuild),
+                // The build is started again with the newer changes.
+                BuildOutcome::Cancelled(paths) => {
+                    pending = Some(rebuild.max(self.rebuild_for(&paths).await));
+                    continue;
+                }
+                BuildOutcome::Stopped => break,
             };
 
             if cmd_args.open && first_run {
@@ -285,32 +636,13 @@ impl Stctl {
 
             first_run = false;
 
-            'inner: loop {
-                match changes.next().await {
-                    Some(change_time) => {
-                        if change_time > start_time {
-                            break 'inner;
-                        }
-                    }
-                    None => break 'outer,
-                }
+            if let Some(paths) = Self::next_change(changes.as_mut(), start_time).await {
+                pending = Some(self.rebuild_for(&paths).await).max(failed);
             }
+        }
 
-            if let Some(m) = artifact {
-                let ServeArtifact {
-                    mut child,
-                    frontend_artifact_dir,
-                    backend_artifact_dir,
-                } = m;
-
-                child.kill().await.context("failed to stop server")?;
-                fs::remove_dir_all(frontend_artifact_dir)
-                    .await
-                    .context("failed to remove stale frontend artifact")?;
-                fs::remove_dir_all(backend_artifact_dir)
-                    .await
-                    .context("failed to remove stale backend artifact")?;
-            }
+        if let Some(m) = serving {
+            m.stop().await?;
         }
 
         Ok(())
@@ -330,16 +662,31 @@ impl Stctl {
 
         let build_dir = self.paths.build_dir().await?;
 
-        let builder = Builder::new(self)
-            .await?
-            .backend_target(cmd_args.backend_target.clone());
+        let builder = Builder::new(self).await?;
+        let backend_targets = self.backend_targets(cmd_args);
+
+        builder
+            .run_hooks(&self.manifest.hooks.pre_build, "pre-build hook")
+            .await?;
 
         let frontend_artifact_dir = builder.build_frontend().await?;
         let backend_artifact_dir = builder.backend_build_dir().await?;
-        let backend_artifact_path = builder.build_backend().await?;
+
+        let mut backend_artifact_paths = Vec::new();
+        for target in backend_targets.iter() {
+            if let Some(m) = target {
+                eprintln!(
+                    "{}",
+                    style(format!("Building backend for {m}...")).cyan().bold()
+                );
+            }
+
+            backend_artifact_paths.push(builder.build_backend(target.as_deref()).await?);
+        }
 
         let backend_build_dir = build_dir.join("backend");
         let frontend_build_dir = build_dir.join("frontend");
+        let bundle_dir = build_dir.join("bundle");
 
         if backend_build_dir.exists() {
             fs::remove_dir_all(&backend_build_dir)
@@ -361,16 +708,11 @@ impl Stctl {
             .await
             .context("failed to create frontend build directory.")?;
 
-        fs::copy(
-            &backend_artifact_path,
-            backend_build_dir.join(
-                backend_artifact_path
-                    .file_name()
-                    .context("failed to find backend binary name")?,
-            ),
-        )
-        .await
-        .context("failed to copy backend")?;
+        if cmd_args.bundle && bundle_dir.exists() {
+            fs::remove_dir_all(&bundle_dir)
+                .await
+                .context("failed to clean past bundles.")?;
+        }
 
         {
             let frontend_artifact_dir = frontend_artifact_dir.to_owned();
@@ -390,6 +732,21 @@ impl Stctl {
         .context("failed to copy frontend")?
         .context("failed to copy frontend")?;
 
+        for (target, backend_artifact_path) in backend_targets.iter().zip(backend_artifact_paths) {
+            self.copy_backend(
+                &builder,
+                target.as_deref(),
+                &backend_artifact_path,
+                frontend_artifact_dir,
+                cmd_args.bundle,
+            )
+            .await?;
+        }
+
+        builder
+            .run_hooks(&self.manifest.hooks.post_build, "post-build hook")
+            .await?;
+
         fs::remove_dir_all(backend_artifact_dir)
             .await
             .context("failed to remove backend temporary artifacts.")?;
@@ -410,6 +767,189 @@ impl Stctl {
         Ok(())
     }
 
+    /// Copies the backend binary of a target to the build directory and writes its bundle.
+    ///
+    /// Binaries of other targets are placed in directories named after the target.
+    async fn copy_backend(
+        &self,
+        builder: &Builder,
+        target: Option<&str>,
+        backend_artifact_path: &Path,
+        frontend_artifact_dir: &Path,
+        bundle: bool,
+    ) -> Result<()> {
+        let build_dir = self.paths.build_dir().await?;
+        let (backend_target_dir, bundle_dir) = match target {
+            Some(m) => (
+                build_dir.join("backend").join(m),
+                build_dir.join("bundle").join(m),
+            ),
+            None => (build_dir.join("backend"), build_dir.join("bundle")),
+        };
+
+        fs::create_dir_all(&backend_target_dir)
+            .await
+            .context("failed to create backend build directory.")?;
+
+        let backend_bin_path = backend_target_dir.join(
+            backend_artifact_path
+                .file_name()
+                .context("failed to find backend binary name")?,
+        );
+        fs::copy(backend_artifact_path, &backend_bin_path)
+            .await
+            .context("failed to copy backend")?;
+
+        if bundle {
+            Bundle {
+                build_id: builder.build_id(),
+                profile: self.profile.name(),
+                target,
+                base_path: &self.manifest.base_path(),
+                bin_path: &backend_bin_path,
+                frontend_dir: frontend_artifact_dir,
+            }
+            .write_to(self.paths.workspace_dir().await?, &bundle_dir)
+            .await
+            .context("failed to write bundle")?;
+        }
+
+        Ok(())
+    }
+
+    /// Returns the backend targets of a build in order, `None` for the native target.
+    ///
+    /// Targets passed with `--backend-target` replace the targets of the build profile.
+    fn backend_targets(&self, cmd_args: &BuildCommand) -> Vec<Option<String>> {
+        let targets = match cmd_args.backend_target.is_empty() {
+            false => cmd_args.backend_target.clone(),
+            true => self
+                .manifest
+                .backend_profile(&self.profile)
+                .map(|m| m.targets.clone())
+                .unwrap_or_default(),
+        };
+
+        if targets.is_empty() {
+            return vec![None];
+        }
+
+        let mut backend_targets = Vec::new();
+        for m in targets {
+            let target = Some(m);
+            if !backend_targets.contains(&target) {
+                backend_targets.push(target);
+            }
+        }
+
+        backend_targets
+    }
+
+    /// Creates a cargo command for the workspace with the env profile loaded.
+    async fn cargo_command(&self, subcommand: &str) -> Result<tokio::process::Command> {
+        let workspace_dir = self.paths.workspace_dir().await?;
+
+        let mut proc = tokio::process::Command::new("cargo");
+        proc.arg(subcommand)
+            .current_dir(workspace_dir)
+            .envs(self.env_file.load(workspace_dir))
+            .stdin(Stdio::null())
+            .stdout(Stdio::inherit())
+            .stderr(Stdio::inherit())
+            .kill_on_drop(true);
+
+        Ok(proc)
+    }
+
+    /// Selects the backend crates and activates the backend features of the build profile.
+    fn backend_args(&self, proc: &mut tokio::process::Command, crates: &CheckCrates) {
+        for m in crates.backend.iter() {
+            proc.arg("-p").arg(m);
+        }
+
+        if let Some(m) = self.manifest.backend_profile(&self.profile) {
+            // Features are qualified by the crate as multiple crates are selected.
+            for feature in m.features.iter() {
+                proc.arg(format!("--features={}/{feature}", crates.backend_crate));
+            }
+
+            // Other selected crates are dependencies of the backend crate, so they keep the
+            // features enabled by their dependents, which are the same as `stctl build`.
+            if m.no_default_features {
+                proc.arg("--no-default-features");
+            }
+        }
+    }
+
+    async fn run_check(&self, cmd_args: &CheckCommand) -> Result<()> {
+        let workspace_dir = self.paths.workspace_dir().await?;
+        let crates = CheckCrates::load(workspace_dir, &self.manifest).await?;
+        let subcommand = if cmd_args.clippy { "clippy" } else { "check" };
+
+        let mut backend = self.cargo_command(subcommand).await?;
+        backend.arg("--all-targets");
+        self.backend_args(&mut backend, &crates);
+
+        let mut frontend = self.cargo_command(subcommand).await?;
+        frontend.arg("--target=wasm32-unknown-unknown");
+        for m in crates.frontend.iter() {
+            frontend.arg("-p").arg(m);
+        }
+
+        if !cmd_args.args.is_empty() {
+            for proc in [&mut backend, &mut frontend] {
+                proc.arg("--").args(&cmd_args.args);
+            }
+        }
+
+        Steps::new()
+            .with_step(format!("{subcommand} of backend"), backend)
+            .with_step(format!("{subcommand} of frontend (wasm32)"), frontend)
+            .run()
+            .await
+    }
+
+    async fn run_test(&self, cmd_args: &TestCommand) -> Result<()> {
+        let workspace_dir = self.paths.workspace_dir().await?;
+        let crates = CheckCrates::load(workspace_dir, &self.manifest).await?;
+
+        let mut backend = self.cargo_command("test").await?;
+        self.backend_args(&mut backend, &crates);
+        backend.arg("--").args(&cmd_args.args);
+
+        let mut steps = Steps::new().with_step("tests of backend", backend);
+
+        if cmd_args.no_wasm {
+            return steps.run().await;
+        }
+
+        if crates.wasm_tests.is_empty() {
+            eprintln!(
+                "{}",
+                style("No frontend crates depend on wasm-bindgen-test, skipping wasm tests.").dim()
+            );
+        } else {
+            let mut frontend = self.cargo_command("test").await?;
+            frontend.arg("--target=wasm32-unknown-unknown");
+            for m in crates.wasm_tests.iter() {
+                frontend.arg("-p").arg(m);
+            }
+
+            // Tests configured to run in browser are run by the runner with a headless browser.
+            if std::env::var_os("CARGO_TARGET_WASM32_UNKNOWN_UNKNOWN_RUNNER").is_none() {
+                frontend.env(
+                    "CARGO_TARGET_WASM32_UNKNOWN_UNKNOWN_RUNNER",
+                    "wasm-bindgen-test-runner",
+                );
+            }
+            frontend.arg("--").args(&cmd_args.args);
+
+            steps = steps.with_step("tests of frontend (wasm32)", frontend);
+        }
+
+        steps.run().await
+    }
+
     async fn run_clean(&self) -> Result<()> {
         use tokio::process::Command;
 
@@ -464,9 +1004,19 @@ impl Stctl {
             CliCommand::Build(ref m) => {
                 self.run_build(m).await?;
             }
+            CliCommand::Check(ref m) => {
+                self.run_check(m).await?;
+            }
+            CliCommand::Test(ref m) => {
+                self.run_test(m).await?;
+            }
             CliCommand::Clean => {
                 self.run_clean().await?;
             }
+            // Projects are created before the manifest is loaded.
+            CliCommand::New(_) | CliCommand::Init(_) => {
+                unreachable!("projects are created without stctl")
+            }
         }
 
         Ok(())
@@ -488,7 +1038,12 @@ pub async fn main() -> Result<()> {
         .init();
 
     let cli = Cli::parse();
-    Stctl::new(cli).await?.run().await?;
+
+    match cli.command {
+        CliCommand::New(ref m) => scaffold::run_new(m).await?,
+        CliCommand::Init(ref m) => scaffold::run_init(m).await?,
+        _ => Stctl::new(cli).await?.run().await?,
+    }
 
     Ok(())
 }
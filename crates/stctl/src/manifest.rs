@@ -1,14 +1,334 @@
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, ensure, Result};
 use serde::{Deserialize, Serialize};
+use stellation_core::base_path;
+
+use crate::profile::Profile;
+use crate::workspace::load_metadata;
 
+/// The files watched by the development server.
+///
+/// Globs are matched against paths relative to the directory of `stellation.toml` and are added to
+/// the default globs.
 #[derive(Debug, Serialize, Deserialize)]
-#[serde(rename_all = "kebab-case")]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub(crate) struct Watch {
+    /// Files that trigger a rebuild when changed.
+    #[serde(default)]
+    pub include: Vec<String>,
+    /// Files that never trigger a rebuild, even if they are included.
+    #[serde(default)]
+    pub exclude: Vec<String>,
+    /// Whether files ignored by `.gitignore` are excluded.
+    #[serde(default = "default_true")]
+    pub gitignore: bool,
+}
+
+impl Default for Watch {
+    fn default() -> Self {
+        Self {
+            include: Vec::new(),
+            exclude: Vec::new(),
+            gitignore: true,
+        }
+    }
+}
+
+fn default_true() -> bool {
+    true
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
 pub(crate) struct DevServer {
     pub listen: String,
     pub bin_name: String,
+    #[serde(default)]
+    pub watch: Watch,
 }
 
+/// The frontend built by trunk.
 #[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub(crate) struct Frontend {
+    /// The name of the frontend crate.
+    ///
+    /// Trunk selects the crate from the index, this is used by commands that build the crate with
+    /// cargo.
+    #[serde(default, rename = "crate")]
+    pub crate_name: Option<String>,
+    /// The path of the index, relative to the directory of `stellation.toml`.
+    #[serde(default = "default_index")]
+    pub index: PathBuf,
+    /// Arguments appended to `trunk build`.
+    #[serde(default)]
+    pub trunk_args: Vec<String>,
+}
+
+impl Default for Frontend {
+    fn default() -> Self {
+        Self {
+            crate_name: None,
+            index: default_index(),
+            trunk_args: Vec::new(),
+        }
+    }
+}
+
+fn default_index() -> PathBuf {
+    "index.html".into()
+}
+
+/// The settings of the backend for a build profile.
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub(crate) struct BackendProfile {
+    /// Features of the backend crate to activate.
+    #[serde(default)]
+    pub features: Vec<String>,
+    /// Whether the default features of the backend crate are deactivated.
+    #[serde(default)]
+    pub no_default_features: bool,
+    /// The build targets of the backend binary, the native target is used if empty.
+    #[serde(default)]
+    pub targets: Vec<String>,
+}
+
+/// The tool that builds the backend for a target.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "kebab-case")]
+pub(crate) enum TargetBuilder {
+    /// `cargo build`, the toolchain and linker of the target need to be installed.
+    #[default]
+    Cargo,
+    /// `cross build`, builds in a container provided by cross.
+    Cross,
+    /// `cargo zigbuild`, links with zig.
+    ///
+    /// The target may have a glibc version suffix, e.g.: `x86_64-unknown-linux-gnu.2.17`.
+    Zigbuild,
+}
+
+impl TargetBuilder {
+    /// Returns the name of the tool.
+    pub fn tool(&self) -> &'static str {
+        match self {
+            Self::Cargo => "cargo",
+            Self::Cross => "cross",
+            Self::Zigbuild => "cargo-zigbuild",
+        }
+    }
+}
+
+/// The settings of a build target of the backend.
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub(crate) struct BackendTarget {
+    /// The tool that builds the target.
+    #[serde(default)]
+    pub builder: TargetBuilder,
+    /// The linker of the target, only used by cargo.
+    #[serde(default)]
+    pub linker: Option<String>,
+}
+
+/// The backend built by cargo.
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub(crate) struct Backend {
+    /// Settings for each build profile, keyed by the name of the profile.
+    #[serde(default)]
+    pub profiles: BTreeMap<String, BackendProfile>,
+    /// Settings for each build target, keyed by the target triple.
+    #[serde(default)]
+    pub targets: BTreeMap<String, BackendTarget>,
+}
+
+/// Commands that are run around builds.
+///
+/// Commands are run by the shell in the directory of `stellation.toml`.
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub(crate) struct Hooks {
+    /// Commands run before the frontend and backend are built.
+    #[serde(default)]
+    pub pre_build: Vec<String>,
+    /// Commands run after the frontend and backend are built.
+    #[serde(default)]
+    pub post_build: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
 pub(crate) struct Manifest {
+    #[serde(default)]
+    pub base_path: Option<String>,
+    /// The env profiles used if `--env` is not specified, keyed by the name of the build profile.
+    #[serde(default)]
+    pub env_profiles: BTreeMap<String, String>,
+    /// The directory of `stctl build` artifacts, relative to the directory of `stellation.toml`.
+    #[serde(default)]
+    pub output_dir: Option<PathBuf>,
     pub dev_server: DevServer,
+    #[serde(default)]
+    pub frontend: Frontend,
+    #[serde(default)]
+    pub backend: Backend,
+    #[serde(default)]
+    pub hooks: Hooks,
+}
+
+impl Manifest {
+    /// Returns the path that the application is mounted at, starting and ending with `/`.
+    pub fn base_path(&self) -> String {
+        base_path::normalize(self.base_path.as_deref().unwrap_or_default())
+    }
+
+    /// Returns the path of the frontend index.
+    pub fn index_path(&self, workspace_dir: &Path) -> PathBuf {
+        workspace_dir.join(&self.frontend.index)
+    }
+
+    /// Returns the directory of `stctl build` artifacts.
+    pub fn output_dir(&self, workspace_dir: &Path) -> PathBuf {
+        workspace_dir.join(self.output_dir.as_deref().unwrap_or(Path::new("build")))
+    }
+
+    /// Returns the env profile used by a build profile if `--env` is not specified.
+    ///
+    /// Defaults to the name of the build profile.
+    pub fn env_profile<'a>(&'a self, profile: &'a Profile) -> &'a str {
+        self.env_profiles
+            .get(profile.name())
+            .map(String::as_str)
+            .unwrap_or(profile.name())
+    }
+
+    /// Returns the backend settings of a build profile.
+    pub fn backend_profile(&self, profile: &Profile) -> Option<&BackendProfile> {
+        self.backend.profiles.get(profile.name())
+    }
+
+    /// Returns the settings of a backend target.
+    pub fn backend_target(&self, target: &str) -> Option<&BackendTarget> {
+        self.backend.targets.get(target)
+    }
+
+    /// Checks that the manifest can be used to build the workspace.
+    ///
+    /// Errors name the key of the invalid value.
+    pub async fn validate(&self, workspace_dir: &Path) -> Result<()> {
+        let listen_port = self
+            .dev_server
+            .listen
+            .rsplit_once(':')
+            .and_then(|(_, m)| m.parse::<u16>().ok());
+        ensure!(
+            listen_port.is_some(),
+            "`dev-server.listen` must be an address with a port, e.g.: localhost:5000, found `{}`",
+            self.dev_server.listen
+        );
+        ensure!(
+            !self.dev_server.bin_name.is_empty(),
+            "`dev-server.bin-name` must not be empty"
+        );
+
+        let index_path = self.index_path(workspace_dir);
+        ensure!(
+            index_path.is_file(),
+            "`frontend.index` must be a file, {} does not exist",
+            index_path.display()
+        );
+
+        for (name, env_profile) in self.env_profiles.iter() {
+            ensure!(
+                name == "debug" || name == "release",
+                "unknown profile `env-profiles.{name}`, profiles must be debug or release"
+            );
+            ensure!(
+                !env_profile.is_empty(),
+                "`env-profiles.{name}` must not be empty"
+            );
+        }
+
+        self.validate_output_dir(workspace_dir)?;
+
+        for (name, profile) in self.backend.profiles.iter() {
+            ensure!(
+                name == "debug" || name == "release",
+                "unknown profile `backend.profiles.{name}`, profiles must be debug or release"
+            );
+            ensure!(
+                profile.features.iter().all(|m| !m.trim().is_empty()),
+                "`backend.profiles.{name}.features` must not contain empty features"
+            );
+            ensure!(
+                profile.targets.iter().all(|m| !m.trim().is_empty()),
+                "`backend.profiles.{name}.targets` must not contain empty targets"
+            );
+        }
+
+        for (name, target) in self.backend.targets.iter() {
+            ensure!(
+                !name.contains('.') || target.builder == TargetBuilder::Zigbuild,
+                "`backend.targets.{name}` has a glibc version, which is only supported by zigbuild"
+            );
+
+            if let Some(ref m) = target.linker {
+                ensure!(
+                    !m.trim().is_empty(),
+                    "`backend.targets.{name}.linker` must not be empty"
+                );
+                ensure!(
+                    target.builder == TargetBuilder::Cargo,
+                    "`backend.targets.{name}.linker` is only supported by the cargo builder"
+                );
+            }
+        }
+
+        for (key, commands) in [
+            ("pre-build", &self.hooks.pre_build),
+            ("post-build", &self.hooks.post_build),
+        ] {
+            ensure!(
+                commands.iter().all(|m| !m.trim().is_empty()),
+                "`hooks.{key}` must not contain empty commands"
+            );
+        }
+
+        if let Some(ref m) = self.frontend.crate_name {
+            let meta = load_metadata(workspace_dir).await?;
+            ensure!(
+                meta.workspace_packages().iter().any(|pkg| &pkg.name == m),
+                "`frontend.crate` must be a crate of the workspace, {m} is not found"
+            );
+        }
+
+        Ok(())
+    }
+
+    /// The output directory is removed by `stctl clean`, so it cannot contain sources.
+    pub fn validate_output_dir(&self, workspace_dir: &Path) -> Result<()> {
+        let output_dir = match self.output_dir {
+            Some(ref m) => m,
+            None => return Ok(()),
+        };
+
+        let is_nested = output_dir.components().next().is_some()
+            && output_dir
+                .components()
+                .all(|m| matches!(m, std::path::Component::Normal(_)));
+
+        if !is_nested || output_dir.starts_with(".stellation") {
+            bail!(
+                "`output-dir` must be a directory inside {} other than .stellation, found `{}`",
+                workspace_dir.display(),
+                output_dir.display()
+            );
+        }
+
+        Ok(())
+    }
 }
@@ -7,6 +7,7 @@ use tokio::sync::OnceCell;
 #[derive(Debug, Clone)]
 pub(crate) struct Paths {
     workspace_dir: PathBuf,
+    output_dir: PathBuf,
     build_dir: OnceCell<PathBuf>,
     data_dir: OnceCell<PathBuf>,
     frontend_data_dir: OnceCell<PathBuf>,
@@ -17,6 +18,7 @@ pub(crate) struct Paths {
 
     frontend_logs_dir: OnceCell<PathBuf>,
     backend_logs_dir: OnceCell<PathBuf>,
+    hooks_logs_dir: OnceCell<PathBuf>,
 }
 
 impl Paths {
@@ -34,6 +36,7 @@ impl Paths {
             .map(|m| m.to_owned())?;
 
         Ok(Self {
+            output_dir: workspace_dir.join("build"),
             workspace_dir,
             build_dir: OnceCell::new(),
             data_dir: OnceCell::new(),
@@ -43,6 +46,7 @@ impl Paths {
             backend_builds_dir: OnceCell::new(),
             frontend_logs_dir: OnceCell::new(),
             backend_logs_dir: OnceCell::new(),
+            hooks_logs_dir: OnceCell::new(),
         })
     }
 
@@ -60,9 +64,20 @@ impl Paths {
         Ok(&self.workspace_dir)
     }
 
+    /// Sets the path of the build directory.
+    pub fn with_output_dir<P>(mut self, output_dir: P) -> Self
+    where
+        P: Into<PathBuf>,
+    {
+        self.output_dir = output_dir.into();
+
+        self
+    }
+
     /// Creates and returns the path of the build directory.
     ///
-    /// This is the `build` directory in the same parent directory as `stellation.toml`.
+    /// This is the `output-dir` of `stellation.toml`, which defaults to the `build` directory in
+    /// the same parent directory as `stellation.toml`.
     ///
     /// # Note
     ///
@@ -70,7 +85,7 @@ impl Paths {
     pub async fn build_dir(&self) -> Result<&Path> {
         self.build_dir
             .get_or_try_init(|| async {
-                let dir = self.workspace_dir().await?.join("build");
+                let dir = self.output_dir.clone();
 
                 fs::create_dir_all(&dir)
                     .await
@@ -176,6 +191,24 @@ impl Paths {
             .map(|m| m.as_ref())
     }
 
+    /// Returns the path of the file that contains the directory of the current frontend
+    /// development build.
+    ///
+    /// This is the `.stellation/frontend/dev-build` file in the same parent directory as
+    /// `stellation.toml`. The development server serves the frontend from the directory in this
+    /// file, so it can be replaced without restarting the server.
+    pub async fn frontend_dev_build_file(&self) -> Result<PathBuf> {
+        Ok(self.frontend_data_dir().await?.join("dev-build"))
+    }
+
+    /// Returns the path of the file that contains the status of the latest development build.
+    ///
+    /// This is the `.stellation/build-status` file in the same parent directory as
+    /// `stellation.toml`.
+    pub async fn build_status_file(&self) -> Result<PathBuf> {
+        Ok(self.data_dir().await?.join("build-status"))
+    }
+
     /// Creates and returns the path of the frontend logs directory.
     ///
     /// This is the `.stellation/frontend/logs` directory in the same parent directory as
@@ -213,4 +246,23 @@ impl Paths {
             .await
             .map(|m| m.as_ref())
     }
+
+    /// Creates and returns the path of the hooks logs directory.
+    ///
+    /// This is the `.stellation/hooks/logs` directory in the same parent directory as
+    /// `stellation.toml`.
+    pub async fn hooks_logs_dir(&self) -> Result<&Path> {
+        self.hooks_logs_dir
+            .get_or_try_init(|| async {
+                let dir = self.data_dir().await?.join("hooks").join("logs");
+
+                fs::create_dir_all(&dir)
+                    .await
+                    .context("failed to create logs directory for hooks.")?;
+
+                Ok(dir)
+            })
+            .await
+            .map(|m| m.as_ref())
+    }
 }
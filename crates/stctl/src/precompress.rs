@@ -0,0 +1,65 @@
+use std::fs;
+use std::io::Write;
+use std::path::Path;
+
+use anyhow::{Context, Result};
+
+/// Extensions of files that benefit from compression.
+const COMPRESSIBLE_EXTENSIONS: &[&str] = &["wasm", "js", "mjs", "css", "svg", "json", "txt", "map"];
+
+/// Files smaller than this are not worth compressing.
+const MIN_COMPRESS_SIZE: u64 = 1024;
+
+fn write_gzip(data: &[u8], target: &Path) -> Result<()> {
+    let mut w = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::best());
+    w.write_all(data)?;
+
+    fs::write(target, w.finish()?).with_context(|| format!("failed to write {}", target.display()))
+}
+
+fn write_brotli(data: &[u8], target: &Path) -> Result<()> {
+    let mut output = Vec::new();
+    {
+        let mut w = brotli::CompressorWriter::new(&mut output, 4096, 11, 22);
+        w.write_all(data)?;
+    }
+
+    fs::write(target, output).with_context(|| format!("failed to write {}", target.display()))
+}
+
+/// Writes `.br` and `.gz` siblings for compressible files in a directory.
+///
+/// The backend serves these files to clients that accept the encoding, so assets do not have to be
+/// compressed at runtime.
+pub(crate) fn precompress_dir(dir: &Path) -> Result<()> {
+    let entries = fs::read_dir(dir).with_context(|| format!("failed to read {}", dir.display()))?;
+
+    for entry in entries {
+        let entry = entry?;
+        let path = entry.path();
+        let meta = entry.metadata()?;
+
+        if meta.is_dir() {
+            precompress_dir(&path)?;
+            continue;
+        }
+
+        let is_compressible = path
+            .extension()
+            .and_then(|m| m.to_str())
+            .map(|m| COMPRESSIBLE_EXTENSIONS.contains(&m))
+            .unwrap_or(false);
+
+        if !is_compressible || meta.len() < MIN_COMPRESS_SIZE {
+            continue;
+        }
+
+        let data = fs::read(&path).with_context(|| format!("failed to read {}", path.display()))?;
+        let path_str = path.as_os_str().to_string_lossy();
+
+        write_gzip(&data, Path::new(&format!("{path_str}.gz")))?;
+        write_brotli(&data, Path::new(&format!("{path_str}.br")))?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,398 @@
+//! Creates projects from the template embedded in stctl.
+
+use std::collections::HashSet;
+use std::env;
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, ensure, Context, Result};
+use console::style;
+use tokio::fs;
+
+use crate::cli::{BackendFlavour, InitCommand, NewCommand, ScaffoldOptions};
+
+/// A file of the template.
+#[derive(Debug)]
+struct TemplateFile {
+    /// The path of the file in the project.
+    path: &'static str,
+    content: &'static str,
+    /// The flag that the file is created with.
+    flag: Option<&'static str>,
+}
+
+macro_rules! template_file {
+    ($path:literal) => {
+        template_file!($path, None)
+    };
+    ($path:literal, $flag:expr) => {
+        TemplateFile {
+            path: $path,
+            content: include_str!(concat!("../template/", $path, ".tmpl")),
+            flag: $flag,
+        }
+    };
+}
+
+const TEMPLATE_FILES: &[TemplateFile] = &[
+    template_file!(".gitignore"),
+    template_file!("Cargo.toml"),
+    template_file!("Makefile.toml"),
+    template_file!("README.md"),
+    template_file!("index.html"),
+    template_file!("rustfmt.toml"),
+    template_file!("stellation.toml"),
+    template_file!("styles/main.css", Some("!stylist")),
+    template_file!("crates/api/Cargo.toml", Some("bridge")),
+    template_file!("crates/api/src/lib.rs", Some("bridge")),
+    template_file!("crates/api/src/resolvers.rs", Some("bridge")),
+    template_file!("crates/api/src/routines.rs", Some("bridge")),
+    template_file!("crates/client/Cargo.toml"),
+    template_file!("crates/client/src/app.rs"),
+    template_file!("crates/client/src/main.rs"),
+    template_file!("crates/server/Cargo.toml"),
+    template_file!("crates/server/src/app.rs"),
+    template_file!("crates/server/src/bridge.rs", Some("bridge")),
+    template_file!("crates/server/src/main.rs"),
+    template_file!("crates/stctl/Cargo.toml"),
+    template_file!("crates/stctl/src/bin/stctl.rs"),
+    template_file!("crates/view/Cargo.toml"),
+    template_file!("crates/view/src/lib.rs"),
+    template_file!("crates/view/src/pages/greeting.rs", Some("bridge")),
+    template_file!("crates/view/src/pages/mod.rs", Some("bridge")),
+    template_file!("crates/view/src/pages/server_time.rs", Some("bridge")),
+];
+
+/// Renders the template.
+///
+/// Templates support the following syntax:
+///
+/// - `{{project-name}}` and `{{crate_name}}`: the name of the project in kebab and snake case.
+/// - `{{source:<crate>}}`: the source of a stellation crate in a dependency table.
+/// - `{{#if <flag>}}`, `{{#if !<flag>}}`, `{{#else}}` and `{{/if}}` on their own lines: includes
+///   the lines between them if the flag is set.
+#[derive(Debug)]
+struct Template {
+    project_name: String,
+    flags: HashSet<&'static str>,
+    stellation_path: Option<PathBuf>,
+}
+
+impl Template {
+    fn new(project_name: &str, options: &ScaffoldOptions) -> Result<Self> {
+        let is_valid_name = project_name
+            .chars()
+            .next()
+            .map(|m| m.is_ascii_alphabetic())
+            .unwrap_or(false)
+            && project_name
+                .chars()
+                .all(|m| m.is_ascii_alphanumeric() || m == '-' || m == '_');
+        ensure!(
+            is_valid_name,
+            "`{project_name}` is not a valid project name, names start with a letter and can only \
+             contain letters, numbers, `-` and `_`"
+        );
+
+        let mut flags = HashSet::new();
+        flags.insert(match options.backend {
+            BackendFlavour::Tower => "tower",
+            BackendFlavour::Warp => "warp",
+        });
+        if !options.no_stylist {
+            flags.insert("stylist");
+        }
+        if !options.no_bridge {
+            flags.insert("bridge");
+        }
+
+        let stellation_path = options
+            .stellation_path
+            .as_ref()
+            .map(|m| {
+                m.canonicalize()
+                    .with_context(|| format!("failed to find stellation at {}", m.display()))
+            })
+            .transpose()?;
+
+        Ok(Self {
+            project_name: project_name.to_string(),
+            flags,
+            stellation_path,
+        })
+    }
+
+    fn is_set(&self, flag: &str) -> bool {
+        match flag.strip_prefix('!') {
+            Some(m) => !self.flags.contains(m),
+            None => self.flags.contains(flag),
+        }
+    }
+
+    fn source(&self, crate_name: &str) -> String {
+        match self.stellation_path {
+            // Literal strings do not need to escape paths on Windows.
+            Some(ref m) => format!("path = '{}'", m.join("crates").join(crate_name).display()),
+            None => format!("version = \"{}\"", env!("CARGO_PKG_VERSION")),
+        }
+    }
+
+    fn render_line(&self, line: &str) -> Result<String> {
+        let mut rendered = String::new();
+        let mut rest = line;
+
+        while let Some(start) = rest.find("{{") {
+            let len = rest[start..]
+                .find("}}")
+                .with_context(|| format!("unclosed placeholder: {line}"))?;
+            let key = &rest[start + 2..start + len];
+
+            rendered.push_str(&rest[..start]);
+            match key {
+                "project-name" => rendered.push_str(&self.project_name),
+                "crate_name" => rendered.push_str(&self.project_name.replace('-', "_")),
+                m => match m.strip_prefix("source:") {
+                    Some(m) => rendered.push_str(&self.source(m)),
+                    None => bail!("unknown placeholder: {m}"),
+                },
+            }
+
+            rest = &rest[start + len + 2..];
+        }
+        rendered.push_str(rest);
+
+        Ok(rendered)
+    }
+
+    fn render(&self, content: &str) -> Result<String> {
+        // Whether lines are included at each level of conditions.
+        let mut conditions = vec![true];
+        let mut rendered = String::new();
+
+        for line in content.lines() {
+            let is_included = *conditions.last().expect("conditions are not balanced");
+
+            match line.trim() {
+                m if m.starts_with("{{#if ") && m.ends_with("}}") => {
+                    let flag = m["{{#if ".len()..m.len() - 2].trim();
+                    conditions.push(is_included && self.is_set(flag));
+                }
+                "{{#else}}" => {
+                    ensure!(
+                        conditions.len() > 1,
+                        "`{{{{#else}}}}` without `{{{{#if}}}}`"
+                    );
+                    let is_parent_included = conditions[conditions.len() - 2];
+                    let condition = conditions.last_mut().expect("conditions are not balanced");
+                    *condition = is_parent_included && !*condition;
+                }
+                "{{/if}}" => {
+                    ensure!(conditions.len() > 1, "`{{{{/if}}}}` without `{{{{#if}}}}`");
+                    conditions.pop();
+                }
+                _ if is_included => {
+                    rendered.push_str(&self.render_line(line)?);
+                    rendered.push('\n');
+                }
+                _ => {}
+            }
+        }
+
+        ensure!(conditions.len() == 1, "`{{{{#if}}}}` without `{{{{/if}}}}`");
+
+        Ok(rendered)
+    }
+
+    /// Returns the files created with the flags of this template.
+    fn files(&self) -> impl Iterator<Item = &'static TemplateFile> + '_ {
+        TEMPLATE_FILES
+            .iter()
+            .filter(|m| m.flag.map(|m| self.is_set(m)).unwrap_or(true))
+    }
+
+    /// Writes the files of the project to a directory.
+    ///
+    /// All files are rendered before any of them is written.
+    async fn write_to(&self, project_dir: &Path) -> Result<()> {
+        let files = self
+            .files()
+            .map(|m| {
+                self.render(m.content)
+                    .map(|content| (m.path, content))
+                    .with_context(|| format!("failed to render {}", m.path))
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        for (path, content) in files {
+            let path = project_dir.join(path);
+            if let Some(m) = path.parent() {
+                fs::create_dir_all(m)
+                    .await
+                    .with_context(|| format!("failed to create {}", m.display()))?;
+            }
+
+            fs::write(&path, content)
+                .await
+                .with_context(|| format!("failed to write {}", path.display()))?;
+        }
+
+        Ok(())
+    }
+}
+
+fn print_created(project_name: &str, cd_dir: Option<&str>) {
+    eprintln!(
+        "{}",
+        style(format!("Created project {project_name}!"))
+            .green()
+            .bold()
+    );
+    eprintln!();
+    eprintln!("To start the development server, run:");
+    if let Some(m) = cd_dir {
+        eprintln!("  cd {m}");
+    }
+    eprintln!("  cargo run --bin stctl -- serve --open");
+}
+
+/// Creates a project in a new directory.
+pub(crate) async fn run_new(cmd_args: &NewCommand) -> Result<()> {
+    let template = Template::new(&cmd_args.name, &cmd_args.options)?;
+    let project_dir = env::current_dir()?.join(&cmd_args.name);
+
+    ensure!(
+        !project_dir.exists(),
+        "failed to create project, {} already exists",
+        project_dir.display()
+    );
+
+    template.write_to(&project_dir).await?;
+    print_created(&cmd_args.name, Some(&cmd_args.name));
+
+    Ok(())
+}
+
+/// Creates a project in the current directory.
+pub(crate) async fn run_init(cmd_args: &InitCommand) -> Result<()> {
+    let project_dir = env::current_dir()?;
+
+    let project_name = match cmd_args.name {
+        Some(ref m) => m.to_owned(),
+        None => project_dir
+            .file_name()
+            .map(|m| m.to_string_lossy().into_owned())
+            .context("failed to find the name of current directory, use --name to set one")?,
+    };
+    let template = Template::new(&project_name, &cmd_args.options)?;
+
+    // Existing files are never overwritten, nothing is written if any of them exists.
+    let conflicts = template
+        .files()
+        .map(|m| m.path)
+        .filter(|m| project_dir.join(m).exists())
+        .collect::<Vec<_>>();
+    ensure!(
+        conflicts.is_empty(),
+        "failed to initialise project, the following files already exist in current directory: {}",
+        conflicts.join(", ")
+    );
+
+    template.write_to(&project_dir).await?;
+    print_created(&project_name, None);
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn template(flags: &[&'static str]) -> Template {
+        Template {
+            project_name: "my-app".to_string(),
+            flags: flags.iter().copied().collect(),
+            stellation_path: None,
+        }
+    }
+
+    #[test]
+    fn renders_placeholders() {
+        let rendered = template(&[])
+            .render("name = \"{{project-name}}\"\nuse {{crate_name}}_api;\n")
+            .unwrap();
+
+        assert_eq!(rendered, "name = \"my-app\"\nuse my_app_api;\n");
+    }
+
+    #[test]
+    fn renders_crate_source() {
+        let rendered = template(&[]).render("{{source:stctl}}").unwrap();
+        assert_eq!(
+            rendered,
+            format!("version = \"{}\"\n", env!("CARGO_PKG_VERSION"))
+        );
+
+        let mut template = template(&[]);
+        template.stellation_path = Some(PathBuf::from("/stellation"));
+        let rendered = template.render("{{source:stctl}}").unwrap();
+        assert_eq!(
+            rendered,
+            format!(
+                "path = '{}'\n",
+                Path::new("/stellation/crates/stctl").display()
+            )
+        );
+    }
+
+    #[test]
+    fn renders_conditions() {
+        let content = "a\n{{#if bridge}}\nb\n{{#else}}\nc\n{{/if}}\n{{#if !stylist}}\nd\n{{/if}}\n";
+
+        assert_eq!(template(&["bridge"]).render(content).unwrap(), "a\nb\nd\n");
+        assert_eq!(template(&["stylist"]).render(content).unwrap(), "a\nc\n");
+    }
+
+    #[test]
+    fn renders_nested_conditions() {
+        let content =
+            "{{#if bridge}}\n{{#if stylist}}\na\n{{#else}}\nb\n{{/if}}\n{{#else}}\n{{#if \
+                       stylist}}\nc\n{{#else}}\nd\n{{/if}}\n{{/if}}\n";
+
+        assert_eq!(
+            template(&["bridge", "stylist"]).render(content).unwrap(),
+            "a\n"
+        );
+        assert_eq!(template(&["bridge"]).render(content).unwrap(), "b\n");
+        assert_eq!(template(&["stylist"]).render(content).unwrap(), "c\n");
+        assert_eq!(template(&[]).render(content).unwrap(), "d\n");
+    }
+
+    #[test]
+    fn rejects_invalid_templates() {
+        let template = template(&[]);
+
+        for content in [
+            "{{project-name",
+            "{{unknown}}",
+            "{{#else}}",
+            "{{/if}}",
+            "{{#if bridge}}\na",
+        ] {
+            assert!(template.render(content).is_err(), "{content} should fail");
+        }
+    }
+
+    #[test]
+    fn renders_all_files_with_all_flags() {
+        for backend in ["tower", "warp"] {
+            for flags in [&[][..], &["bridge"], &["stylist"], &["bridge", "stylist"]] {
+                let mut template = template(flags);
+                template.flags.insert(backend);
+
+                for file in template.files() {
+                    let rendered = template.render(file.content);
+                    assert!(rendered.is_ok(), "failed to render {}", file.path);
+                }
+            }
+        }
+    }
+}
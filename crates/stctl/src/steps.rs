@@ -0,0 +1,102 @@
+//! Runs the cargo invocations of `stctl check` and `stctl test`.
+
+use std::time::{Duration, Instant};
+
+use anyhow::{bail, Context, Result};
+use console::style;
+use tokio::process::Command;
+
+/// A command that is run as a step.
+#[derive(Debug)]
+struct Step {
+    name: String,
+    command: Command,
+}
+
+/// The result of a step.
+#[derive(Debug)]
+struct StepResult {
+    name: String,
+    success: bool,
+    elapsed: Duration,
+}
+
+/// Steps that are run in order.
+///
+/// All steps are run even if a step fails, the results are printed after the last step.
+#[derive(Debug, Default)]
+pub(crate) struct Steps {
+    steps: Vec<Step>,
+}
+
+impl Steps {
+    /// Creates an empty list of steps.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Appends a step.
+    pub fn with_step<S>(mut self, name: S, command: Command) -> Self
+    where
+        S: Into<String>,
+    {
+        self.steps.push(Step {
+            name: name.into(),
+            command,
+        });
+
+        self
+    }
+
+    /// Runs all steps and fails if any step fails.
+    pub async fn run(self) -> Result<()> {
+        let mut results = Vec::new();
+
+        for Step { name, mut command } in self.steps {
+            eprintln!("{}", style(format!("Running {name}...")).cyan().bold());
+
+            let start_time = Instant::now();
+            let status = command
+                .spawn()
+                .with_context(|| format!("failed to run {name}"))?
+                .wait()
+                .await
+                .with_context(|| format!("failed to run {name}"))?;
+
+            results.push(StepResult {
+                name,
+                success: status.success(),
+                elapsed: start_time.elapsed(),
+            });
+        }
+
+        Self::print_results(&results);
+
+        let failed = results.iter().filter(|m| !m.success).count();
+        if failed > 0 {
+            bail!("{failed} of {} steps failed", results.len());
+        }
+
+        Ok(())
+    }
+
+    fn print_results(results: &[StepResult]) {
+        let name_width = results.iter().map(|m| m.name.len()).max().unwrap_or(0);
+
+        eprintln!();
+        eprintln!("{}", style("Results:").bold());
+
+        for result in results {
+            let mark = match result.success {
+                true => style("ok").green().bold(),
+                false => style("FAILED").red().bold(),
+            };
+
+            eprintln!(
+                "  {:name_width$}  {:>8.2}s  {mark}",
+                result.name,
+                result.elapsed.as_secs_f64(),
+            );
+        }
+    }
+}
@@ -0,0 +1,271 @@
+//! Selects the files that trigger a rebuild of the development server.
+
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use anyhow::{Context, Result};
+use globset::{GlobBuilder, GlobSet, GlobSetBuilder};
+use ignore::gitignore::Gitignore;
+use ignore::{Match, WalkBuilder};
+use tokio::fs;
+
+use crate::manifest::Manifest;
+use crate::profile::Profile;
+use crate::workspace::load_metadata;
+
+/// Files that are watched unless they are excluded.
+const DEFAULT_INCLUDE: &[&str] = &[
+    "**/src/**",
+    "**/Cargo.toml",
+    "**/build.rs",
+    "**/index.html",
+    "**/assets/**",
+    "**/public/**",
+    "**/static/**",
+    "**/styles/**",
+    "**/*.{css,scss,sass}",
+    ".env",
+    ".env.*",
+];
+
+/// Files that are never watched, these are written by builds.
+///
+/// The output directory is excluded as well.
+const DEFAULT_EXCLUDE: &[&str] = &[
+    "target/**",
+    ".stellation/**",
+    "**/.git/**",
+    "**/node_modules/**",
+];
+
+/// Environment files are usually ignored by git, but they are loaded by stctl.
+const ENV_FILES: &[&str] = &[".env", ".env.*"];
+
+fn build_glob_set<'a, I>(globs: I) -> Result<GlobSet>
+where
+    I: IntoIterator<Item = &'a str>,
+{
+    let mut builder = GlobSetBuilder::new();
+
+    for glob in globs {
+        builder.add(
+            GlobBuilder::new(glob)
+                .literal_separator(true)
+                .build()
+                .with_context(|| format!("invalid watch glob: {glob}"))?,
+        );
+    }
+
+    Ok(builder.build()?)
+}
+
+/// Returns the `.gitignore` files that apply to the workspace, the deepest file comes first.
+///
+/// This includes files in parent directories up to the root of the git repository.
+fn load_gitignores(workspace_dir: &Path) -> Vec<Gitignore> {
+    let mut paths = Vec::new();
+
+    for dir in workspace_dir.ancestors() {
+        paths.push(dir.join(".gitignore"));
+
+        if dir.join(".git").exists() {
+            break;
+        }
+    }
+
+    // The walker skips directories that are ignored, so files inside of them are not collected.
+    for entry in WalkBuilder::new(workspace_dir)
+        .hidden(false)
+        .build()
+        .filter_map(|m| m.ok())
+    {
+        if entry.file_name() == ".gitignore" && entry.depth() > 0 {
+            paths.push(entry.into_path());
+        }
+    }
+
+    let mut gitignores = paths
+        .into_iter()
+        .filter(|m| m.is_file())
+        .map(|m| {
+            let (gitignore, e) = Gitignore::new(&m);
+            if let Some(e) = e {
+                tracing::warn!("failed to parse {}: {}", m.display(), e);
+            }
+
+            gitignore
+        })
+        .collect::<Vec<_>>();
+    gitignores.sort_by_key(|m| std::cmp::Reverse(m.path().components().count()));
+
+    gitignores
+}
+
+/// Parses the files that a build script depends on from its output.
+fn parse_rerun_if_changed(output: &str, package_dir: &Path) -> Vec<PathBuf> {
+    output
+        .lines()
+        .filter_map(|m| {
+            m.strip_prefix("cargo:rerun-if-changed=")
+                .or_else(|| m.strip_prefix("cargo::rerun-if-changed="))
+        })
+        .map(|m| package_dir.join(m.trim()))
+        .collect()
+}
+
+/// A filter of the files that trigger a rebuild when changed.
+#[derive(Debug)]
+pub(crate) struct WatchFilter {
+    workspace_dir: PathBuf,
+    output_dir: PathBuf,
+    index_path: PathBuf,
+    include: GlobSet,
+    exclude: GlobSet,
+    env_files: GlobSet,
+    gitignores: Vec<Gitignore>,
+    /// Files read by build scripts of local crates.
+    build_inputs: Mutex<Vec<PathBuf>>,
+}
+
+impl WatchFilter {
+    /// Creates a filter from the watch settings of the manifest.
+    pub fn new(workspace_dir: &Path, manifest: &Manifest) -> Result<Self> {
+        let watch = &manifest.dev_server.watch;
+        let include = build_glob_set(
+            DEFAULT_INCLUDE
+                .iter()
+                .copied()
+                .chain(watch.include.iter().map(|m| m.as_str())),
+        )?;
+        let exclude = build_glob_set(
+            DEFAULT_EXCLUDE
+                .iter()
+                .copied()
+                .chain(watch.exclude.iter().map(|m| m.as_str())),
+        )?;
+
+        let gitignores = if watch.gitignore {
+            load_gitignores(workspace_dir)
+        } else {
+            Vec::new()
+        };
+
+        Ok(Self {
+            workspace_dir: workspace_dir.to_owned(),
+            output_dir: manifest.output_dir(workspace_dir),
+            index_path: manifest.index_path(workspace_dir),
+            include,
+            exclude,
+            env_files: build_glob_set(ENV_FILES.iter().copied())?,
+            gitignores,
+            build_inputs: Mutex::default(),
+        })
+    }
+
+    /// Returns `true` if a change to the file should trigger a rebuild.
+    pub fn is_watched(&self, path: &Path) -> bool {
+        let relative_path = match path.strip_prefix(&self.workspace_dir) {
+            Ok(m) => m,
+            Err(_) => return false,
+        };
+
+        if self.exclude.is_match(relative_path) || path.starts_with(&self.output_dir) {
+            return false;
+        }
+
+        let is_included = path == self.index_path
+            || self.include.is_match(relative_path)
+            || self.is_build_input(path);
+
+        if !is_included {
+            return false;
+        }
+
+        self.env_files.is_match(relative_path) || !self.is_gitignored(path)
+    }
+
+    fn is_build_input(&self, path: &Path) -> bool {
+        self.build_inputs
+            .lock()
+            .expect("failed to lock build inputs")
+            .iter()
+            .any(|m| path.starts_with(m))
+    }
+
+    fn is_gitignored(&self, path: &Path) -> bool {
+        for gitignore in self.gitignores.iter() {
+            if !path.starts_with(gitignore.path()) {
+                continue;
+            }
+
+            match gitignore.matched_path_or_any_parents(path, path.is_dir()) {
+                Match::Ignore(_) => return true,
+                Match::Whitelist(_) => return false,
+                Match::None => {}
+            }
+        }
+
+        false
+    }
+
+    /// Reloads the files read by build scripts of local crates.
+    ///
+    /// The files are declared with `cargo:rerun-if-changed` by build scripts, so they are only
+    /// known after a build.
+    pub async fn refresh_build_inputs(&self, profile: &Profile) -> Result<()> {
+        let meta = load_metadata(&self.workspace_dir).await?;
+        let build_dir = meta
+            .target_directory
+            .as_std_path()
+            .join(profile.name())
+            .join("build");
+
+        let mut build_script_dirs = Vec::new();
+        if let Ok(mut entries) = fs::read_dir(&build_dir).await {
+            while let Some(entry) = entries.next_entry().await? {
+                build_script_dirs.push(entry.file_name().to_string_lossy().into_owned());
+            }
+        }
+
+        let mut build_inputs = Vec::new();
+
+        for pkg in meta.packages.iter().filter(|m| m.source.is_none()) {
+            if !pkg
+                .targets
+                .iter()
+                .any(|t| t.kind.iter().any(|k| k == "custom-build"))
+            {
+                continue;
+            }
+
+            let package_dir = match pkg.manifest_path.parent() {
+                Some(m) => m.as_std_path(),
+                None => continue,
+            };
+            // Directories of build scripts are named after the crate and a hash.
+            let prefix = format!("{}-", pkg.name);
+
+            for dir_name in build_script_dirs.iter().filter(|m| {
+                m.strip_prefix(&prefix)
+                    .map(|hash| hash.bytes().all(|b| b.is_ascii_hexdigit()))
+                    .unwrap_or(false)
+            }) {
+                if let Ok(output) =
+                    fs::read_to_string(build_dir.join(dir_name).join("output")).await
+                {
+                    build_inputs.extend(parse_rerun_if_changed(&output, package_dir));
+                }
+            }
+        }
+
+        build_inputs.sort();
+        build_inputs.dedup();
+
+        *self
+            .build_inputs
+            .lock()
+            .expect("failed to lock build inputs") = build_inputs;
+
+        Ok(())
+    }
+}
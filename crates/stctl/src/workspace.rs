@@ -0,0 +1,258 @@
+//! Locates the crates of changed files.
+
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+
+use anyhow::{bail, Context, Result};
+use cargo_metadata::{DependencyKind, Metadata, Package, PackageId};
+use tokio::fs;
+
+use crate::manifest::Manifest;
+
+/// Loads the metadata of the cargo workspace.
+pub(crate) async fn load_metadata(workspace_dir: &Path) -> Result<Metadata> {
+    use tokio::process::Command;
+
+    let pkg_meta_output = Command::new("cargo")
+        .arg("metadata")
+        .arg("--format-version=1")
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .current_dir(workspace_dir)
+        .spawn()?
+        .wait_with_output()
+        .await
+        .context("failed to read package metadata")?;
+
+    if !pkg_meta_output.status.success() {
+        bail!(
+            "cargo metadata failed with status {}",
+            pkg_meta_output.status
+        );
+    }
+
+    serde_json::from_slice(&pkg_meta_output.stdout).context("failed to parse package metadata")
+}
+
+/// Returns the crate that contains a binary.
+fn find_bin_crate<'a>(meta: &'a Metadata, bin_name: &str) -> Result<&'a Package> {
+    meta.packages
+        .iter()
+        .find(|m| {
+            m.targets
+                .iter()
+                .any(|t| t.name == bin_name && t.kind.iter().any(|k| k == "bin"))
+        })
+        .with_context(|| format!("failed to find the crate of binary {bin_name}"))
+}
+
+/// Returns a crate and the crates it depends on.
+fn dependency_closure(meta: &Metadata, id: PackageId) -> Result<HashSet<PackageId>> {
+    let resolve = meta
+        .resolve
+        .as_ref()
+        .context("failed to resolve dependencies of the workspace")?;
+
+    let mut crates = HashSet::new();
+    let mut pending = vec![id];
+
+    while let Some(id) = pending.pop() {
+        if !crates.insert(id.clone()) {
+            continue;
+        }
+
+        if let Some(node) = resolve.nodes.iter().find(|m| m.id == id) {
+            pending.extend(node.dependencies.iter().cloned());
+        }
+    }
+
+    Ok(crates)
+}
+
+/// Returns the `href` of the rust asset in an index processed by trunk.
+fn trunk_rust_href(index_html: &str) -> Option<&str> {
+    fn attr<'a>(tag: &'a str, name: &str) -> Option<&'a str> {
+        let start = tag.find(&format!("{name}=\""))? + name.len() + 2;
+        let len = tag[start..].find('"')?;
+
+        Some(&tag[start..start + len])
+    }
+
+    index_html
+        .split("<link")
+        .skip(1)
+        .filter_map(|m| m.split('>').next())
+        .filter(|m| m.contains("data-trunk") && attr(m, "rel") == Some("rust"))
+        .find_map(|m| attr(m, "href"))
+}
+
+/// Returns the frontend crate, which is the crate in `frontend.crate` or the crate that trunk
+/// builds from the index.
+async fn find_frontend_crate<'a>(
+    meta: &'a Metadata,
+    workspace_dir: &Path,
+    manifest: &Manifest,
+) -> Result<&'a Package> {
+    if let Some(ref m) = manifest.frontend.crate_name {
+        return meta
+            .workspace_packages()
+            .into_iter()
+            .find(|pkg| &pkg.name == m)
+            .with_context(|| format!("failed to find frontend crate {m}"));
+    }
+
+    let index_html = fs::read_to_string(manifest.index_path(workspace_dir))
+        .await
+        .context("failed to read frontend index")?;
+
+    // Trunk uses the Cargo.toml in the directory of the index if the index does not have a rust
+    // asset.
+    let mut manifest_path = workspace_dir.join(trunk_rust_href(&index_html).unwrap_or_default());
+    if manifest_path.is_dir() {
+        manifest_path = manifest_path.join("Cargo.toml");
+    }
+    let manifest_path = manifest_path.canonicalize().with_context(|| {
+        format!(
+            "failed to find the frontend crate at {}, set `frontend.crate` in stellation.toml",
+            manifest_path.display()
+        )
+    })?;
+
+    meta.packages
+        .iter()
+        .find(|m| m.manifest_path.as_std_path() == manifest_path)
+        .context("failed to find the frontend crate, set `frontend.crate` in stellation.toml")
+}
+
+/// The local crates that are checked and tested by stctl.
+#[derive(Debug)]
+pub(crate) struct CheckCrates {
+    /// The name of the crate of the backend binary.
+    pub backend_crate: String,
+    /// The local crates compiled into the backend, including the backend itself.
+    pub backend: Vec<String>,
+    /// The local crates compiled into the frontend, including the frontend itself.
+    pub frontend: Vec<String>,
+    /// The frontend crates that have `wasm-bindgen-test` tests.
+    pub wasm_tests: Vec<String>,
+}
+
+impl CheckCrates {
+    /// Loads the crates of the backend and the frontend of the manifest.
+    pub async fn load(workspace_dir: &Path, manifest: &Manifest) -> Result<Self> {
+        let meta = load_metadata(workspace_dir).await?;
+
+        let backend_crate = find_bin_crate(&meta, &manifest.dev_server.bin_name)?;
+        let frontend_crate = find_frontend_crate(&meta, workspace_dir, manifest).await?;
+
+        let local_names = |id: PackageId| {
+            let ids = dependency_closure(&meta, id)?;
+            let mut crates = meta
+                .packages
+                .iter()
+                .filter(|m| m.source.is_none() && ids.contains(&m.id))
+                .collect::<Vec<_>>();
+            crates.sort_by(|l, r| l.name.cmp(&r.name));
+
+            Ok::<_, anyhow::Error>(crates)
+        };
+
+        let backend = local_names(backend_crate.id.clone())?;
+        let frontend = local_names(frontend_crate.id.clone())?;
+
+        let wasm_tests = frontend
+            .iter()
+            .filter(|m| {
+                m.dependencies.iter().any(|dep| {
+                    dep.name == "wasm-bindgen-test" && dep.kind == DependencyKind::Development
+                })
+            })
+            .map(|m| m.name.clone())
+            .collect();
+
+        Ok(Self {
+            backend_crate: backend_crate.name.clone(),
+            backend: backend.into_iter().map(|m| m.name.clone()).collect(),
+            frontend: frontend.into_iter().map(|m| m.name.clone()).collect(),
+            wasm_tests,
+        })
+    }
+}
+
+/// The artifacts that need to be rebuilt after a change.
+///
+/// Variants are ordered by the amount of artifacts that are rebuilt.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub(crate) enum Rebuild {
+    /// Only crates that are not compiled into the backend have changed.
+    Frontend,
+    /// The backend needs to be rebuilt, which also rebuilds the frontend.
+    All,
+}
+
+/// The local crates of a workspace and whether they are compiled into the backend.
+#[derive(Debug)]
+pub(crate) struct WorkspaceCrates {
+    /// The directories of local crates, the deepest directory comes first.
+    crate_dirs: Vec<(PathBuf, PackageId)>,
+    /// The local crates that the backend binary depends on, including the backend itself.
+    backend_crates: HashSet<PackageId>,
+    /// The page template, which is only read by the frontend build.
+    index_html: PathBuf,
+}
+
+impl WorkspaceCrates {
+    /// Loads the crates of a workspace with the backend binary of the manifest.
+    pub async fn load(workspace_dir: &Path, manifest: &Manifest) -> Result<Self> {
+        let bin_name = manifest.dev_server.bin_name.as_str();
+        let meta = load_metadata(workspace_dir).await?;
+
+        // Crates from registries and git repositories do not change during development.
+        let mut crate_dirs = meta
+            .packages
+            .iter()
+            .filter(|m| m.source.is_none())
+            .filter_map(|m| {
+                let dir = m.manifest_path.parent()?.as_std_path().to_owned();
+                Some((dir, m.id.clone()))
+            })
+            .collect::<Vec<_>>();
+        crate_dirs.sort_by_key(|(dir, _)| std::cmp::Reverse(dir.components().count()));
+
+        let backend_id = find_bin_crate(&meta, bin_name)?.id.clone();
+        let backend_crates = dependency_closure(&meta, backend_id)?;
+
+        Ok(Self {
+            crate_dirs,
+            backend_crates,
+            index_html: manifest.index_path(workspace_dir),
+        })
+    }
+
+    /// Returns the crate that a file belongs to.
+    fn find_crate(&self, path: &Path) -> Option<&PackageId> {
+        self.crate_dirs
+            .iter()
+            .find(|(dir, _)| path.starts_with(dir))
+            .map(|(_, id)| id)
+    }
+
+    /// Returns the artifacts that need to be rebuilt after files are changed.
+    ///
+    /// Files outside of local crates may affect any artifact, so everything is rebuilt.
+    pub fn rebuild_for<'a, I>(&self, paths: I) -> Rebuild
+    where
+        I: IntoIterator<Item = &'a PathBuf>,
+    {
+        for path in paths.into_iter().filter(|m| **m != self.index_html) {
+            match self.find_crate(path) {
+                Some(m) if !self.backend_crates.contains(m) => {}
+                _ => return Rebuild::All,
+            }
+        }
+
+        Rebuild::Frontend
+    }
+}
@@ -1,24 +1,93 @@
+use std::convert::Infallible;
 use std::env;
-use std::net::ToSocketAddrs;
+use std::net::{IpAddr, ToSocketAddrs};
+use std::num::NonZeroUsize;
 use std::path::PathBuf;
 
 use anyhow::{anyhow, Context};
 use clap::Parser;
+use hyper::server::conn::AddrStream;
+use hyper::service::{make_service_fn, service_fn};
+use hyper::{Body, Request};
 use stellation_backend::ServerAppProps;
-use stellation_backend_tower::{Frontend, Server, TowerEndpoint, TowerRenderRequest};
+use stellation_backend_tower::{Frontend, RemoteAddr, Server, TowerEndpoint, TowerRenderRequest};
 use stellation_bridge::links::{Link, PhantomLink};
+use stellation_core::base_path;
 use stellation_core::dev::StctlMetadata;
+use tower::Service;
 use typed_builder::TypedBuilder;
+use yew::platform::Runtime;
 use yew::BaseComponent;
 
+use crate::trace::{self, LogFormat, TraceOptions};
+
+/// Parses a sample rate between `0.0` and `1.0`.
+fn parse_sample_rate(value: &str) -> Result<f64, String> {
+    let sample_rate = value
+        .parse::<f64>()
+        .map_err(|e| format!("invalid sample rate: {e}"))?;
+
+    match (0.0..=1.0).contains(&sample_rate) {
+        true => Ok(sample_rate),
+        false => Err(format!(
+            "sample rate must be between 0.0 and 1.0, found {value}"
+        )),
+    }
+}
+
 #[derive(Parser)]
-struct Arguments {
+pub(crate) struct Arguments {
     /// The address to listen to.
     #[arg(long, default_value = "localhost:5000", env = "STELLATION_LISTEN_ADDR")]
-    listen_addr: String,
+    pub listen_addr: String,
     /// The ditectory that contains the frontend artifact.
     #[arg(long, env = "STELLATION_FRONTEND_DIR")]
-    frontend_dir: Option<PathBuf>,
+    pub frontend_dir: Option<PathBuf>,
+    /// The path that the application is mounted at. [Default: the base path in stellation.toml
+    /// for backends built by `stctl build`, `/` otherwise]
+    #[arg(long, env = "STELLATION_BASE_PATH")]
+    pub base_path: Option<String>,
+    /// The number of worker threads that render pages and resolve bridge requests, the threads are
+    /// named `prokio-runtime-worker`. [Default: the number of logical CPU cores, shared with
+    /// request processing]
+    #[arg(long, env = "STELLATION_RENDER_WORKERS")]
+    pub render_workers: Option<NonZeroUsize>,
+    /// The format of server logs, only used if the server initialises tracing. [Default: pretty
+    /// under the development server, compact otherwise]
+    #[arg(long, value_enum, env = "STELLATION_LOG_FORMAT")]
+    pub log_format: Option<LogFormat>,
+    /// The ratio of requests recorded in json access logs, between 0.0 and 1.0.
+    #[arg(
+        long,
+        default_value = "1.0",
+        env = "STELLATION_ACCESS_LOG_SAMPLE_RATE",
+        value_parser = parse_sample_rate
+    )]
+    pub access_log_sample_rate: f64,
+    /// Paths excluded from json access logs, separated by commas.
+    #[arg(long, value_delimiter = ',', env = "STELLATION_ACCESS_LOG_EXCLUDE")]
+    pub access_log_exclude: Vec<String>,
+    /// The ip addresses of reverse proxies that are trusted to forward the address of clients
+    /// with the `Forwarded` or `X-Forwarded-For` header, separated by commas.
+    #[arg(long, value_delimiter = ',', env = "STELLATION_TRUSTED_PROXIES")]
+    pub trusted_proxies: Vec<IpAddr>,
+}
+
+impl Arguments {
+    fn trace_options(&self) -> TraceOptions {
+        let mut options =
+            TraceOptions::new().with_access_log_sample_rate(self.access_log_sample_rate);
+
+        if let Some(m) = self.log_format {
+            options = options.with_log_format(m);
+        }
+
+        self.access_log_exclude
+            .iter()
+            .fold(options, |options, path| {
+                options.with_access_log_excluded_path(path)
+            })
+    }
 }
 
 /// The default command line instance for the backend server.
@@ -28,6 +97,12 @@ where
     COMP: BaseComponent,
 {
     endpoint: TowerEndpoint<COMP, CTX, L>,
+    /// The environment variable of the log filter, e.g.: `STELLATION_APP_SERVER_LOG`.
+    ///
+    /// If set, tracing is initialised with the log arguments of the command line before the server
+    /// starts.
+    #[builder(default, setter(strip_option, into))]
+    log_filter_var: Option<String>,
 }
 
 impl<COMP, CTX, L> Cli<COMP, CTX, L>
@@ -38,10 +113,17 @@ where
 {
     /// Parses the arguments and runs the server.
     pub async fn run(self) -> anyhow::Result<()> {
-        let Self { mut endpoint } = self;
+        let Self {
+            mut endpoint,
+            log_filter_var,
+        } = self;
 
         let args = Arguments::parse();
 
+        if let Some(m) = log_filter_var {
+            trace::init(m, args.trace_options());
+        }
+
         // Prioritise information from stctl.
         let meta = match env::var(StctlMetadata::ENV_NAME) {
             Ok(m) => Some(StctlMetadata::from_json(&m).context("failed to load metadata")?),
@@ -53,14 +135,51 @@ where
             .map(|m| m.listen_addr.as_str())
             .unwrap_or_else(|| args.listen_addr.as_str());
 
+        let base_path = base_path::normalize(
+            &meta
+                .as_ref()
+                .map(|m| m.base_path.clone())
+                .or_else(|| args.base_path.clone())
+                .unwrap_or_else(base_path::build_default),
+        );
+
+        endpoint = endpoint.with_base_path(&base_path);
+
+        if let Some(m) = args.render_workers {
+            let rt = Runtime::builder()
+                .worker_threads(m.get())
+                .build()
+                .context("failed to create runtime")?;
+
+            endpoint = endpoint.with_runtime(rt);
+        }
+
+        for addr in args.trusted_proxies.iter().copied() {
+            endpoint = endpoint.with_trusted_proxy(addr);
+        }
+
         if let Some(ref p) = args.frontend_dir {
             endpoint = endpoint.with_frontend(Frontend::new_path(p));
         }
 
-        if let Some(ref meta) = meta {
-            endpoint = endpoint
-                .with_frontend(Frontend::new_path(&meta.frontend_dev_build_dir))
-                .with_auto_refresh();
+        match meta {
+            Some(ref meta) => {
+                // The frontend can be replaced by stctl without restarting the server.
+                let frontend = match meta.frontend_dev_build_file {
+                    Some(ref m) => Frontend::new_path_file(m),
+                    None => Frontend::new_path(&meta.frontend_dev_build_dir),
+                };
+
+                endpoint = endpoint.with_frontend(frontend).with_auto_refresh();
+
+                if let Some(ref m) = meta.build_status_file {
+                    endpoint = endpoint.with_build_status_file(m);
+                }
+            }
+            // Responses are only compressed outside of the development server.
+            None => {
+                endpoint = endpoint.with_compression();
+            }
         }
 
         let listen_addr = addr
@@ -72,10 +191,24 @@ where
                     .ok_or_else(|| anyhow!("failed to parse address"))
             })?;
 
-        tracing::info!("Listening at: http://{}/", addr);
+        tracing::info!("Listening at: http://{}{}", addr, base_path);
+
+        let svc = endpoint.into_tower_service();
+        // The address of the client is provided to the endpoint with an extension.
+        let make_svc = make_service_fn(move |conn: &AddrStream| {
+            let remote_addr = RemoteAddr(conn.remote_addr());
+            let svc = svc.clone();
+
+            async move {
+                Ok::<_, Infallible>(service_fn(move |mut req: Request<Body>| {
+                    req.extensions_mut().insert(remote_addr);
+                    svc.clone().call(req)
+                }))
+            }
+        });
 
         Server::<()>::bind(listen_addr)
-            .serve_service(endpoint.into_tower_service())
+            .serve_make_service(make_svc)
             .await?;
 
         Ok(())
@@ -1,17 +1,96 @@
 //! Tracing support.
 
+use std::collections::HashSet;
 use std::env;
+use std::io::Write;
+use std::time::SystemTime;
 
+use clap::ValueEnum;
 use console::style;
+use rand::Rng;
+use serde_json::json;
 use stellation_core::dev::StctlMetadata;
 use tracing::field::Visit;
 use tracing::{Level, Subscriber};
 use tracing_subscriber::filter::filter_fn;
+use tracing_subscriber::fmt::MakeWriter;
 use tracing_subscriber::layer::Context;
 use tracing_subscriber::prelude::*;
 use tracing_subscriber::registry::LookupSpan;
 use tracing_subscriber::{EnvFilter, Layer};
 
+const ACCESS_LOG_TARGET: &str = "stellation_backend::endpoint::trace";
+
+/// The format of logs emitted by the server.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum LogFormat {
+    /// Coloured access logs and compact application logs, used by the development server.
+    Pretty,
+    /// Human readable logs in a compact text format.
+    Compact,
+    /// Newline delimited json objects, suitable for log collectors.
+    Json,
+}
+
+/// Values recorded by the access log event of an endpoint.
+#[derive(Default, Debug)]
+struct AccessValues {
+    request_id: Option<String>,
+    duration: Option<u128>,
+    path: Option<String>,
+    method: Option<String>,
+    status: Option<u64>,
+    remote_addr: Option<String>,
+    referer: Option<String>,
+    user_agent: Option<String>,
+}
+
+impl AccessValues {
+    fn from_event(event: &tracing::Event<'_>) -> Self {
+        let mut values = Self::default();
+        event.record(&mut values);
+
+        values
+    }
+}
+
+impl Visit for AccessValues {
+    fn record_u128(&mut self, field: &tracing::field::Field, value: u128) {
+        if field.as_ref() == "duration" {
+            self.duration = Some(value);
+        }
+    }
+
+    fn record_str(&mut self, field: &tracing::field::Field, value: &str) {
+        // Empty values are emitted for information that is not available.
+        let value = (!value.is_empty()).then(|| value.to_string());
+
+        match field.as_ref() {
+            "request_id" => self.request_id = value,
+            "path" => self.path = value,
+            "referer" => self.referer = value,
+            "user_agent" => self.user_agent = value,
+            _ => {}
+        }
+    }
+
+    fn record_u64(&mut self, field: &tracing::field::Field, value: u64) {
+        if field.as_ref() == "status" {
+            self.status = Some(value);
+        }
+    }
+
+    fn record_debug(&mut self, field: &tracing::field::Field, value: &dyn std::fmt::Debug) {
+        let value = format!("{value:?}");
+
+        match field.as_ref() {
+            "method" => self.method = Some(value),
+            "remote_addr" if !value.is_empty() => self.remote_addr = Some(value),
+            _ => {}
+        }
+    }
+}
+
 /// A layer that emits pretty access logs for stellation servers.
 #[derive(Debug, Default)]
 pub struct AccessLog {}
@@ -26,46 +105,11 @@ where
     S: Subscriber + for<'lookup> LookupSpan<'lookup>,
 {
     fn on_event(&self, event: &tracing::Event<'_>, _ctx: Context<'_, S>) {
-        if event.metadata().target() != "stellation_backend::endpoint::trace" {
+        if event.metadata().target() != ACCESS_LOG_TARGET {
             return;
         }
 
-        #[derive(Default, Debug)]
-        struct Values {
-            duration: Option<u128>,
-            path: Option<String>,
-            method: Option<String>,
-            status: Option<u64>,
-        }
-
-        impl Visit for Values {
-            fn record_u128(&mut self, field: &tracing::field::Field, value: u128) {
-                if field.as_ref() == "duration" {
-                    self.duration = Some(value);
-                }
-            }
-
-            fn record_str(&mut self, field: &tracing::field::Field, value: &str) {
-                if field.as_ref() == "path" {
-                    self.path = Some(value.to_string());
-                }
-            }
-
-            fn record_u64(&mut self, field: &tracing::field::Field, value: u64) {
-                if field.as_ref() == "status" {
-                    self.status = Some(value);
-                }
-            }
-
-            fn record_debug(&mut self, field: &tracing::field::Field, value: &dyn std::fmt::Debug) {
-                if field.as_ref() == "method" {
-                    self.method = Some(format!("{value:?}"));
-                }
-            }
-        }
-
-        let mut values = Values::default();
-        event.record(&mut values);
+        let values = AccessValues::from_event(event);
 
         if let (Some(path), Some(duration), Some(status), Some(method)) =
             (values.path, values.duration, values.status, values.method)
@@ -89,8 +133,219 @@ where
     }
 }
 
+/// A layer that emits access logs as newline delimited json objects.
+///
+/// Each line contains the request id, method, path, status, duration, remote address and user
+/// agent of a request.
+pub struct JsonAccessLog<W = fn() -> std::io::Stdout> {
+    sample_rate: f64,
+    excluded_paths: HashSet<String>,
+    make_writer: W,
+}
+
+impl<W> std::fmt::Debug for JsonAccessLog<W> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("JsonAccessLog")
+            .field("sample_rate", &self.sample_rate)
+            .field("excluded_paths", &self.excluded_paths)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Default for JsonAccessLog {
+    fn default() -> Self {
+        Self {
+            sample_rate: 1.0,
+            excluded_paths: HashSet::new(),
+            make_writer: std::io::stdout,
+        }
+    }
+}
+
+/// Returns a layer that emits json access logs.
+pub fn json_access() -> JsonAccessLog {
+    JsonAccessLog::default()
+}
+
+impl<W> JsonAccessLog<W> {
+    /// Sets the writer that access logs are written to.
+    ///
+    /// This accepts the same writers as [`tracing_subscriber::fmt::Layer::with_writer`].
+    ///
+    /// Defaults to standard output.
+    pub fn with_writer<W2>(self, make_writer: W2) -> JsonAccessLog<W2>
+    where
+        W2: for<'writer> MakeWriter<'writer> + 'static,
+    {
+        JsonAccessLog {
+            sample_rate: self.sample_rate,
+            excluded_paths: self.excluded_paths,
+            make_writer,
+        }
+    }
+
+    /// Sets the ratio of requests to be logged, between `0.0` and `1.0`.
+    ///
+    /// Responses with a server error status are always logged regardless of the sample rate.
+    ///
+    /// Defaults to `1.0`.
+    ///
+    /// # Panics
+    ///
+    /// This method panics if the sample rate is not a finite number.
+    pub fn with_sample_rate(mut self, sample_rate: f64) -> Self {
+        assert!(
+            sample_rate.is_finite(),
+            "sample rate must be a finite number, found {sample_rate}"
+        );
+        self.sample_rate = sample_rate.clamp(0.0, 1.0);
+
+        self
+    }
+
+    /// Excludes requests to a path from access logs.
+    ///
+    /// This is useful for health checks. The path needs to match the requested path exactly.
+    pub fn with_excluded_path<P>(mut self, path: P) -> Self
+    where
+        P: Into<String>,
+    {
+        self.excluded_paths.insert(path.into());
+
+        self
+    }
+
+    fn should_log(&self, path: &str, status: u64) -> bool {
+        if self.excluded_paths.contains(path) {
+            return false;
+        }
+
+        if status >= 500 || self.sample_rate >= 1.0 {
+            return true;
+        }
+
+        rand::thread_rng().gen_bool(self.sample_rate)
+    }
+}
+
+impl<S, W> Layer<S> for JsonAccessLog<W>
+where
+    S: Subscriber + for<'lookup> LookupSpan<'lookup>,
+    W: for<'writer> MakeWriter<'writer> + 'static,
+{
+    fn on_event(&self, event: &tracing::Event<'_>, _ctx: Context<'_, S>) {
+        if event.metadata().target() != ACCESS_LOG_TARGET {
+            return;
+        }
+
+        let values = AccessValues::from_event(event);
+
+        let (Some(path), Some(duration), Some(status), Some(method)) =
+            (values.path, values.duration, values.status, values.method)
+        else {
+            return;
+        };
+
+        if !self.should_log(&path, status) {
+            return;
+        }
+
+        let timestamp = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .map(|m| m.as_millis())
+            .unwrap_or_default();
+
+        let line = json!({
+            "timestamp": timestamp,
+            "request_id": values.request_id,
+            "method": method,
+            "path": path,
+            "status": status,
+            // u128 cannot be represented in json, a float is accurate enough for milliseconds.
+            "duration_ms": duration as f64 / 1_000_000.0,
+            "remote_addr": values.remote_addr,
+            "referer": values.referer,
+            "user_agent": values.user_agent,
+        });
+
+        let mut writer = self.make_writer.make_writer_for(event.metadata());
+        let _ = writeln!(writer, "{line}");
+    }
+}
+
+/// Options of tracing, see [`init`].
+#[derive(Debug, Clone)]
+pub struct TraceOptions {
+    log_format: Option<LogFormat>,
+    access_log_sample_rate: f64,
+    access_log_excluded_paths: Vec<String>,
+}
+
+impl Default for TraceOptions {
+    fn default() -> Self {
+        Self {
+            log_format: None,
+            access_log_sample_rate: 1.0,
+            access_log_excluded_paths: Vec::new(),
+        }
+    }
+}
+
+impl TraceOptions {
+    /// Creates options with default settings.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the format of logs.
+    ///
+    /// Defaults to pretty logs under the development server and compact logs otherwise.
+    pub fn with_log_format(mut self, log_format: LogFormat) -> Self {
+        self.log_format = Some(log_format);
+
+        self
+    }
+
+    /// Sets the ratio of requests recorded in json access logs.
+    ///
+    /// See [`JsonAccessLog::with_sample_rate`].
+    pub fn with_access_log_sample_rate(mut self, sample_rate: f64) -> Self {
+        self.access_log_sample_rate = sample_rate;
+
+        self
+    }
+
+    /// Excludes requests to a path from json access logs.
+    ///
+    /// See [`JsonAccessLog::with_excluded_path`].
+    pub fn with_access_log_excluded_path<P>(mut self, path: P) -> Self
+    where
+        P: Into<String>,
+    {
+        self.access_log_excluded_paths.push(path.into());
+
+        self
+    }
+}
+
 /// Initialise tracing with default settings.
+///
+/// Pretty logs are used under the development server and compact logs are used otherwise. Use
+/// [`init`] to select the log format.
 pub fn init_default<S>(var_name: S)
+where
+    S: Into<String>,
+{
+    init(var_name, TraceOptions::default());
+}
+
+/// Initialise tracing with options.
+///
+/// The log filter is read from the environment variable `var_name`.
+///
+/// [`Cli`](crate::Cli) calls this with the log options of the command line if a log filter
+/// variable is set on the command line instance.
+pub fn init<S>(var_name: S, options: TraceOptions)
 where
     S: Into<String>,
 {
@@ -100,8 +355,16 @@ where
         .with_env_var(var_name)
         .from_env_lossy();
 
-    match env::var(StctlMetadata::ENV_NAME) {
-        Ok(_) => {
+    let log_format =
+        options
+            .log_format
+            .unwrap_or_else(|| match env::var(StctlMetadata::ENV_NAME) {
+                Ok(_) => LogFormat::Pretty,
+                Err(_) => LogFormat::Compact,
+            });
+
+    match log_format {
+        LogFormat::Pretty => {
             // Register pretty logging if under development server.
             tracing_subscriber::registry()
                 .with(pretty_access())
@@ -109,18 +372,33 @@ where
                     tracing_subscriber::fmt::layer()
                         .compact()
                         // access logs are processed by the access log layer
-                        .with_filter(filter_fn(|metadata| {
-                            metadata.target() != "stellation_backend::endpoint::trace"
-                        })),
+                        .with_filter(filter_fn(|metadata| metadata.target() != ACCESS_LOG_TARGET)),
                 )
                 .with(env_filter)
                 .init();
         }
-        Err(_) => {
+        LogFormat::Compact => {
             tracing_subscriber::registry()
                 .with(tracing_subscriber::fmt::layer().compact())
                 .with(env_filter)
                 .init();
         }
+        LogFormat::Json => {
+            let access_log = options.access_log_excluded_paths.into_iter().fold(
+                json_access().with_sample_rate(options.access_log_sample_rate),
+                |access_log, path| access_log.with_excluded_path(path),
+            );
+
+            tracing_subscriber::registry()
+                .with(access_log)
+                .with(
+                    tracing_subscriber::fmt::layer()
+                        .json()
+                        // access logs are processed by the access log layer
+                        .with_filter(filter_fn(|metadata| metadata.target() != ACCESS_LOG_TARGET)),
+                )
+                .with(env_filter)
+                .init();
+        }
     }
 }
@@ -1,13 +1,18 @@
 use std::convert::Infallible;
 use std::future::Future;
+use std::net::IpAddr;
+use std::path::PathBuf;
+use std::time::Duration;
 
 use hyper::{Body, Request, Response};
 use stellation_backend::ServerAppProps;
-use stellation_backend_warp::{Frontend, WarpEndpoint};
+use stellation_backend_warp::{Frontend, RenderMode, TaskMetrics, WarpEndpoint};
 use stellation_bridge::links::{Link, PhantomLink};
-use stellation_bridge::Bridge;
+use stellation_bridge::{Bridge, Identity};
 use tower::Service;
+use yew::platform::Runtime;
 use yew::BaseComponent;
+use yew_router::Routable;
 
 use crate::{TowerRenderRequest, TowerRequest};
 
@@ -90,12 +95,157 @@ where
         self
     }
 
+    /// Sets a file that contains the status of the latest build of the development server.
+    ///
+    /// The status is displayed in pages opened in browsers, with the error of a failed build
+    /// displayed over the page. This requires auto refresh to be enabled.
+    pub fn with_build_status_file<P>(mut self, path: P) -> Self
+    where
+        P: Into<PathBuf>,
+    {
+        self.inner = self.inner.with_build_status_file(path);
+        self
+    }
+
+    /// Mounts the application under a path, e.g.: `/app/`.
+    pub fn with_base_path<S>(mut self, base_path: S) -> Self
+    where
+        S: AsRef<str>,
+    {
+        self.inner = self.inner.with_base_path(base_path);
+        self
+    }
+
+    /// Sets the page served when a page fails to render.
+    pub fn with_error_page<S>(mut self, html: S) -> Self
+    where
+        S: AsRef<str>,
+    {
+        self.inner = self.inner.with_error_page(html);
+        self
+    }
+
+    /// Renders the page at the client side if server-side rendering fails.
+    pub fn with_client_only_fallback(mut self) -> Self {
+        self.inner = self.inner.with_client_only_fallback();
+        self
+    }
+
+    /// Sets the time limit of server-side rendering.
+    pub fn with_render_timeout(mut self, timeout: Duration) -> Self {
+        self.inner = self.inner.with_render_timeout(timeout);
+        self
+    }
+
+    /// Limits the number of pages that can be rendered at the server side at the same time.
+    pub fn with_max_concurrent_renders(mut self, max_concurrent_renders: usize) -> Self {
+        self.inner = self
+            .inner
+            .with_max_concurrent_renders(max_concurrent_renders);
+        self
+    }
+
+    /// Sets the runtime that pages are rendered and bridge requests are resolved on.
+    pub fn with_runtime(mut self, runtime: Runtime) -> Self {
+        self.inner = self.inner.with_runtime(runtime);
+        self
+    }
+
+    /// Returns the metrics of tasks spawned by the current endpoint.
+    pub fn task_metrics(&self) -> TaskMetrics {
+        self.inner.task_metrics()
+    }
+
+    /// Sets how pages with paths matching a pattern are rendered.
+    pub fn with_render_mode<P>(mut self, pattern: P, mode: RenderMode) -> Self
+    where
+        P: AsRef<str>,
+    {
+        self.inner = self.inner.with_render_mode(pattern, mode);
+        self
+    }
+
+    /// Matches requests against the routes of a routable before rendering.
+    pub fn with_routable<R>(mut self) -> Self
+    where
+        R: Routable,
+    {
+        self.inner = self.inner.with_routable::<R>();
+        self
+    }
+
+    /// Limits the size of uploads in bytes.
+    ///
+    /// Defaults to 2 MiB.
+    pub fn with_max_upload_size(mut self, max_upload_size: u64) -> Self {
+        self.inner = self.inner.with_max_upload_size(max_upload_size);
+        self
+    }
+
+    /// Limits the size of bridge requests and submitted forms in bytes.
+    pub fn with_max_body_size(mut self, max_body_size: u64) -> Self {
+        self.inner = self.inner.with_max_body_size(max_body_size);
+        self
+    }
+
+    /// Limits the number of static pages kept in memory.
+    pub fn with_max_static_pages(mut self, max_static_pages: usize) -> Self {
+        self.inner = self.inner.with_max_static_pages(max_static_pages);
+        self
+    }
+
+    /// Authenticates the client of each request.
+    pub fn with_authenticator<F, Fut>(mut self, authenticate: F) -> Self
+    where
+        F: 'static + Send + Sync + Fn(TowerRequest<()>) -> Fut,
+        Fut: 'static + Send + Future<Output = Option<Identity>>,
+    {
+        self.inner = self.inner.with_authenticator(authenticate);
+        self
+    }
+
+    /// Enables CSRF protection with tokens.
+    pub fn with_csrf_protection(mut self) -> Self {
+        self.inner = self.inner.with_csrf_protection();
+        self
+    }
+
+    /// Checks the `Origin` or `Referer` header of mutations, uploads, downloads and submitted forms.
+    pub fn with_origin_check(mut self) -> Self {
+        self.inner = self.inner.with_origin_check();
+        self
+    }
+
+    /// Trusts an origin other than the host of the server.
+    pub fn with_trusted_origin<S>(mut self, origin: S) -> Self
+    where
+        S: Into<String>,
+    {
+        self.inner = self.inner.with_trusted_origin(origin);
+        self
+    }
+
+    /// Trusts a reverse proxy to forward the address of clients.
+    pub fn with_trusted_proxy<A>(mut self, addr: A) -> Self
+    where
+        A: Into<IpAddr>,
+    {
+        self.inner = self.inner.with_trusted_proxy(addr);
+        self
+    }
+
     /// Serves a frontend with current endpoint.
     pub fn with_frontend(mut self, frontend: Frontend) -> Self {
         self.inner = self.inner.with_frontend(frontend);
         self
     }
 
+    /// Enables compression of server rendered html and bridge responses.
+    pub fn with_compression(mut self) -> Self {
+        self.inner = self.inner.with_compression();
+        self
+    }
+
     /// Creates a tower service from current endpoint.
     pub fn into_tower_service(
         self,
@@ -24,7 +24,7 @@ pub type TowerRenderRequest<CTX> = stellation_backend_warp::WarpRenderRequest<CT
 /// [`WarpRequest`](stellation_backend_warp::WarpRequest).
 pub type TowerRequest<CTX> = stellation_backend_warp::WarpRequest<CTX>;
 #[doc(inline)]
-pub use stellation_backend_warp::Frontend;
+pub use stellation_backend_warp::{Frontend, RemoteAddr, RenderMode, TaskMetrics};
 
 mod server;
 pub use server::Server;
@@ -63,6 +63,15 @@ impl<I> Server<I> {
             rt: None,
         }
     }
+
+    /// Sets the runtime that requests are processed on.
+    ///
+    /// If no runtime is set, the default runtime is used.
+    pub fn with_runtime(mut self, rt: Runtime) -> Self {
+        self.rt = Some(rt);
+
+        self
+    }
 }
 impl<I> Server<I>
 where
@@ -0,0 +1,230 @@
+//! Cache validators and cache control of frontend assets.
+
+use std::fs::Metadata;
+use std::ops::RangeInclusive;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use http::header::{CACHE_CONTROL, ETAG, IF_MODIFIED_SINCE, IF_NONE_MATCH, LAST_MODIFIED, VARY};
+use http::{HeaderMap, HeaderValue, StatusCode};
+use rust_embed::EmbeddedFile;
+use warp::reply::Response;
+
+/// The lengths of hashes appended to filenames by trunk.
+///
+/// Hashes are 64-bit integers in hex without zero padding, so they can be shorter than 16
+/// characters. Shorter hashes are very unlikely, so names like `app-1` are not treated as hashed.
+const ASSET_HASH_LEN: RangeInclusive<usize> = 8..=16;
+
+/// Returns `true` if an asset is a script, stylesheet or wasm binary hashed by trunk.
+///
+/// Trunk names these assets as `<name>-<hash>.<ext>`, with an additional `_bg` suffix for the wasm
+/// binary. Other files are not hashed by trunk, so they are not matched even if their names look
+/// like hashed names, e.g.: `report-20231018.pdf`.
+pub(crate) fn is_hashed_asset(path: &str) -> bool {
+    let file_name = path.rsplit('/').next().unwrap_or(path);
+    let (stem, ext) = match file_name.split_once('.') {
+        Some(m) => m,
+        None => return false,
+    };
+    let stem = match ext {
+        "wasm" => stem.strip_suffix("_bg").unwrap_or(stem),
+        "js" | "css" => stem,
+        _ => return false,
+    };
+
+    stem.rsplit_once('-')
+        .map(|(_, hash)| {
+            ASSET_HASH_LEN.contains(&hash.len()) && hash.chars().all(|m| m.is_ascii_hexdigit())
+        })
+        .unwrap_or(false)
+}
+
+/// The validators of an asset, used to answer conditional requests.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Validators {
+    etag: Option<String>,
+    last_modified: Option<SystemTime>,
+}
+
+impl Validators {
+    /// Creates validators from the hash and modification time of an embedded file.
+    pub fn from_embedded(file: &EmbeddedFile) -> Self {
+        let hash = file
+            .metadata
+            .sha256_hash()
+            .iter()
+            .map(|m| format!("{m:02x}"))
+            .collect::<String>();
+
+        Self {
+            etag: Some(format!("\"{hash}\"")),
+            last_modified: file
+                .metadata
+                .last_modified()
+                .map(|m| UNIX_EPOCH + Duration::from_secs(m)),
+        }
+    }
+
+    /// Creates validators from the modification time and length of a file.
+    pub fn from_metadata(meta: &Metadata) -> Self {
+        let last_modified = meta.modified().ok();
+        let etag = last_modified
+            .and_then(|m| m.duration_since(UNIX_EPOCH).ok())
+            .map(|m| format!("\"{:x}-{:x}\"", m.as_secs(), meta.len()));
+
+        Self {
+            etag,
+            last_modified,
+        }
+    }
+
+    /// Returns `true` if the client already has an up-to-date copy of the asset.
+    ///
+    /// `If-None-Match` takes precedence over `If-Modified-Since`.
+    fn is_not_modified(&self, request_headers: &HeaderMap) -> bool {
+        if let Some(m) = request_headers.get(IF_NONE_MATCH) {
+            let etag = match self.etag {
+                Some(ref m) => m,
+                None => return false,
+            };
+
+            return m
+                .to_str()
+                .map(|m| {
+                    m.split(',')
+                        .map(str::trim)
+                        .any(|m| m == "*" || m.trim_start_matches("W/") == etag)
+                })
+                .unwrap_or(false);
+        }
+
+        let since = request_headers
+            .get(IF_MODIFIED_SINCE)
+            .and_then(|m| m.to_str().ok())
+            .and_then(|m| httpdate::parse_http_date(m).ok());
+
+        match (self.last_modified, since) {
+            // Http dates have a precision of seconds.
+            (Some(last_modified), Some(since)) => {
+                let last_modified = last_modified.duration_since(UNIX_EPOCH).ok();
+                let since = since.duration_since(UNIX_EPOCH).ok();
+
+                matches!((last_modified, since), (Some(l), Some(r)) if l.as_secs() <= r.as_secs())
+            }
+            _ => false,
+        }
+    }
+
+    fn apply(&self, headers: &mut HeaderMap) {
+        if let Some(m) = self
+            .etag
+            .as_deref()
+            .and_then(|m| HeaderValue::from_str(m).ok())
+        {
+            headers.insert(ETAG, m);
+        }
+
+        if let Some(m) = self
+            .last_modified
+            .and_then(|m| HeaderValue::from_str(&httpdate::fmt_http_date(m)).ok())
+        {
+            headers.insert(LAST_MODIFIED, m);
+        }
+    }
+}
+
+/// How frontend assets are cached by browsers.
+#[derive(Debug, Clone)]
+pub(crate) struct CachePolicy {
+    pub hashed_max_age: Duration,
+    pub cache_control: HeaderValue,
+}
+
+impl Default for CachePolicy {
+    fn default() -> Self {
+        Self {
+            hashed_max_age: Duration::from_secs(365 * 24 * 60 * 60),
+            cache_control: HeaderValue::from_static("no-cache"),
+        }
+    }
+}
+
+impl CachePolicy {
+    /// Returns the cache control header of an asset.
+    fn cache_control(&self, path: &str) -> HeaderValue {
+        if !is_hashed_asset(path) {
+            return self.cache_control.clone();
+        }
+
+        HeaderValue::from_str(&format!(
+            "public, max-age={}, immutable",
+            self.hashed_max_age.as_secs()
+        ))
+        .expect("failed to create cache control header")
+    }
+
+    /// Creates a response for an asset, or a `304 Not Modified` response if the client already
+    /// has an up-to-date copy.
+    pub fn respond<F>(
+        &self,
+        path: &str,
+        validators: &Validators,
+        request_headers: &HeaderMap,
+        create_response: F,
+    ) -> Response
+    where
+        F: FnOnce() -> Response,
+    {
+        let mut resp = match validators.is_not_modified(request_headers) {
+            true => {
+                let mut resp = Response::default();
+                *resp.status_mut() = StatusCode::NOT_MODIFIED;
+
+                resp
+            }
+            false => create_response(),
+        };
+
+        let headers = resp.headers_mut();
+        validators.apply(headers);
+        headers.insert(CACHE_CONTROL, self.cache_control(path));
+        // A precompressed variant may be served depending on the accepted encodings.
+        headers.insert(VARY, HeaderValue::from_static("accept-encoding"));
+
+        resp
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matches_trunk_assets() {
+        for path in [
+            "/app-5a1c1f8e3e3fc1d2.js",
+            "/app-5a1c1f8e3e3fc1d2_bg.wasm",
+            "/main-f0e4c2f76c58916e.css",
+            "/nested/dir/app-c1f8e3e3.js",
+        ] {
+            assert!(is_hashed_asset(path), "{path} should be hashed");
+        }
+    }
+
+    #[test]
+    fn ignores_other_assets() {
+        for path in [
+            "/index.html",
+            "/app.js",
+            "/app-1.js",
+            "/app-5a1c1f8e3e3fc1d2f.js",
+            "/app-nothexnothexno.js",
+            "/app-5a1c1f8e3e3fc1d2_bg.js",
+            "/report-20231018.pdf",
+            "/logo-deadbeef.png",
+            "/app-5a1c1f8e3e3fc1d2.js.map",
+        ] {
+            assert!(!is_hashed_asset(path), "{path} should not be hashed");
+        }
+    }
+}
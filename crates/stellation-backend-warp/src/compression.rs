@@ -0,0 +1,142 @@
+//! Content encoding negotiation and response compression.
+
+use std::io::{self, Write};
+
+use bytes::Bytes;
+use http::header::{ACCEPT_ENCODING, CONTENT_ENCODING, CONTENT_TYPE, VARY};
+use http::{HeaderMap, HeaderValue};
+use warp::reply::Response;
+
+/// Responses smaller than this are not worth compressing.
+const MIN_COMPRESS_SIZE: usize = 1024;
+
+/// A content encoding supported by stellation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum ContentEncoding {
+    Brotli,
+    Gzip,
+}
+
+impl ContentEncoding {
+    /// Returns the name of the encoding used in http headers.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Brotli => "br",
+            Self::Gzip => "gzip",
+        }
+    }
+
+    /// Returns the file extension of a precompressed sibling file.
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Self::Brotli => "br",
+            Self::Gzip => "gz",
+        }
+    }
+
+    /// Returns the encodings accepted by the client, in the order of preference.
+    ///
+    /// Brotli is preferred over gzip if the client weights them equally.
+    pub fn negotiate(headers: &HeaderMap) -> Vec<Self> {
+        let mut brotli = None;
+        let mut gzip = None;
+        let mut wildcard = None;
+
+        for value in headers
+            .get_all(ACCEPT_ENCODING)
+            .iter()
+            .filter_map(|m| m.to_str().ok())
+            .flat_map(|m| m.split(','))
+        {
+            let mut parts = value.split(';').map(str::trim);
+            let name = parts.next().unwrap_or_default();
+            let quality = parts
+                .find_map(|m| m.strip_prefix("q="))
+                .and_then(|m| m.parse::<f32>().ok())
+                .unwrap_or(1.0);
+
+            match name.to_ascii_lowercase().as_str() {
+                "br" => brotli = Some(quality),
+                "gzip" | "x-gzip" => gzip = Some(quality),
+                "*" => wildcard = Some(quality),
+                _ => {}
+            }
+        }
+
+        let mut encodings = [
+            (Self::Brotli, brotli.or(wildcard).unwrap_or(0.0)),
+            (Self::Gzip, gzip.or(wildcard).unwrap_or(0.0)),
+        ]
+        .into_iter()
+        .filter(|(_, quality)| *quality > 0.0)
+        .collect::<Vec<_>>();
+
+        // Sorting is stable, so brotli remains in front if qualities are equal.
+        encodings.sort_by(|(_, l), (_, r)| r.total_cmp(l));
+
+        encodings.into_iter().map(|(m, _)| m).collect()
+    }
+
+    /// Compresses data with the current encoding.
+    pub fn encode(&self, data: &[u8]) -> io::Result<Vec<u8>> {
+        match self {
+            Self::Brotli => {
+                let mut output = Vec::new();
+                {
+                    // A moderate quality is used as responses are compressed on every request.
+                    let mut w = brotli::CompressorWriter::new(&mut output, 4096, 5, 22);
+                    w.write_all(data)?;
+                }
+
+                Ok(output)
+            }
+            Self::Gzip => {
+                let mut w =
+                    flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+                w.write_all(data)?;
+
+                w.finish()
+            }
+        }
+    }
+}
+
+/// Creates a response with the content compressed with the encoding preferred by the client.
+///
+/// The content is sent uncompressed if the client does not accept any supported encoding.
+pub(crate) fn compressed_response<B>(
+    content: B,
+    content_type: &'static str,
+    request_headers: &HeaderMap,
+) -> Response
+where
+    B: Into<Bytes>,
+{
+    let content = content.into();
+    let encoding = (content.len() >= MIN_COMPRESS_SIZE)
+        .then(|| ContentEncoding::negotiate(request_headers))
+        .and_then(|m| m.into_iter().next());
+
+    let mut resp = match encoding.map(|m| (m, m.encode(&content))) {
+        Some((encoding, Ok(m))) => {
+            let mut resp = Response::new(m.into());
+            resp.headers_mut().insert(
+                CONTENT_ENCODING,
+                HeaderValue::from_static(encoding.as_str()),
+            );
+
+            resp
+        }
+        Some((_, Err(e))) => {
+            tracing::warn!("failed to compress response: {:?}", e);
+            Response::new(content.into())
+        }
+        None => Response::new(content.into()),
+    };
+
+    let headers = resp.headers_mut();
+    headers.insert(CONTENT_TYPE, HeaderValue::from_static(content_type));
+    headers.insert(VARY, HeaderValue::from_static("accept-encoding"));
+
+    resp
+}
@@ -0,0 +1,261 @@
+//! Cross-site request forgery protection.
+
+use std::sync::Arc;
+
+use http::header::{COOKIE, HOST, ORIGIN, REFERER};
+use http::{HeaderMap, HeaderValue};
+use rand::Rng;
+
+const COOKIE_NAME: &str = "stellation-csrf";
+const HEADER_NAME: &str = "x-stellation-csrf-token";
+
+/// Settings of CSRF protection.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct CsrfProtection {
+    /// Whether tokens are issued to pages and verified for mutations.
+    pub verify_token: bool,
+    /// Whether the origin of mutations is checked.
+    pub verify_origin: bool,
+    /// Origins other than the host of the server that are allowed to send mutations.
+    pub trusted_origins: Arc<Vec<String>>,
+}
+
+impl CsrfProtection {
+    /// Returns `true` if a mutation sent with the bridge passes enabled checks.
+    pub fn verify_request(&self, headers: &HeaderMap) -> bool {
+        if self.verify_token && !verify_token(headers) {
+            return false;
+        }
+
+        !self.verify_origin || self.is_same_origin(headers)
+    }
+
+    /// Returns `true` if a form submitted to a page passes enabled checks.
+    ///
+    /// Forms cannot carry the token in a header, so their origin is checked instead.
+    pub fn verify_form(&self, headers: &HeaderMap) -> bool {
+        !(self.verify_token || self.verify_origin) || self.is_same_origin(headers)
+    }
+
+    /// Returns the token of the client, a new token is created if the client does not have one.
+    ///
+    /// A cookie is returned alongside a new token, which should be set on the response.
+    pub fn issue_token(
+        &self,
+        headers: &HeaderMap,
+        base_path: &str,
+    ) -> (String, Option<HeaderValue>) {
+        if let Some(m) = cookie_token(headers) {
+            return (m.to_owned(), None);
+        }
+
+        let token = new_token();
+        let cookie = HeaderValue::from_str(&format!(
+            "{COOKIE_NAME}={token}; Path={base_path}; SameSite=Lax; HttpOnly"
+        ))
+        .ok();
+
+        (token, cookie)
+    }
+
+    /// Returns `true` if the request is sent from the host of the server or a trusted origin.
+    ///
+    /// The referer is used if the origin is not sent by the client.
+    fn is_same_origin(&self, headers: &HeaderMap) -> bool {
+        let origin = headers
+            .get(ORIGIN)
+            .and_then(|m| m.to_str().ok())
+            .filter(|m| *m != "null")
+            .or_else(|| headers.get(REFERER).and_then(|m| m.to_str().ok()))
+            .and_then(|m| {
+                // Removes the path of a referer.
+                let host_end = m.find("://")? + 3;
+                let path_start = m[host_end..].find('/').map(|i| i + host_end);
+
+                Some(&m[..path_start.unwrap_or(m.len())])
+            });
+
+        let origin = match origin {
+            Some(m) => m,
+            None => return false,
+        };
+
+        if self.trusted_origins.iter().any(|m| m == origin) {
+            return true;
+        }
+
+        let host = headers.get(HOST).and_then(|m| m.to_str().ok());
+
+        origin
+            .split_once("://")
+            .map(|(_, m)| Some(m) == host)
+            .unwrap_or(false)
+    }
+}
+
+/// Creates a token with 128 bits of randomness.
+fn new_token() -> String {
+    let token: u128 = rand::thread_rng().gen();
+
+    format!("{token:032x}")
+}
+
+/// Returns the token stored in the cookie of the client.
+fn cookie_token(headers: &HeaderMap) -> Option<&str> {
+    headers
+        .get_all(COOKIE)
+        .iter()
+        .filter_map(|m| m.to_str().ok())
+        .flat_map(|m| m.split(';'))
+        .filter_map(|m| m.trim().split_once('='))
+        .find(|(name, _)| *name == COOKIE_NAME)
+        .map(|(_, value)| value)
+        // Tokens are always created by the server, other values are ignored.
+        .filter(|m| m.len() == 32 && m.bytes().all(|m| m.is_ascii_hexdigit()))
+}
+
+/// Returns `true` if the token sent with the request matches the token in the cookie.
+fn verify_token(headers: &HeaderMap) -> bool {
+    let expected = match cookie_token(headers) {
+        Some(m) => m,
+        None => return false,
+    };
+
+    let token = match headers.get(HEADER_NAME) {
+        Some(m) => m.as_bytes(),
+        None => return false,
+    };
+
+    // Compares in constant time, so the token cannot be guessed from the response time.
+    token.len() == expected.len()
+        && token
+            .iter()
+            .zip(expected.bytes())
+            .fold(0, |acc, (l, r)| acc | (l ^ r))
+            == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TOKEN: &str = "0123456789abcdef0123456789abcdef";
+
+    fn headers(entries: &[(&'static str, &str)]) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        for (name, value) in entries {
+            headers.append(*name, HeaderValue::from_str(value).unwrap());
+        }
+
+        headers
+    }
+
+    fn protection(verify_token: bool, verify_origin: bool) -> CsrfProtection {
+        CsrfProtection {
+            verify_token,
+            verify_origin,
+            trusted_origins: Arc::new(vec!["https://trusted.example".to_string()]),
+        }
+    }
+
+    #[test]
+    fn verifies_token() {
+        let csrf = protection(true, false);
+        let cookie = format!("theme=dark; {COOKIE_NAME}={TOKEN}");
+
+        assert!(csrf.verify_request(&headers(&[("cookie", &cookie), (HEADER_NAME, TOKEN)])));
+
+        let other = "fedcba9876543210fedcba9876543210";
+        assert!(!csrf.verify_request(&headers(&[("cookie", &cookie), (HEADER_NAME, other)])));
+        assert!(!csrf.verify_request(&headers(&[("cookie", &cookie), (HEADER_NAME, "0123")])));
+        assert!(!csrf.verify_request(&headers(&[("cookie", &cookie)])));
+        assert!(!csrf.verify_request(&headers(&[(HEADER_NAME, TOKEN)])));
+
+        // Cookies that are not created by the server are ignored.
+        let cookie = format!("{COOKIE_NAME}=attacker");
+        assert!(!csrf.verify_request(&headers(&[("cookie", &cookie), (HEADER_NAME, "attacker")])));
+
+        assert!(protection(false, false).verify_request(&headers(&[])));
+    }
+
+    #[test]
+    fn issues_token() {
+        let csrf = protection(true, false);
+
+        let (token, cookie) = csrf.issue_token(&headers(&[]), "/app");
+        assert_eq!(token.len(), 32);
+        let cookie = cookie.unwrap();
+        assert_eq!(
+            cookie.to_str().unwrap(),
+            format!("{COOKIE_NAME}={token}; Path=/app; SameSite=Lax; HttpOnly")
+        );
+
+        // The token is verified with the issued cookie.
+        let cookie = format!("{COOKIE_NAME}={token}");
+        let req = headers(&[("cookie", &cookie), (HEADER_NAME, &token)]);
+        assert!(csrf.verify_request(&req));
+
+        // An existing token is reused.
+        let (reused, cookie) = csrf.issue_token(&req, "/app");
+        assert_eq!(reused, token);
+        assert!(cookie.is_none());
+    }
+
+    #[test]
+    fn matches_origin() {
+        let csrf = protection(false, true);
+        let host = ("host", "example.com:8080");
+
+        assert!(csrf.verify_request(&headers(&[host, ("origin", "http://example.com:8080")])));
+        assert!(csrf.verify_request(&headers(&[host, ("origin", "https://trusted.example")])));
+
+        assert!(!csrf.verify_request(&headers(&[host, ("origin", "http://example.com")])));
+        assert!(!csrf.verify_request(&headers(&[host, ("origin", "http://evil.example")])));
+        assert!(!csrf.verify_request(&headers(&[("origin", "http://example.com:8080")])));
+        assert!(!csrf.verify_request(&headers(&[host])));
+    }
+
+    #[test]
+    fn matches_referer() {
+        let csrf = protection(false, true);
+        let host = ("host", "example.com");
+
+        assert!(csrf.verify_request(&headers(&[host, ("referer", "https://example.com/a/b?c")])));
+        assert!(csrf.verify_request(&headers(&[host, ("referer", "https://example.com")])));
+        assert!(csrf.verify_request(&headers(&[host, ("referer", "https://trusted.example/")])));
+
+        // The referer is used if the origin is opaque.
+        assert!(csrf.verify_request(&headers(&[
+            host,
+            ("origin", "null"),
+            ("referer", "https://example.com/"),
+        ])));
+        // The origin takes precedence over the referer.
+        assert!(!csrf.verify_request(&headers(&[
+            host,
+            ("origin", "https://evil.example"),
+            ("referer", "https://example.com/"),
+        ])));
+
+        assert!(!csrf.verify_request(&headers(&[
+            host,
+            ("referer", "https://example.com.evil.example/")
+        ])));
+        assert!(!csrf.verify_request(&headers(&[host, ("referer", "example.com")])));
+    }
+
+    #[test]
+    fn verifies_form_origin() {
+        let host = ("host", "example.com");
+        let same_origin = headers(&[host, ("origin", "https://example.com")]);
+        let cross_origin = headers(&[host, ("origin", "https://evil.example")]);
+
+        // Forms are checked by origin if either check is enabled.
+        for csrf in [protection(true, false), protection(false, true)] {
+            assert!(csrf.verify_form(&same_origin));
+            assert!(!csrf.verify_form(&cross_origin));
+        }
+
+        assert!(protection(false, false).verify_form(&cross_origin));
+    }
+}
@@ -1,29 +1,53 @@
 use core::fmt;
 use std::future::Future;
+use std::io;
 use std::marker::PhantomData;
+use std::net::IpAddr;
 use std::ops::Deref;
+use std::panic::AssertUnwindSafe;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
 
-use bytes::Bytes;
-use futures::future::LocalBoxFuture;
-use futures::{FutureExt, SinkExt, StreamExt};
+use bytes::{Buf, Bytes};
+use futures::channel::mpsc;
+use futures::future::{self, LocalBoxFuture};
+use futures::{FutureExt, SinkExt, Stream, StreamExt};
+use http::header::{CACHE_CONTROL, CONTENT_LENGTH, CONTENT_TYPE, RETRY_AFTER, SET_COOKIE};
 use http::status::StatusCode;
+use http::{HeaderMap, HeaderValue};
+use hyper::Body;
 use stellation_backend::utils::ThreadLocalLazy;
-use stellation_backend::{ServerAppProps, ServerRenderer};
+use stellation_backend::{RenderRequest, Request, ServerAppProps, ServerRenderer};
 use stellation_bridge::links::{Link, PhantomLink};
-use stellation_bridge::{Bridge, BridgeError};
-use tokio::sync::oneshot as sync_oneshot;
+use stellation_bridge::registry::StreamOutput;
+use stellation_bridge::routines::{ByteStream, FormSubmission};
+use stellation_bridge::{Bridge, BridgeError, Identity};
+use stellation_core::base_path;
+use tokio::sync::{oneshot as sync_oneshot, OwnedSemaphorePermit, Semaphore};
 use warp::body::bytes;
+use warp::filters::BoxedFilter;
 use warp::reply::Response;
-use warp::ws::{Message, Ws};
+use warp::ws::Ws;
 use warp::{header, log, reply, Filter, Rejection, Reply};
-use yew::platform::{LocalHandle, Runtime};
+use yew::platform::time::sleep;
+use yew::platform::Runtime;
 use yew::prelude::*;
+use yew_router::Routable;
 
-use crate::filters::{reject, warp_render_request, warp_request};
+use crate::compression::compressed_response;
+use crate::csrf::CsrfProtection;
+use crate::filters::{reject, warp_render_request, warp_request, Authenticate};
 use crate::frontend::Frontend;
+use crate::html::{self, DEFAULT_ERROR_PAGE};
+use crate::proxy::TrustedProxies;
+use crate::refresh::refresh_message;
 use crate::request::WarpRenderRequest;
-use crate::utils::spawn_pinned_or_local;
-use crate::{WarpRequest, SERVER_ID};
+use crate::routes::{self, RenderMode, RouteTable};
+use crate::runtime::{TaskMetrics, TaskSpawner};
+use crate::static_pages::{StaticPages, DEFAULT_MAX_STATIC_PAGES};
+use crate::utils::{panic_message, random_str};
+use crate::WarpRequest;
 
 type BoxedSendFn<IN, OUT> = Box<dyn Send + Fn(IN) -> LocalBoxFuture<'static, OUT>>;
 type SendFn<IN, OUT> = ThreadLocalLazy<BoxedSendFn<IN, OUT>>;
@@ -33,6 +57,22 @@ type CreateBridge<L> = SendFn<WarpRequest<()>, Bridge<L>>;
 
 type RenderIndex = SendFn<WarpRenderRequest<()>, String>;
 
+type MatchRoute = fn(&WarpRenderRequest<()>) -> bool;
+
+const HTML_CONTENT_TYPE: &str = "text/html; charset=utf-8";
+const BINCODE_CONTENT_TYPE: &str = "application/x-bincode";
+const STREAM_CONTENT_TYPE: &str = "application/octet-stream";
+const FORM_CONTENT_TYPE: &str = "application/x-www-form-urlencoded";
+
+/// The default size limit of bridge requests and submitted forms.
+const DEFAULT_MAX_BODY_SIZE: u64 = 2 * 1024 * 1024;
+
+/// The default size limit of uploads.
+const DEFAULT_MAX_UPLOAD_SIZE: u64 = 2 * 1024 * 1024;
+
+/// The name of the field that identifies the mutation of a submitted form.
+const MUTATION_INDEX_FIELD: &str = "_stellation_mutation";
+
 /// Creates a stellation endpoint that can be turned into a warp filter.
 ///
 /// This endpoint serves bridge requests and frontend requests.
@@ -46,6 +86,22 @@ where
     append_context: AppendContext<CTX>,
     create_bridge: Option<CreateBridge<L>>,
     auto_refresh: bool,
+    build_status_file: Option<Arc<Path>>,
+    compression: bool,
+    base_path: Arc<str>,
+    error_page: Arc<str>,
+    client_only_fallback: bool,
+    render_timeout: Option<Duration>,
+    max_concurrent_renders: Option<usize>,
+    spawner: TaskSpawner,
+    routes: RouteTable,
+    is_route_matched: Option<MatchRoute>,
+    max_upload_size: u64,
+    max_body_size: u64,
+    max_static_pages: usize,
+    csrf: CsrfProtection,
+    authenticate: Option<Authenticate>,
+    trusted_proxies: TrustedProxies,
     _marker: PhantomData<COMP>,
 }
 
@@ -85,6 +141,22 @@ where
             frontend: None,
             create_bridge: None,
             auto_refresh: false,
+            build_status_file: None,
+            compression: false,
+            base_path: base_path::build_default().into(),
+            error_page: DEFAULT_ERROR_PAGE.into(),
+            client_only_fallback: false,
+            render_timeout: None,
+            max_concurrent_renders: None,
+            spawner: TaskSpawner::default(),
+            routes: RouteTable::default(),
+            is_route_matched: None,
+            max_upload_size: DEFAULT_MAX_UPLOAD_SIZE,
+            max_body_size: DEFAULT_MAX_BODY_SIZE,
+            max_static_pages: DEFAULT_MAX_STATIC_PAGES,
+            csrf: CsrfProtection::default(),
+            authenticate: None,
+            trusted_proxies: TrustedProxies::default(),
             _marker: PhantomData,
         }
     }
@@ -110,6 +182,22 @@ where
             frontend: self.frontend,
             create_bridge: self.create_bridge,
             auto_refresh: self.auto_refresh,
+            build_status_file: self.build_status_file,
+            compression: self.compression,
+            base_path: self.base_path,
+            error_page: self.error_page,
+            client_only_fallback: self.client_only_fallback,
+            render_timeout: self.render_timeout,
+            max_concurrent_renders: self.max_concurrent_renders,
+            spawner: self.spawner,
+            routes: self.routes,
+            is_route_matched: self.is_route_matched,
+            max_upload_size: self.max_upload_size,
+            max_body_size: self.max_body_size,
+            max_static_pages: self.max_static_pages,
+            csrf: self.csrf,
+            authenticate: self.authenticate,
+            trusted_proxies: self.trusted_proxies,
             _marker: PhantomData,
         }
     }
@@ -123,6 +211,255 @@ where
         self
     }
 
+    /// Sets a file that contains the status of the latest build of the development server.
+    ///
+    /// The status is displayed in pages opened in browsers, with the error of a failed build
+    /// displayed over the page. This requires auto refresh to be enabled.
+    pub fn with_build_status_file<P>(mut self, path: P) -> Self
+    where
+        P: Into<PathBuf>,
+    {
+        self.build_status_file = Some(path.into().into());
+
+        self
+    }
+
+    /// Enables response compression.
+    ///
+    /// Rendered pages and bridge responses are compressed with gzip or brotli if the client
+    /// accepts it.
+    ///
+    /// Static assets are not compressed at runtime, precompressed `.br` and `.gz` files next to
+    /// the original files are served instead, if available.
+    pub fn with_compression(mut self) -> Self {
+        self.compression = true;
+
+        self
+    }
+
+    /// Mounts the application under a path, e.g.: `/app/`.
+    ///
+    /// The frontend, the bridge and auto refresh are served under this path and the router of the
+    /// application uses this path as its basename.
+    ///
+    /// The frontend needs to be built with the same path as its public url.
+    ///
+    /// Defaults to the base path in `stellation.toml` for backends built by `stctl build`, `/`
+    /// otherwise.
+    pub fn with_base_path<S>(mut self, base_path: S) -> Self
+    where
+        S: AsRef<str>,
+    {
+        self.base_path = base_path::normalize(base_path.as_ref()).into();
+
+        self
+    }
+
+    /// Sets the page served when a page fails to render.
+    ///
+    /// The page is served with a `500 Internal Server Error` status if a component panics during
+    /// server-side rendering or the template cannot be loaded.
+    ///
+    /// Panics can only be recovered if they unwind. With `panic = "abort"` in the build profile,
+    /// a panic during rendering terminates the server instead.
+    pub fn with_error_page<S>(mut self, html: S) -> Self
+    where
+        S: AsRef<str>,
+    {
+        self.error_page = html.as_ref().into();
+
+        self
+    }
+
+    /// Renders the page at the client side if server-side rendering fails.
+    ///
+    /// The error page is served if the page fails to render at the client side as well. Panics are
+    /// only recovered if they unwind, see [`with_error_page`](Self::with_error_page).
+    pub fn with_client_only_fallback(mut self) -> Self {
+        self.client_only_fallback = true;
+
+        self
+    }
+
+    /// Sets the time limit of server-side rendering.
+    ///
+    /// Renders that do not complete within this time are cancelled and the page is rendered at the
+    /// client side instead.
+    pub fn with_render_timeout(mut self, timeout: Duration) -> Self {
+        self.render_timeout = Some(timeout);
+
+        self
+    }
+
+    /// Limits the number of pages that can be rendered at the server side at the same time.
+    ///
+    /// Requests exceeding this limit are rendered at the client side instead of waiting for other
+    /// renders to complete.
+    pub fn with_max_concurrent_renders(mut self, max_concurrent_renders: usize) -> Self {
+        self.max_concurrent_renders = Some(max_concurrent_renders);
+
+        self
+    }
+
+    /// Sets the runtime that pages are rendered and bridge requests are resolved on.
+    ///
+    /// The number of workers of the runtime can be set with
+    /// [`Runtime::builder`](yew::platform::Runtime::builder), so rendering can be sized separately
+    /// from the threads that process network I/O.
+    ///
+    /// If no runtime is set, tasks are spawned onto the current worker thread or the default
+    /// runtime.
+    ///
+    /// The runtime names its worker threads `prokio-runtime-worker`, which cannot be configured.
+    pub fn with_runtime(mut self, runtime: Runtime) -> Self {
+        self.spawner = self.spawner.with_runtime(runtime);
+
+        self
+    }
+
+    /// Returns the metrics of tasks spawned by the current endpoint.
+    ///
+    /// The returned handle keeps updating after the endpoint is turned into a filter.
+    pub fn task_metrics(&self) -> TaskMetrics {
+        self.spawner.metrics()
+    }
+
+    /// Sets how pages with paths matching a pattern are rendered.
+    ///
+    /// Patterns are matched against the path relative to the base path, e.g.: `/admin/**`.
+    /// `*` matches a single segment and `**` at the end of a pattern matches all remaining
+    /// segments. Patterns are matched in the order they are added and the first match is used.
+    ///
+    /// Pages that do not match any pattern are rendered at the server side.
+    pub fn with_render_mode<P>(mut self, pattern: P, mode: RenderMode) -> Self
+    where
+        P: AsRef<str>,
+    {
+        self.routes.push(pattern.as_ref(), mode);
+
+        self
+    }
+
+    /// Matches requests against the routes of a routable before rendering.
+    ///
+    /// Requests that do not match any route are responded with `404 Not Found` without being
+    /// rendered. The matched route is available with [`Request::route`] to contexts, bridges and
+    /// the application.
+    pub fn with_routable<R>(mut self) -> Self
+    where
+        R: Routable,
+    {
+        self.is_route_matched = Some(|req| req.route::<R>().is_some());
+
+        self
+    }
+
+    /// Limits the size of uploads in bytes.
+    ///
+    /// Uploads exceeding the limit are responded with `413 Payload Too Large`. If the size of an
+    /// upload is not known in advance, the body stops with an error once the limit is exceeded.
+    ///
+    /// Defaults to 2 MiB.
+    pub fn with_max_upload_size(mut self, max_upload_size: u64) -> Self {
+        self.max_upload_size = max_upload_size;
+
+        self
+    }
+
+    /// Limits the size of bridge requests and submitted forms in bytes.
+    ///
+    /// Requests exceeding the limit or without a known length are rejected before their body is
+    /// read. Uploads are limited with [`with_max_upload_size`](Self::with_max_upload_size) instead.
+    ///
+    /// Defaults to 2 MiB.
+    pub fn with_max_body_size(mut self, max_body_size: u64) -> Self {
+        self.max_body_size = max_body_size;
+
+        self
+    }
+
+    /// Limits the number of pages rendered with [`RenderMode::Static`] that are kept in memory.
+    ///
+    /// The least recently served page is removed when the limit is reached, and rendered again when
+    /// it is requested. Setting the limit to `0` renders static pages for every request.
+    ///
+    /// Defaults to 1024 pages.
+    pub fn with_max_static_pages(mut self, max_static_pages: usize) -> Self {
+        self.max_static_pages = max_static_pages;
+
+        self
+    }
+
+    /// Authenticates the client of each request.
+    ///
+    /// The authenticator runs once per request before contexts and bridges are created, e.g.: to
+    /// verify a bearer token or to look up a session with a cookie. The identity it returns is
+    /// available with [`Request::identity`] to contexts, bridges and the application. Clients are
+    /// treated as anonymous if `None` is returned.
+    pub fn with_authenticator<F, Fut>(mut self, authenticate: F) -> Self
+    where
+        F: 'static + Send + Sync + Fn(WarpRequest<()>) -> Fut,
+        Fut: 'static + Send + Future<Output = Option<Identity>>,
+    {
+        self.authenticate = Some(Arc::new(move |req| authenticate(req).boxed()));
+
+        self
+    }
+
+    /// Enables CSRF protection with tokens.
+    ///
+    /// A token is issued to each client with a cookie and appended to the head of rendered pages,
+    /// which is attached to bridge requests by `FetchLink`. Mutations, uploads and downloads
+    /// without a matching token are responded with `403 Forbidden`.
+    ///
+    /// Forms submitted before the application is loaded cannot carry the token, their origin is
+    /// checked instead.
+    pub fn with_csrf_protection(mut self) -> Self {
+        self.csrf.verify_token = true;
+
+        self
+    }
+
+    /// Checks the `Origin` or `Referer` header of mutations, uploads, downloads and submitted forms.
+    ///
+    /// Requests that are not sent from the host of the server or a trusted origin are responded
+    /// with `403 Forbidden`.
+    pub fn with_origin_check(mut self) -> Self {
+        self.csrf.verify_origin = true;
+
+        self
+    }
+
+    /// Trusts an origin other than the host of the server, e.g.: `https://example.com`.
+    ///
+    /// This is used by CSRF protection and origin check.
+    pub fn with_trusted_origin<S>(mut self, origin: S) -> Self
+    where
+        S: Into<String>,
+    {
+        Arc::make_mut(&mut self.csrf.trusted_origins).push(origin.into());
+
+        self
+    }
+
+    /// Trusts a reverse proxy to forward the address of clients.
+    ///
+    /// The address of clients is read from the `Forwarded` or `X-Forwarded-For` header of requests
+    /// sent by a trusted proxy, which is available with [`WarpRequest::remote_addr`] and used to
+    /// apply rate limits to anonymous clients. Without a trusted proxy, all clients behind a proxy
+    /// have the address of the proxy.
+    ///
+    /// Only trust proxies that set or append to these headers, otherwise clients can forge their
+    /// address.
+    pub fn with_trusted_proxy<A>(mut self, addr: A) -> Self
+    where
+        A: Into<IpAddr>,
+    {
+        self.trusted_proxies.push(addr.into());
+
+        self
+    }
+
     /// Serves a frontend with current endpoint.
     pub fn with_frontend(mut self, frontend: Frontend) -> Self {
         self.frontend = Some(frontend);
@@ -145,6 +482,22 @@ where
                 Box::new(move |input| create_bridge(input).boxed_local())
             })),
             auto_refresh: self.auto_refresh,
+            build_status_file: self.build_status_file,
+            compression: self.compression,
+            base_path: self.base_path,
+            error_page: self.error_page,
+            client_only_fallback: self.client_only_fallback,
+            render_timeout: self.render_timeout,
+            max_concurrent_renders: self.max_concurrent_renders,
+            spawner: self.spawner,
+            routes: self.routes,
+            is_route_matched: self.is_route_matched,
+            max_upload_size: self.max_upload_size,
+            max_body_size: self.max_body_size,
+            max_static_pages: self.max_static_pages,
+            csrf: self.csrf,
+            authenticate: self.authenticate,
+            trusted_proxies: self.trusted_proxies,
             _marker: PhantomData,
         }
     }
@@ -196,99 +549,166 @@ where
         }
     }
 
-    fn create_index_filter(
-        &self,
-    ) -> Option<
-        impl Clone
-            + Send
-            + Filter<
-                Extract = (Response,),
-                Error = Rejection,
-                Future = impl Future<Output = Result<(Response,), Rejection>>,
-            >,
-    > {
-        let render_index = self.create_render_index();
-        let index_html = self.frontend.as_ref()?.index_html();
-        let auto_refresh = self.auto_refresh;
-
-        let f = warp::get()
-            .and(warp_render_request(index_html, auto_refresh))
-            .then(move |req: WarpRenderRequest<()>| {
-                let render_index = render_index.clone();
+    fn create_page_service(&self) -> Option<PageService> {
+        let frontend = self.frontend.as_ref()?;
+
+        Some(PageService {
+            render_index: self.create_render_index(),
+            cache_control: frontend.html_cache_control(),
+            compression: self.compression,
+            base_path: self.base_path.clone(),
+            error_page: self.error_page.clone(),
+            render_options: RenderOptions {
+                client_only_fallback: self.client_only_fallback,
+                timeout: self.render_timeout,
+                admission: self
+                    .max_concurrent_renders
+                    .map(|m| Arc::new(Semaphore::new(m))),
+                spawner: self.spawner.clone(),
+            },
+            routes: Arc::new(self.routes.clone()),
+            is_route_matched: self.is_route_matched,
+            static_pages: Arc::new(StaticPages::new(self.max_static_pages)),
+            csrf: self.csrf.clone(),
+        })
+    }
+
+    fn create_index_filter(&self) -> Option<BoxedFilter<(Response,)>> {
+        let frontend = self.frontend.as_ref()?;
+        let index_html = frontend.index_html();
+        let pages = self.create_page_service()?;
+
+        let page_f = {
+            let pages = pages.clone();
+
+            warp::get()
+                .and(warp_render_request(
+                    index_html.clone(),
+                    self.auto_refresh,
+                    self.base_path.clone(),
+                    self.authenticate.clone(),
+                    self.trusted_proxies.clone(),
+                ))
+                .and_then(move |req: io::Result<WarpRenderRequest<()>>| pages.clone().serve(req))
+        };
+
+        let create_bridge = match self.create_bridge.clone() {
+            Some(m) => m,
+            None => return Some(page_f.boxed()),
+        };
+        let spawner = self.spawner.clone();
+
+        // Forms are submitted to the page before the application is loaded.
+        let form_f = warp::post()
+            .and(header::exact_ignore_case("content-type", FORM_CONTENT_TYPE))
+            .and(warp_render_request(
+                index_html,
+                self.auto_refresh,
+                self.base_path.clone(),
+                self.authenticate.clone(),
+                self.trusted_proxies.clone(),
+            ))
+            .and(warp::body::content_length_limit(self.max_body_size))
+            .and(bytes())
+            .and_then(move |req: io::Result<WarpRenderRequest<()>>, form: Bytes| {
+                let pages = pages.clone();
+                let create_bridge = create_bridge.clone();
+                let spawner = spawner.clone();
 
                 async move {
-                    let (tx, rx) = sync_oneshot::channel::<String>();
-                    spawn_pinned_or_local(move || async move {
-                        let s = render_index(req).await;
+                    let req = match req {
+                        Ok(m) => m,
+                        Err(e) => return pages.serve(Err(e)).await,
+                    };
 
-                        let _ = tx.send(s);
-                    });
+                    if !pages.csrf.verify_form(req.headers()) {
+                        return Ok(reply::with_status("", StatusCode::FORBIDDEN).into_response());
+                    }
 
-                    warp::reply::html(rx.await.expect("renderer panicked?")).into_response()
+                    // Forms without a mutation are not submitted by the application.
+                    let (mutation_index, form) = match parse_form(&form) {
+                        Ok(m) => m,
+                        Err(e) => {
+                            tracing::warn!(path = req.path(), "failed to parse form: {}", e);
+                            return Ok(bridge_error_response(&e, req.headers()));
+                        }
+                    };
+
+                    match submit_form(&spawner, create_bridge, &req, mutation_index, form).await {
+                        Ok(m) => pages.serve(Ok(req.with_form_submission(m))).await,
+                        Err(e) => pages.serve_form_error(req, mutation_index, e).await,
+                    }
                 }
             });
 
-        Some(f)
+        Some(page_f.or(form_f).unify().boxed())
     }
 
     fn create_refresh_filter(
         &self,
     ) -> impl Clone + Send + Filter<Extract = (Response,), Error = Rejection> {
+        let spawner = self.spawner.clone();
+        let index_html = self.frontend.as_ref().map(|m| m.index_html());
+        let build_status_file = self.build_status_file.clone();
+
         warp::path::path("_refresh")
             .and(warp::ws())
-            .then(|m: Ws| async move {
-                m.on_upgrade(|mut ws| async move {
-                    let read_refresh = {
-                        || async move {
-                            while let Some(m) = ws.next().await {
-                                let m = match m {
-                                    Ok(m) => m,
-                                    Err(e) => {
-                                        tracing::error!("receive message error: {:?}", e);
-
-                                        if let Err(e) = ws.close().await {
-                                            tracing::error!("failed to close websocket: {:?}", e);
+            .then(move |m: Ws| {
+                let spawner = spawner.clone();
+                let index_html = index_html.clone();
+                let build_status_file = build_status_file.clone();
+                async move {
+                    m.on_upgrade(move |mut ws| async move {
+                        let read_refresh = {
+                            || async move {
+                                while let Some(m) = ws.next().await {
+                                    let m = match m {
+                                        Ok(m) => m,
+                                        Err(e) => {
+                                            tracing::error!("receive message error: {:?}", e);
+
+                                            if let Err(e) = ws.close().await {
+                                                tracing::error!(
+                                                    "failed to close websocket: {:?}",
+                                                    e
+                                                );
+                                            }
+
+                                            return;
                                         }
+                                    };
 
-                                        return;
+                                    if m.is_ping() || m.is_pong() {
+                                        continue;
                                     }
-                                };
 
-                                if m.is_ping() || m.is_pong() {
-                                    continue;
-                                }
+                                    let m = match m.to_str() {
+                                        Ok(m) => m,
+                                        Err(_) => {
+                                            tracing::error!("received unknown message: {:?}", m);
+                                            return;
+                                        }
+                                    };
+
+                                    let message_to_send = refresh_message(
+                                        m,
+                                        index_html.as_ref(),
+                                        build_status_file.as_deref(),
+                                    )
+                                    .await;
 
-                                let m = match m.to_str() {
-                                    Ok(m) => m,
-                                    Err(_) => {
-                                        tracing::error!("received unknown message: {:?}", m);
+                                    if let Err(e) = ws.send(message_to_send).await {
+                                        tracing::error!("error sending message: {:?}", e);
                                         return;
                                     }
-                                };
-
-                                // Ping client if string matches.
-                                // Otherwise, tell the client to reload the page.
-                                let message_to_send = if m == SERVER_ID.as_str() {
-                                    Message::ping("")
-                                } else {
-                                    Message::text("restart")
-                                };
-
-                                if let Err(e) = ws.send(message_to_send).await {
-                                    tracing::error!("error sending message: {:?}", e);
-                                    return;
                                 }
                             }
-                        }
-                    };
+                        };
 
-                    match LocalHandle::try_current() {
-                        Some(handle) => handle.spawn_local(read_refresh()),
-                        // TODO: Allow Overriding Runtime with Endpoint.
-                        None => Runtime::default().spawn_pinned(read_refresh),
-                    }
-                })
-                .into_response()
+                        spawner.spawn_pinned_or_local(read_refresh);
+                    })
+                    .into_response()
+                }
             })
     }
 
@@ -296,47 +716,150 @@ where
         &self,
     ) -> Option<impl Clone + Send + Filter<Extract = (Response,), Error = Rejection>> {
         let create_bridge = self.create_bridge.clone()?;
+        let compression = self.compression;
+        let spawner = self.spawner.clone();
+        let csrf = self.csrf.clone();
 
         let http_bridge_f = warp::post()
             .and(header::exact_ignore_case(
                 "content-type",
-                "application/x-bincode",
+                BINCODE_CONTENT_TYPE,
+            ))
+            .and(warp_request(
+                self.base_path.clone(),
+                self.authenticate.clone(),
+                self.trusted_proxies.clone(),
             ))
-            .and(warp_request())
+            .and(warp::body::content_length_limit(self.max_body_size))
             .and(bytes())
             .then(move |req: WarpRequest<()>, input: Bytes| {
                 let create_bridge = create_bridge.clone();
+                let csrf = csrf.clone();
+                let request_headers = req.headers().clone();
 
                 let (tx, rx) = sync_oneshot::channel();
 
                 let resolve_encoded = move || async move {
                     let bridge = create_bridge(req).await;
 
+                    // Requests that cannot be decoded are verified as mutations.
+                    let is_mutation = bridge.link().is_mutation(&input).unwrap_or(true);
+                    if is_mutation && !csrf.verify_request(&request_headers) {
+                        let _ =
+                            tx.send(reply::with_status("", StatusCode::FORBIDDEN).into_response());
+                        return;
+                    }
+
                     let content = bridge.link().resolve_encoded(&input).await;
 
                     let reply = match content {
-                        Ok(m) => reply::with_header(m, "content-type", "application/x-bincode")
-                            .into_response(),
-                        Err(BridgeError::Encoding(_))
-                        | Err(BridgeError::InvalidIndex(_))
-                        | Err(BridgeError::InvalidType(_)) => {
-                            reply::with_status("", StatusCode::BAD_REQUEST).into_response()
-                        }
-                        Err(BridgeError::Network(_)) => {
-                            reply::with_status("", StatusCode::INTERNAL_SERVER_ERROR)
-                                .into_response()
-                        }
+                        Ok(m) => bincode_response(m, compression, &request_headers),
+                        Err(e) => bridge_error_response(&e, &request_headers),
                     };
 
                     let _ = tx.send(reply);
                 };
 
-                spawn_pinned_or_local(resolve_encoded);
+                spawner.spawn_pinned_or_local(resolve_encoded);
 
                 async move { rx.await.expect("failed to resolve the bridge request") }
             });
 
-        Some(warp::path::path("_bridge").and(http_bridge_f))
+        Some(
+            warp::path::path("_bridge")
+                .and(warp::path::end())
+                .and(http_bridge_f),
+        )
+    }
+
+    fn create_stream_filter(
+        &self,
+    ) -> Option<impl Clone + Send + Filter<Extract = (Response,), Error = Rejection>> {
+        let create_bridge = self.create_bridge.clone()?;
+        let compression = self.compression;
+        let spawner = self.spawner.clone();
+        let max_upload_size = self.max_upload_size;
+        let csrf = self.csrf.clone();
+
+        let stream_f = warp::post()
+            .and(header::exact_ignore_case(
+                "content-type",
+                STREAM_CONTENT_TYPE,
+            ))
+            .and(warp_request(
+                self.base_path.clone(),
+                self.authenticate.clone(),
+                self.trusted_proxies.clone(),
+            ))
+            .and(warp::body::stream())
+            .then(move |req: WarpRequest<()>, body| {
+                let create_bridge = create_bridge.clone();
+                let request_headers = req.headers().clone();
+
+                let (tx, rx) = sync_oneshot::channel();
+
+                let content_length = request_headers
+                    .get(CONTENT_LENGTH)
+                    .and_then(|m| m.to_str().ok())
+                    .and_then(|m| m.parse::<u64>().ok());
+                if !csrf.verify_request(&request_headers) {
+                    let _ = tx.send(reply::with_status("", StatusCode::FORBIDDEN).into_response());
+                } else if content_length > Some(max_upload_size) {
+                    let _ = tx.send(
+                        reply::with_status(
+                            "",
+                            bridge_error_status(&BridgeError::TooLarge(max_upload_size)),
+                        )
+                        .into_response(),
+                    );
+                } else {
+                    let resolve_stream = move || async move {
+                        let bridge = create_bridge(req).await;
+
+                        let body = limit_body(body, max_upload_size);
+                        let content = bridge.link().resolve_stream(body).await;
+
+                        let mut stream = match content {
+                            Ok(StreamOutput::Stream(m)) => m,
+                            Ok(StreamOutput::Encoded(m)) => {
+                                let _ = tx.send(bincode_response(m, compression, &request_headers));
+                                return;
+                            }
+                            Err(e) => {
+                                let _ = tx.send(bridge_error_response(&e, &request_headers));
+                                return;
+                            }
+                        };
+
+                        // The stream is not Send, so it is forwarded to the response through a
+                        // channel.
+                        let (mut body_tx, body_rx) = mpsc::channel(1);
+                        let mut resp = Response::new(Body::wrap_stream(body_rx));
+                        resp.headers_mut()
+                            .insert(CONTENT_TYPE, HeaderValue::from_static(STREAM_CONTENT_TYPE));
+                        let _ = tx.send(resp);
+
+                        while let Some(m) = stream.next().await {
+                            let m =
+                                m.map_err(|e| io::Error::new(io::ErrorKind::Other, e.to_string()));
+                            if body_tx.send(m).await.is_err() {
+                                break;
+                            }
+                        }
+                    };
+
+                    spawner.spawn_pinned_or_local(resolve_stream);
+                }
+
+                async move { rx.await.expect("failed to resolve the stream request") }
+            });
+
+        Some(
+            warp::path::path("_bridge")
+                .and(warp::path::path("stream"))
+                .and(warp::path::end())
+                .and(stream_f),
+        )
     }
 
     /// Creates a warp filter from current endpoint.
@@ -346,8 +869,22 @@ where
         let index_html_f = self.create_index_filter();
         let auto_refresh_f = self.auto_refresh.then(|| self.create_refresh_filter());
         let bridge_f = self.create_bridge_filter();
+        let stream_f = self.create_stream_filter();
 
-        let Self { frontend, .. } = self;
+        let Self {
+            frontend,
+            base_path,
+            ..
+        } = self;
+
+        // Matches the segments of the base path.
+        let base_path_f = base_path
+            .split('/')
+            .filter(|m| !m.is_empty())
+            .map(String::from)
+            .fold(warp::any().boxed(), |last, item| {
+                last.and(warp::path(item)).boxed()
+            });
 
         let frontend_f = frontend.map(|m| m.into_warp_filter());
 
@@ -356,6 +893,7 @@ where
         bridge_f
             .map(|m| m.boxed())
             .into_iter()
+            .chain(stream_f.map(|m| m.boxed()))
             // Add auto refresh filter, if needed.
             .chain(auto_refresh_f.map(|m| m.boxed()))
             // Render "/" as index.html.
@@ -368,20 +906,509 @@ where
             // Serve other resources, if available.
             .chain(frontend_f.map(|m| m.boxed()))
             // Fallback to index.html.
-            .chain(index_html_f.map(|m| m.boxed()))
+            .chain(index_html_f)
             // Chain everything together with or.
             .fold(reject().boxed(), |last, item| last.or(item).unify().boxed());
 
-        routes.with(log::custom(|info| {
+        base_path_f.and(routes).with(log::custom(|info| {
+            // Requests without an id from an upstream proxy are assigned a random one.
+            let request_id = info
+                .request_headers()
+                .get("x-request-id")
+                .and_then(|m| m.to_str().ok())
+                .map(String::from)
+                .unwrap_or_else(random_str);
+
             // We emit a custom span so it won't interfere with warp's default tracing event.
             tracing::info!(target: "stellation_backend::endpoint::trace",
-                remote_addr = ?info.remote_addr(),
+                request_id = request_id.as_str(),
+                remote_addr = %info.remote_addr().map(|m| m.to_string()).unwrap_or_default(),
                 method = %info.method(),
                 path = info.path(),
                 status = info.status().as_u16(),
-                referer = ?info.referer(),
-                user_agent = ?info.user_agent(),
+                referer = info.referer().unwrap_or_default(),
+                user_agent = info.user_agent().unwrap_or_default(),
                 duration = info.elapsed().as_nanos());
         }))
     }
 }
+
+/// Serves pages rendered from the index html.
+#[derive(Clone)]
+struct PageService {
+    render_index: RenderIndex,
+    cache_control: HeaderValue,
+    compression: bool,
+    base_path: Arc<str>,
+    error_page: Arc<str>,
+    render_options: RenderOptions,
+    routes: Arc<RouteTable>,
+    is_route_matched: Option<MatchRoute>,
+    static_pages: Arc<StaticPages>,
+    csrf: CsrfProtection,
+}
+
+impl PageService {
+    /// Renders a page with the render mode of its route.
+    async fn render(&self, req: WarpRenderRequest<()>) -> Result<Option<RenderedPage>, ()> {
+        if self.is_route_matched.map(|m| !m(&req)).unwrap_or(false) {
+            return Ok(None);
+        }
+
+        // Routes are matched relative to the base path.
+        let path = routes::relative_path(&self.base_path, req.path());
+
+        let render_index = self.render_index.clone();
+        let render_options = self.render_options.clone();
+
+        let page = match self.routes.render_mode(path) {
+            RenderMode::NotFound => return Ok(None),
+            RenderMode::ServerSide => render_page(render_index, req, render_options).await,
+            RenderMode::ClientOnly => {
+                render_page(render_index, req.client_only(), render_options).await
+            }
+            // Pages with a submitted form are specific to the submission and are not stored.
+            RenderMode::Static if req.form_submission().is_some() => {
+                render_page(render_index, req, render_options).await
+            }
+            RenderMode::Static => {
+                render_static_page(render_index, req, render_options, &self.static_pages).await
+            }
+        };
+
+        page.map(Some)
+    }
+
+    /// Serves the page of a rejected form submission, which is rendered with the error.
+    ///
+    /// Errors that cannot be shown by the page are responded as bridge errors.
+    async fn serve_form_error(
+        self,
+        req: WarpRenderRequest<()>,
+        mutation_index: usize,
+        e: BridgeError,
+    ) -> Result<Response, Rejection> {
+        tracing::warn!(path = req.path(), "failed to resolve submitted form: {}", e);
+
+        let status = bridge_error_status(&e);
+        let retry_after = match e {
+            BridgeError::RateLimited(m) => Some(m),
+            _ => None,
+        };
+        let submission = match FormSubmission::from_error(mutation_index, e) {
+            Ok(m) => m,
+            Err(e) => return Ok(bridge_error_response(&e, req.headers())),
+        };
+
+        let mut resp = self.serve(Ok(req.with_form_submission(submission))).await?;
+        // The error page keeps its own status.
+        if resp.status().is_success() {
+            *resp.status_mut() = status;
+            if let Some(m) = retry_after {
+                insert_retry_after(&mut resp, m);
+            }
+        }
+
+        Ok(resp)
+    }
+
+    /// Serves a page, responding with the error page if it fails to render.
+    async fn serve(self, req: io::Result<WarpRenderRequest<()>>) -> Result<Response, Rejection> {
+        let mut csrf_cookie = None;
+
+        let html = match req {
+            Ok(req) => {
+                let request_headers = req.headers().clone();
+
+                match self.render(req).await {
+                    // The token is appended after rendering, so static pages can be shared by all
+                    // clients.
+                    Ok(Some(page)) if self.csrf.verify_token => {
+                        let (token, cookie) =
+                            self.csrf.issue_token(&request_headers, &self.base_path);
+                        csrf_cookie = cookie;
+
+                        Ok((html::add_csrf_token(&page.html, &token), request_headers))
+                    }
+                    Ok(Some(page)) => Ok((page.html, request_headers)),
+                    Ok(None) => return Err(warp::reject::not_found()),
+                    Err(()) => Err(()),
+                }
+            }
+            Err(e) => {
+                tracing::error!("failed to load index.html: {}", e);
+
+                Err(())
+            }
+        };
+
+        let mut resp = match html {
+            Ok((html, request_headers)) if self.compression => {
+                compressed_response(html, HTML_CONTENT_TYPE, &request_headers)
+            }
+            Ok((html, _)) => warp::reply::html(html).into_response(),
+            Err(_) => reply::with_status(
+                reply::html(self.error_page.to_string()),
+                StatusCode::INTERNAL_SERVER_ERROR,
+            )
+            .into_response(),
+        };
+        resp.headers_mut()
+            .insert(CACHE_CONTROL, self.cache_control.clone());
+        if let Some(m) = csrf_cookie {
+            resp.headers_mut().append(SET_COOKIE, m);
+        }
+
+        Ok(resp)
+    }
+}
+
+/// Returns the status code to respond with when a bridge request fails.
+fn bridge_error_status(e: &BridgeError) -> StatusCode {
+    match e {
+        BridgeError::Encoding(_)
+        | BridgeError::InvalidForm(_)
+        | BridgeError::InvalidIndex(_)
+        | BridgeError::InvalidType(_)
+        | BridgeError::Body(_)
+        | BridgeError::Unsupported
+        | BridgeError::Validation(_) => StatusCode::BAD_REQUEST,
+        BridgeError::TooLarge(_) => StatusCode::PAYLOAD_TOO_LARGE,
+        BridgeError::Unauthenticated => StatusCode::UNAUTHORIZED,
+        BridgeError::Forbidden => StatusCode::FORBIDDEN,
+        BridgeError::Status(m) => {
+            StatusCode::from_u16(*m).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+        BridgeError::RateLimited(_) => StatusCode::TOO_MANY_REQUESTS,
+        BridgeError::Network(_) | BridgeError::UnknownClient => StatusCode::INTERNAL_SERVER_ERROR,
+    }
+}
+
+/// Creates a response for a failed bridge request.
+///
+/// Validation errors are sent to the client as a bincode encoded body, so the client can tell
+/// which field is rejected. Rate limited requests are responded with the number of seconds after
+/// which the client can retry.
+fn bridge_error_response(e: &BridgeError, request_headers: &HeaderMap) -> Response {
+    let status = bridge_error_status(e);
+
+    let content = match e {
+        BridgeError::Validation(m) => bincode::serialize(m).ok(),
+        _ => None,
+    };
+
+    let mut resp = match content {
+        Some(m) => {
+            reply::with_status(bincode_response(m, false, request_headers), status).into_response()
+        }
+        None => reply::with_status("", status).into_response(),
+    };
+
+    if let BridgeError::RateLimited(m) = e {
+        insert_retry_after(&mut resp, *m);
+    }
+
+    resp
+}
+
+/// Inserts the number of seconds after which a rate limited client can retry.
+fn insert_retry_after(resp: &mut Response, retry_after: Duration) {
+    // The duration is rounded up, so the client does not retry too early.
+    let retry_after = retry_after.as_secs() + u64::from(retry_after.subsec_nanos() > 0);
+    resp.headers_mut()
+        .insert(RETRY_AFTER, HeaderValue::from(retry_after.max(1)));
+}
+
+/// Creates a response with bincode encoded content.
+fn bincode_response(content: Vec<u8>, compression: bool, request_headers: &HeaderMap) -> Response {
+    if compression {
+        return compressed_response(content, BINCODE_CONTENT_TYPE, request_headers);
+    }
+
+    reply::with_header(content, "content-type", BINCODE_CONTENT_TYPE).into_response()
+}
+
+/// Converts the body of a request into a byte stream, which stops with an error if it exceeds
+/// the size limit.
+fn limit_body<S, B>(body: S, limit: u64) -> ByteStream
+where
+    S: 'static + Stream<Item = Result<B, warp::Error>>,
+    B: Buf,
+{
+    let mut received = 0;
+
+    body.map(move |m| {
+        let m = m
+            .map(|mut m| m.copy_to_bytes(m.remaining()).to_vec())
+            .map_err(|e| BridgeError::Body(e.into()))?;
+        received += m.len() as u64;
+
+        match received > limit {
+            true => Err(BridgeError::TooLarge(limit)),
+            false => Ok(m),
+        }
+    })
+    // The body ends after the first error.
+    .scan(false, |failed, m| {
+        let m = (!*failed).then_some(m);
+        *failed = m.as_ref().map(|m| m.is_err()).unwrap_or(true);
+
+        future::ready(m)
+    })
+    .boxed_local()
+}
+
+/// Splits a submitted form into the index of its mutation and the fields of the input.
+///
+/// The mutation is identified by a field in the form, the other fields are decoded into the input
+/// of the mutation.
+fn parse_form(form: &[u8]) -> Result<(usize, String), BridgeError> {
+    let mut fields = serde_urlencoded::from_bytes::<Vec<(String, String)>>(form)?;
+    let mutation_index = fields
+        .iter()
+        .position(|(name, _)| name == MUTATION_INDEX_FIELD)
+        .map(|m| fields.remove(m).1)
+        .and_then(|m| m.parse::<usize>().ok())
+        .ok_or_else(|| serde::de::Error::custom("missing mutation index"))
+        .map_err(BridgeError::InvalidForm)?;
+    let form = serde_urlencoded::to_string(fields).expect("failed to encode form");
+
+    Ok((mutation_index, form))
+}
+
+/// Resolves a submitted form with the bridge of the request.
+async fn submit_form<L>(
+    spawner: &TaskSpawner,
+    create_bridge: CreateBridge<L>,
+    req: &WarpRenderRequest<()>,
+    mutation_index: usize,
+    form: String,
+) -> Result<FormSubmission, BridgeError>
+where
+    L: 'static + Link,
+{
+    let req = req.clone().into_inner();
+    let (tx, rx) = sync_oneshot::channel();
+
+    spawner.spawn_pinned_or_local(move || async move {
+        let bridge = create_bridge(req).await;
+        let submission = bridge
+            .link()
+            .resolve_form(mutation_index, form.as_bytes())
+            .await;
+
+        let _ = tx.send(submission);
+    });
+
+    rx.await.expect("failed to resolve the submitted form")
+}
+
+/// The reason that a page failed to render.
+#[derive(Debug)]
+enum RenderError {
+    Panicked(String),
+    TimedOut(Duration),
+}
+
+impl fmt::Display for RenderError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Panicked(m) => write!(f, "renderer panicked: {m}"),
+            Self::TimedOut(m) => write!(f, "render did not complete within {m:?}"),
+        }
+    }
+}
+
+/// A page rendered by the endpoint.
+#[derive(Debug)]
+struct RenderedPage {
+    html: String,
+    /// Whether the page is rendered at the client side instead of the requested mode.
+    is_fallback: bool,
+}
+
+/// Options that control how pages are rendered.
+#[derive(Debug, Clone)]
+struct RenderOptions {
+    client_only_fallback: bool,
+    timeout: Option<Duration>,
+    admission: Option<Arc<Semaphore>>,
+    spawner: TaskSpawner,
+}
+
+/// Renders a page on a pinned thread.
+///
+/// Panics during the render are caught and returned as errors. The render is cancelled if it does
+/// not complete before the timeout.
+async fn render_in_pinned(
+    spawner: &TaskSpawner,
+    render_index: RenderIndex,
+    req: WarpRenderRequest<()>,
+    timeout: Option<Duration>,
+) -> Result<String, RenderError> {
+    let (tx, rx) = sync_oneshot::channel();
+
+    spawner.spawn_pinned_or_local(move || async move {
+        let render = AssertUnwindSafe(render_index(req))
+            .catch_unwind()
+            .map(|m| m.map_err(|e| RenderError::Panicked(panic_message(e))));
+
+        let result = match timeout {
+            Some(m) => {
+                futures::select! {
+                    result = render.fuse() => result,
+                    _ = sleep(m).fuse() => Err(RenderError::TimedOut(m)),
+                }
+            }
+            None => render.await,
+        };
+
+        let _ = tx.send(result);
+    });
+
+    rx.await.unwrap_or_else(|_| {
+        Err(RenderError::Panicked(
+            "renderer stopped before completing the render".to_string(),
+        ))
+    })
+}
+
+impl RenderOptions {
+    /// Admits a request to be rendered at the server side.
+    ///
+    /// Requests exceeding the limit of concurrent renders are rendered at the client side. The
+    /// returned permit needs to be held until the render completes.
+    fn admit(
+        &self,
+        req: WarpRenderRequest<()>,
+    ) -> (WarpRenderRequest<()>, Option<OwnedSemaphorePermit>) {
+        let admission = match self.admission {
+            Some(ref m) => m.clone(),
+            None => return (req, None),
+        };
+
+        match admission.try_acquire_owned() {
+            Ok(m) => (req, Some(m)),
+            Err(_) => {
+                tracing::warn!(
+                    path = req.path(),
+                    queries = req.raw_queries(),
+                    "too many concurrent renders, rendering page at the client side"
+                );
+
+                (req.client_only(), None)
+            }
+        }
+    }
+}
+
+/// Renders a page, falling back to client-side rendering if the render fails.
+///
+/// Timed out renders always fall back to client-side rendering, panicked renders only fall back if
+/// enabled. Failures are logged with the request that caused them.
+async fn render_page(
+    render_index: RenderIndex,
+    req: WarpRenderRequest<()>,
+    options: RenderOptions,
+) -> Result<RenderedPage, ()> {
+    let is_client_only = req.is_client_only();
+    let (req, permit) = options.admit(req);
+
+    let e = match render_in_pinned(
+        &options.spawner,
+        render_index.clone(),
+        req.clone(),
+        options.timeout,
+    )
+    .await
+    {
+        Ok(html) => {
+            return Ok(RenderedPage {
+                html,
+                is_fallback: req.is_client_only() != is_client_only,
+            })
+        }
+        Err(e) => e,
+    };
+    drop(permit);
+
+    let fallback = match e {
+        RenderError::TimedOut(_) => {
+            tracing::warn!(
+                path = req.path(),
+                queries = req.raw_queries(),
+                "failed to render page: {}",
+                e
+            );
+
+            true
+        }
+        RenderError::Panicked(_) => {
+            tracing::error!(
+                path = req.path(),
+                queries = req.raw_queries(),
+                "failed to render page: {}",
+                e
+            );
+
+            options.client_only_fallback
+        }
+    };
+
+    // A client-only render does not render any component on the server side.
+    if !fallback || req.is_client_only() {
+        return Err(());
+    }
+
+    let path = req.path().to_owned();
+    let queries = req.raw_queries().to_owned();
+
+    render_in_pinned(
+        &options.spawner,
+        render_index,
+        req.client_only(),
+        options.timeout,
+    )
+    .await
+    .map(|html| RenderedPage {
+        html,
+        is_fallback: true,
+    })
+    .map_err(|e| {
+        tracing::error!(
+            path = path.as_str(),
+            queries = queries.as_str(),
+            "failed to render page at the client side: {}",
+            e
+        );
+    })
+}
+
+/// Renders a page at the server side once and serves it from memory afterwards.
+///
+/// Pages that fall back to client-side rendering are not stored.
+async fn render_static_page(
+    render_index: RenderIndex,
+    req: WarpRenderRequest<()>,
+    options: RenderOptions,
+    static_pages: &StaticPages,
+) -> Result<RenderedPage, ()> {
+    let path = req.path().to_owned();
+    let template = req.template.clone();
+
+    // Pages are rendered again if the frontend has been replaced.
+    if let Some(m) = static_pages.get(&path, &template) {
+        return Ok(RenderedPage {
+            html: m.to_string(),
+            is_fallback: false,
+        });
+    }
+
+    let page = render_page(render_index, req.into_static(), options).await?;
+
+    if !page.is_fallback {
+        static_pages.insert(path, template, page.html.as_str().into());
+    }
+
+    Ok(page)
+}
@@ -1,4 +1,11 @@
+use std::io;
+use std::net::SocketAddr;
+use std::sync::Arc;
+
+use futures::future::BoxFuture;
 use futures::Future;
+use http::HeaderMap;
+use stellation_bridge::Identity;
 use warp::path::FullPath;
 use warp::reject::not_found;
 use warp::reply::Response;
@@ -6,10 +13,25 @@ use warp::{Filter, Rejection};
 
 use crate::frontend::IndexHtml;
 use crate::html;
-use crate::request::{WarpRenderRequest, WarpRequest};
+use crate::proxy::TrustedProxies;
+use crate::request::{RemoteAddr, WarpRenderRequest, WarpRequest};
+
+/// Resolves the identity of the client that sent a request.
+pub(crate) type Authenticate =
+    Arc<dyn Send + Sync + Fn(WarpRequest<()>) -> BoxFuture<'static, Option<Identity>>>;
+
+/// A render request, or the error occurred while loading its template.
+type LoadedRenderRequest = io::Result<WarpRenderRequest<()>>;
 
 /// A filter that extracts the warp request.
-pub(crate) fn warp_request() -> impl Clone
+///
+/// The client is authenticated with the authenticator, if any. The address of clients behind
+/// trusted proxies is read from the forwarded headers.
+pub(crate) fn warp_request(
+    base_path: Arc<str>,
+    authenticate: Option<Authenticate>,
+    trusted_proxies: TrustedProxies,
+) -> impl Clone
        + Send
        + Filter<
     Extract = (WarpRequest<()>,),
@@ -19,45 +41,79 @@ pub(crate) fn warp_request() -> impl Clone
     warp::path::full()
         .and(warp::query::raw().or_else(|_| async move { Ok::<_, Rejection>((String::new(),)) }))
         .and(warp::header::headers_cloned())
+        .and(warp::addr::remote())
+        .and(warp::ext::optional::<RemoteAddr>())
         .then(
-            move |path: FullPath, raw_queries: String, headers| async move {
-                WarpRequest {
-                    path: path.into(),
-                    raw_queries: raw_queries.into(),
-                    context: ().into(),
-                    headers,
+            move |path: FullPath,
+                  raw_queries: String,
+                  headers: HeaderMap,
+                  remote_addr: Option<SocketAddr>,
+                  ext_addr: Option<RemoteAddr>| {
+                let base_path = base_path.clone();
+                let authenticate = authenticate.clone();
+                let remote_addr = remote_addr
+                    .or(ext_addr.map(|m| m.0))
+                    .map(|m| trusted_proxies.client_addr(m, &headers));
+                async move {
+                    let req = WarpRequest {
+                        path: path.into(),
+                        raw_queries: raw_queries.into(),
+                        context: ().into(),
+                        headers,
+                        base_path,
+                        identity: None,
+                        remote_addr,
+                    };
+
+                    match authenticate {
+                        Some(m) => WarpRequest {
+                            identity: m(req.clone()).await,
+                            ..req
+                        },
+                        None => req,
+                    }
                 }
             },
         )
 }
 
 /// A filter that extracts the warp render request.
+///
+/// This filter fails with an error if the template cannot be loaded.
 pub(crate) fn warp_render_request(
     index_html: IndexHtml,
     auto_refresh: bool,
+    base_path: Arc<str>,
+    authenticate: Option<Authenticate>,
+    trusted_proxies: TrustedProxies,
 ) -> impl Clone
        + Send
        + Filter<
-    Extract = (WarpRenderRequest<()>,),
+    Extract = (LoadedRenderRequest,),
     Error = Rejection,
-    Future = impl Future<Output = Result<(WarpRenderRequest<()>,), Rejection>>,
+    Future = impl Future<Output = Result<(LoadedRenderRequest,), Rejection>>,
 > {
-    warp_request().then(move |req: WarpRequest<()>| {
-        let index_html = index_html.clone();
-        async move {
-            let mut template = index_html.read_content().await;
+    warp_request(base_path.clone(), authenticate, trusted_proxies).then(
+        move |req: WarpRequest<()>| {
+            let index_html = index_html.clone();
+            let base_path = base_path.clone();
+            async move {
+                let mut template = index_html.read_content().await?;
 
-            if auto_refresh {
-                template = html::add_refresh_script(&template).into();
-            }
+                if auto_refresh {
+                    let refresh_id = html::refresh_id(index_html.build_id().await.as_deref());
+                    template = html::add_refresh_script(&template, &base_path, &refresh_id).into();
+                }
 
-            WarpRenderRequest {
-                inner: req,
-                template,
-                is_client_only: false,
+                Ok(WarpRenderRequest {
+                    inner: req,
+                    template,
+                    is_client_only: false,
+                    form_submission: None,
+                })
             }
-        }
-    })
+        },
+    )
 }
 
 /// A filter that rejects all responses.
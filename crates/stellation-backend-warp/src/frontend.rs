@@ -1,8 +1,12 @@
 use std::ops::Deref;
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
-use std::{fmt, str};
+use std::time::Duration;
+use std::{fmt, io, str};
 
+use http::header::{CONTENT_ENCODING, CONTENT_TYPE, VARY};
+use http::{HeaderMap, HeaderValue};
+use hyper::Body;
 use rust_embed::{EmbeddedFile, RustEmbed};
 use stellation_backend::utils::ThreadLocalLazy;
 use tokio::fs;
@@ -12,6 +16,9 @@ use warp::path::Tail;
 use warp::reply::{with_header, Response};
 use warp::{Filter, Rejection, Reply};
 
+use crate::cache::{CachePolicy, Validators};
+use crate::compression::ContentEncoding;
+
 type GetFileFn = Box<dyn Send + Fn(&str) -> Option<EmbeddedFile>>;
 
 type GetFile = ThreadLocalLazy<GetFileFn>;
@@ -19,6 +26,7 @@ type GetFile = ThreadLocalLazy<GetFileFn>;
 #[derive(Clone)]
 enum Inner {
     Path(PathBuf),
+    PathFile(PathBuf),
     Embed { get_file: GetFile },
 }
 
@@ -26,6 +34,7 @@ impl fmt::Debug for Inner {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             Inner::Path(ref p) => f.debug_struct("Inner::Path").field("0", p).finish(),
+            Inner::PathFile(ref p) => f.debug_struct("Inner::PathFile").field("0", p).finish(),
             Inner::Embed { .. } => f.debug_struct("Inner::Embed").finish_non_exhaustive(),
         }
     }
@@ -37,6 +46,7 @@ impl fmt::Debug for Inner {
 #[derive(Debug, Clone)]
 pub struct Frontend {
     inner: Inner,
+    cache: CachePolicy,
 }
 
 impl Frontend {
@@ -49,6 +59,23 @@ impl Frontend {
 
         Self {
             inner: Inner::Path(p),
+            cache: CachePolicy::default(),
+        }
+    }
+
+    /// Serves the frontend from the directory whose path is stored in a file.
+    ///
+    /// The file is read for each request, so the frontend can be replaced without restarting the
+    /// server. This is used by the development server to serve new frontend builds.
+    pub fn new_path_file<P>(p: P) -> Self
+    where
+        P: Into<PathBuf>,
+    {
+        let p = p.into();
+
+        Self {
+            inner: Inner::PathFile(p),
+            cache: CachePolicy::default(),
         }
     }
 
@@ -61,31 +88,176 @@ impl Frontend {
 
         Self {
             inner: Inner::Embed { get_file },
+            cache: CachePolicy::default(),
         }
     }
 
+    /// Sets how long assets with hashed filenames can be cached by browsers.
+    ///
+    /// Trunk appends a content hash to the filenames of assets, so these assets are marked as
+    /// immutable.
+    ///
+    /// Defaults to 1 year.
+    pub fn with_hashed_asset_max_age(mut self, max_age: Duration) -> Self {
+        self.cache.hashed_max_age = max_age;
+
+        self
+    }
+
+    /// Sets the `Cache-Control` header of rendered pages, `index.html` and assets without hashed
+    /// filenames.
+    ///
+    /// Defaults to `no-cache`, which requires browsers to revalidate the asset before using a
+    /// cached copy.
+    ///
+    /// # Panics
+    ///
+    /// This method panics if the value is not a valid header value.
+    pub fn with_cache_control<S>(mut self, value: S) -> Self
+    where
+        S: AsRef<str>,
+    {
+        self.cache.cache_control =
+            HeaderValue::from_str(value.as_ref()).expect("invalid cache control value");
+
+        self
+    }
+
+    /// Returns the `Cache-Control` header of rendered pages.
+    pub(crate) fn html_cache_control(&self) -> HeaderValue {
+        self.cache.cache_control.clone()
+    }
+
     pub(crate) fn into_warp_filter(self) -> BoxedFilter<(Response,)> {
-        match self.inner {
-            Inner::Path(m) => warp::fs::dir(m)
-                .then(|m: File| async move { m.into_response() })
+        let Self { inner, cache } = self;
+
+        match inner {
+            Inner::Path(m) => {
+                let precompressed_f = {
+                    let dir = m.clone();
+                    let cache = cache.clone();
+                    warp::path::tail()
+                        .and(warp::header::headers_cloned())
+                        .and_then(move |path: Tail, headers: HeaderMap| {
+                            let dir = dir.clone();
+                            let cache = cache.clone();
+                            async move {
+                                // warp::fs::dir sanitises the path for the uncompressed file.
+                                if path.as_str().split('/').any(|m| m == "..") {
+                                    return Err(warp::reject::not_found());
+                                }
+
+                                precompressed_file(&dir, path.as_str(), &headers, &cache)
+                                    .await
+                                    .ok_or_else(warp::reject::not_found)
+                            }
+                        })
+                };
+
+                let dir_f = warp::fs::dir(m).and(warp::header::headers_cloned()).then(
+                    move |m: File, headers: HeaderMap| {
+                        let cache = cache.clone();
+                        async move {
+                            let validators = fs::metadata(m.path())
+                                .await
+                                .map(|m| Validators::from_metadata(&m))
+                                .unwrap_or_default();
+                            let path = m.path().to_string_lossy().into_owned();
+
+                            cache.respond(&path, &validators, &headers, || m.into_response())
+                        }
+                    },
+                );
+
+                precompressed_f.or(dir_f).unify().boxed()
+            }
+            Inner::PathFile(m) => warp::path::tail()
+                .and(warp::header::headers_cloned())
+                .and_then(move |path: Tail, headers: HeaderMap| {
+                    let file = m.clone();
+                    let cache = cache.clone();
+                    async move {
+                        if path.as_str().split('/').any(|m| m == "..") {
+                            return Err(warp::reject::not_found());
+                        }
+
+                        let dir = read_dir_file(&file)
+                            .await
+                            .map_err(|_| warp::reject::not_found())?;
+
+                        if let Some(m) =
+                            precompressed_file(&dir, path.as_str(), &headers, &cache).await
+                        {
+                            return Ok(m);
+                        }
+
+                        let file_path = dir.join(path.as_str());
+                        let meta = match fs::metadata(&file_path).await {
+                            Ok(m) if m.is_file() => m,
+                            _ => return Err(warp::reject::not_found()),
+                        };
+                        let validators = Validators::from_metadata(&meta);
+                        let data = fs::read(&file_path)
+                            .await
+                            .map_err(|_| warp::reject::not_found())?;
+                        let mime = mime_guess::from_path(path.as_str()).first_or_octet_stream();
+
+                        Ok::<_, Rejection>(cache.respond(
+                            path.as_str(),
+                            &validators,
+                            &headers,
+                            || {
+                                with_header(
+                                    warp::hyper::Response::new(Body::from(data)),
+                                    "content-type",
+                                    mime.as_ref(),
+                                )
+                                .into_response()
+                            },
+                        ))
+                    }
+                })
                 .boxed(),
             Inner::Embed { get_file } => warp::path::tail()
-                .and_then(move |path: Tail| {
+                .and(warp::header::headers_cloned())
+                .and_then(move |path: Tail, headers: HeaderMap| {
                     let get_file = get_file.clone();
+                    let cache = cache.clone();
                     async move {
                         let get_file = get_file.deref();
 
+                        // Serves a precompressed sibling if the client accepts it.
+                        for encoding in ContentEncoding::negotiate(&headers) {
+                            if let Some(m) = get_file(&precompressed_path(path.as_str(), encoding))
+                            {
+                                let validators = Validators::from_embedded(&m);
+
+                                return Ok(cache.respond(
+                                    path.as_str(),
+                                    &validators,
+                                    &headers,
+                                    || precompressed_response(path.as_str(), m.data, encoding),
+                                ));
+                            }
+                        }
+
                         let asset = get_file(path.as_str()).ok_or_else(warp::reject::not_found)?;
+                        let validators = Validators::from_embedded(&asset);
                         let mime = mime_guess::from_path(path.as_str()).first_or_octet_stream();
 
-                        Ok::<_, Rejection>(
-                            with_header(
-                                warp::hyper::Response::new(asset.data),
-                                "content-type",
-                                mime.as_ref(),
-                            )
-                            .into_response(),
-                        )
+                        Ok::<_, Rejection>(cache.respond(
+                            path.as_str(),
+                            &validators,
+                            &headers,
+                            || {
+                                with_header(
+                                    warp::hyper::Response::new(asset.data),
+                                    "content-type",
+                                    mime.as_ref(),
+                                )
+                                .into_response()
+                            },
+                        ))
                     }
                 })
                 .boxed(),
@@ -95,6 +267,7 @@ impl Frontend {
     pub(crate) fn index_html(&self) -> IndexHtml {
         match self.inner {
             Inner::Path(ref m) => IndexHtml::Path(m.join("index.html").into()),
+            Inner::PathFile(ref m) => IndexHtml::PathFile(m.as_path().into()),
             Inner::Embed { ref get_file } => (get_file.deref())("index.html")
                 .map(|m| m.data)
                 .as_deref()
@@ -106,21 +279,99 @@ impl Frontend {
     }
 }
 
+/// Reads the directory of the current build from a file written by the development server.
+async fn read_dir_file(file: &Path) -> io::Result<PathBuf> {
+    fs::read_to_string(file)
+        .await
+        .map(|m| PathBuf::from(m.trim()))
+}
+
+/// Returns a precompressed sibling of a file in a directory, if the client accepts it.
+async fn precompressed_file(
+    dir: &Path,
+    path: &str,
+    headers: &HeaderMap,
+    cache: &CachePolicy,
+) -> Option<Response> {
+    for encoding in ContentEncoding::negotiate(headers) {
+        let compressed_path = dir.join(precompressed_path(path, encoding));
+
+        let meta = match fs::metadata(&compressed_path).await {
+            Ok(m) if m.is_file() => m,
+            _ => continue,
+        };
+        let validators = Validators::from_metadata(&meta);
+
+        if let Ok(data) = fs::read(&compressed_path).await {
+            return Some(cache.respond(path, &validators, headers, || {
+                precompressed_response(path, data, encoding)
+            }));
+        }
+    }
+
+    None
+}
+
+/// Returns the path of the precompressed sibling of a file.
+fn precompressed_path(path: &str, encoding: ContentEncoding) -> String {
+    format!("{}.{}", path, encoding.extension())
+}
+
+/// Creates a response for a precompressed file.
+///
+/// The content type is guessed from the path of the original file.
+fn precompressed_response<B>(path: &str, data: B, encoding: ContentEncoding) -> Response
+where
+    B: Into<Body>,
+{
+    let mime = mime_guess::from_path(path).first_or_octet_stream();
+
+    let mut resp = Response::new(data.into());
+    let headers = resp.headers_mut();
+
+    if let Ok(m) = HeaderValue::from_str(mime.as_ref()) {
+        headers.insert(CONTENT_TYPE, m);
+    }
+    headers.insert(
+        CONTENT_ENCODING,
+        HeaderValue::from_static(encoding.as_str()),
+    );
+    headers.insert(VARY, HeaderValue::from_static("accept-encoding"));
+
+    resp
+}
+
 #[derive(Clone)]
 pub(crate) enum IndexHtml {
     Embedded(Arc<str>),
     Path(Arc<Path>),
+    PathFile(Arc<Path>),
 }
 
 impl IndexHtml {
-    pub async fn read_content(&self) -> Arc<str> {
+    pub async fn read_content(&self) -> io::Result<Arc<str>> {
         match self {
-            IndexHtml::Path(p) => fs::read_to_string(&p)
-                .await
-                .map(Arc::from)
-                .expect("TODO: implement failure."),
+            IndexHtml::Path(p) => fs::read_to_string(&p).await.map(Arc::from),
+            IndexHtml::PathFile(p) => {
+                let dir = read_dir_file(p).await?;
 
-            IndexHtml::Embedded(ref s) => s.clone(),
+                fs::read_to_string(dir.join("index.html"))
+                    .await
+                    .map(Arc::from)
+            }
+            IndexHtml::Embedded(ref s) => Ok(s.clone()),
+        }
+    }
+
+    /// Returns the id of the current build, if the frontend can be replaced at runtime.
+    pub async fn build_id(&self) -> Option<String> {
+        match self {
+            IndexHtml::PathFile(p) => read_dir_file(p)
+                .await
+                .ok()?
+                .file_name()
+                .map(|m| m.to_string_lossy().into_owned()),
+            IndexHtml::Path(_) | IndexHtml::Embedded(_) => None,
         }
     }
 }
@@ -1,58 +1,225 @@
-use lol_html::{doc_comments, rewrite_str, Settings};
-use once_cell::sync::Lazy;
+use lol_html::{doc_comments, element, rewrite_str, Settings};
 
 use crate::SERVER_ID;
 
-static AUTO_REFRESH_SCRIPT: Lazy<String> = Lazy::new(|| {
-    format!(
-        r#"
+const AUTO_REFRESH_SCRIPT: &str = r#"
 <script>
-    (() => {{
+    (() => {
         const protocol = window.location.protocol === 'https' ? 'wss' : 'ws';
-        const wsUrl = `${{protocol}}://${{window.location.host}}/_refresh`;
-        const serverId = '{}';
+        const wsUrl = `${protocol}://${window.location.host}%BASE_PATH%_refresh`;
+        let refreshId = '%SERVER_ID%';
+        let buildStatus = 'ready';
 
-        const connectWs = () => {{
+        const showBuildError = (message) => {
+            let overlay = document.getElementById('stellation-build-error');
+            if (!overlay) {
+                overlay = document.createElement('div');
+                overlay.id = 'stellation-build-error';
+                overlay.style.cssText = 'position:fixed;inset:0;z-index:2147483646;overflow:auto;'
+                    + 'padding:24px;background:rgba(24,24,27,0.95);color:#fafafa;'
+                    + 'font:14px/1.5 monospace;';
+                const title = document.createElement('h2');
+                title.textContent = 'Build failed';
+                title.style.cssText = 'margin:0 0 16px;color:#f87171;font:bold 18px sans-serif;';
+                const pre = document.createElement('pre');
+                pre.style.cssText = 'margin:0;white-space:pre-wrap;';
+                overlay.append(title, pre);
+                document.body.append(overlay);
+            }
+            const pre = overlay.querySelector('pre');
+            if (pre.textContent !== message) {
+                pre.textContent = message;
+            }
+        };
+
+        const hideBuildError = () => {
+            const overlay = document.getElementById('stellation-build-error');
+            if (overlay) {
+                overlay.remove();
+            }
+        };
+
+        let indicatorTimer = null;
+        const showIndicator = (text, color, duration) => {
+            let indicator = document.getElementById('stellation-build-status');
+            if (!indicator) {
+                indicator = document.createElement('div');
+                indicator.id = 'stellation-build-status';
+                indicator.style.cssText = 'position:fixed;right:12px;bottom:12px;'
+                    + 'z-index:2147483647;padding:4px 10px;border-radius:12px;color:#fff;'
+                    + 'font:12px/1.5 sans-serif;pointer-events:none;';
+                document.body.append(indicator);
+            }
+            indicator.textContent = text;
+            indicator.style.background = color;
+
+            clearTimeout(indicatorTimer);
+            if (duration) {
+                indicatorTimer = setTimeout(() => indicator.remove(), duration);
+            }
+        };
+
+        const setBuildStatus = (m) => {
+            if (m.status === 'failed') {
+                showIndicator('Build failed', '#dc2626');
+                showBuildError(m.message);
+            } else if (m.status === buildStatus) {
+                return;
+            } else if (m.status === 'building') {
+                showIndicator('Building…', '#d97706');
+            } else {
+                showIndicator('Ready', '#16a34a', 2000);
+                hideBuildError();
+            }
+            buildStatus = m.status;
+        };
+
+        // The application is loaded by scripts and preloaded modules created by trunk.
+        const appSignature = (doc) => Array.from(doc.querySelectorAll(
+            'script[src], script[type="module"], link[rel="modulepreload"], '
+                + 'link[rel="preload"][href$=".wasm"], link[rel="preload"][href$=".js"]'
+        )).map((m) => m.getAttribute('src') || m.getAttribute('href') || m.textContent).join('\n');
+
+        // Stylesheets are replaced after the new stylesheets are loaded, which avoids a flash of
+        // unstyled content.
+        const swapStylesheets = (doc, version) => {
+            const stale = Array.from(document.querySelectorAll('link[rel="stylesheet"]'));
+            const anchor = stale.length > 0 ? stale[stale.length - 1] : null;
+
+            const loads = Array.from(doc.querySelectorAll('link[rel="stylesheet"]')).map((m) => {
+                const url = new URL(m.getAttribute('href'), window.location.href);
+                if (url.origin === window.location.origin) {
+                    url.searchParams.set('stellation-refresh', version);
+                }
+
+                const link = document.createElement('link');
+                link.rel = 'stylesheet';
+                link.href = url.href;
+
+                if (anchor) {
+                    anchor.after(link);
+                } else {
+                    document.head.append(link);
+                }
+
+                return new Promise((resolve) => {
+                    link.addEventListener('load', resolve);
+                    link.addEventListener('error', resolve);
+                });
+            });
+
+            Promise.all(loads).then(() => stale.forEach((m) => m.remove()));
+        };
+
+        // Assets without hashed filenames keep their urls, so they are requested again.
+        const reloadAssets = (version) => {
+            document.querySelectorAll('img[src], link[rel~="icon"]').forEach((m) => {
+                const attr = m.tagName === 'IMG' ? 'src' : 'href';
+                const url = new URL(m.getAttribute(attr), window.location.href);
+                if (url.origin === window.location.origin) {
+                    url.searchParams.set('stellation-refresh', version);
+                    m.setAttribute(attr, url.href);
+                }
+            });
+        };
+
+        const updateFrontend = async (id) => {
+            refreshId = id;
+
+            try {
+                const resp = await fetch(window.location.href, { cache: 'no-store' });
+                const doc = new DOMParser().parseFromString(await resp.text(), 'text/html');
+
+                if (appSignature(doc) !== appSignature(document)) {
+                    window.location.reload();
+                    return;
+                }
+
+                swapStylesheets(doc, id);
+                reloadAssets(id);
+            } catch(e) {
+                window.location.reload();
+            }
+        };
+
+        const connectWs = () => {
             const ws = new WebSocket(wsUrl);
-            ws.addEventListener('open', () => {{
-                const invId = setInterval(() => {{
-                    try {{
-                        ws.send(serverId);
-                    }} catch(e) {{
+            ws.addEventListener('open', () => {
+                const invId = setInterval(() => {
+                    try {
+                        ws.send(refreshId);
+                    } catch(e) {
                         // do nothing if errored.
-                    }}
-                }}, 1000);
-                ws.addEventListener('error', () => {{
+                    }
+                }, 1000);
+                ws.addEventListener('error', () => {
                     clearInterval(invId);
-                }});
-            }});
-            ws.addEventListener('close', () => {{
+                });
+            });
+            ws.addEventListener('close', () => {
+                // stctl serves the error of a failed build when the server cannot be started.
+                fetch(window.location.href, { method: 'HEAD', cache: 'no-store' })
+                    .then((resp) => {
+                        if (resp.headers.has('x-stellation-build-error')) {
+                            window.location.reload();
+                        }
+                    })
+                    .catch(() => {});
                 setTimeout(connectWs, 1000);
-            }});
-            ws.addEventListener('message', (e) => {{
-                if (e.data === 'restart') {{
+            });
+            ws.addEventListener('message', (e) => {
+                const m = JSON.parse(e.data);
+
+                if (m.type === 'reload') {
                     window.location.reload();
-                }}
-            }});
-        }};
+                } else if (m.type === 'update') {
+                    updateFrontend(m.id);
+                } else if (m.type === 'status') {
+                    setBuildStatus(m);
+                }
+            });
+        };
 
         connectWs();
-    }})();
-</script>"#,
-        SERVER_ID.as_str()
-    )
-});
+    })();
+</script>"#;
+
+/// The page served when a page fails to render, unless overridden by the endpoint.
+pub(crate) const DEFAULT_ERROR_PAGE: &str = r#"<!doctype html>
+<html>
+<head>
+    <meta charset="utf-8">
+    <title>Internal Server Error</title>
+</head>
+<body>
+    <h1>Internal Server Error</h1>
+    <p>The server encountered an error while rendering this page.</p>
+</body>
+</html>
+"#;
+
+/// Returns the id sent by pages to the refresh endpoint.
+///
+/// The id changes when the server restarts or the frontend is replaced, which tells the page to
+/// reload.
+pub(crate) fn refresh_id(build_id: Option<&str>) -> String {
+    match build_id {
+        Some(m) => format!("{}-{}", SERVER_ID.as_str(), m),
+        None => SERVER_ID.to_string(),
+    }
+}
+
+pub(crate) fn add_refresh_script(html_s: &str, base_path: &str, refresh_id: &str) -> String {
+    let script = AUTO_REFRESH_SCRIPT
+        .replace("%BASE_PATH%", base_path)
+        .replace("%SERVER_ID%", refresh_id);
 
-pub(crate) fn add_refresh_script(html_s: &str) -> String {
     rewrite_str(
         html_s,
         Settings {
             document_content_handlers: vec![doc_comments!(|c| {
                 if c.text() == "%STELLATION_BODY%" {
-                    c.after(
-                        AUTO_REFRESH_SCRIPT.as_str(),
-                        lol_html::html_content::ContentType::Html,
-                    );
+                    c.after(&script, lol_html::html_content::ContentType::Html);
                 }
                 Ok(())
             })],
@@ -61,3 +228,20 @@ pub(crate) fn add_refresh_script(html_s: &str) -> String {
     )
     .expect("failed to render html")
 }
+
+/// Appends the CSRF token to the head of a rendered page, where it is read by the bridge.
+pub(crate) fn add_csrf_token(html_s: &str, token: &str) -> String {
+    let meta = format!(r#"<meta name="stellation-csrf-token" content="{token}">"#);
+
+    rewrite_str(
+        html_s,
+        Settings {
+            element_content_handlers: vec![element!("head", |el| {
+                el.append(&meta, lol_html::html_content::ContentType::Html);
+                Ok(())
+            })],
+            ..Default::default()
+        },
+    )
+    .expect("failed to render html")
+}
@@ -10,17 +10,27 @@
 #![cfg_attr(documenting, feature(doc_auto_cfg))]
 #![cfg_attr(any(releasing, not(debug_assertions)), deny(dead_code, unused_imports))]
 
+mod cache;
+mod compression;
+mod csrf;
 mod endpoint;
 mod filters;
 mod frontend;
 mod html;
+mod proxy;
+mod refresh;
 mod request;
+mod routes;
+mod runtime;
+mod static_pages;
 mod utils;
 
 pub use endpoint::WarpEndpoint;
 pub use frontend::Frontend;
 use once_cell::sync::Lazy;
-pub use request::{WarpRenderRequest, WarpRequest};
+pub use request::{RemoteAddr, WarpRenderRequest, WarpRequest};
+pub use routes::RenderMode;
+pub use runtime::TaskMetrics;
 
 // A server id that is different every time it starts.
 static SERVER_ID: Lazy<String> = Lazy::new(crate::utils::random_str);
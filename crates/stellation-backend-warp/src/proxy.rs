@@ -0,0 +1,220 @@
+//! Addresses of clients behind reverse proxies.
+
+use std::net::{IpAddr, SocketAddr};
+use std::sync::Arc;
+
+use http::header::FORWARDED;
+use http::HeaderMap;
+
+const X_FORWARDED_FOR: &str = "x-forwarded-for";
+
+/// Reverse proxies that are trusted to forward the address of clients.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct TrustedProxies {
+    addrs: Arc<Vec<IpAddr>>,
+}
+
+impl TrustedProxies {
+    /// Trusts a proxy.
+    pub fn push(&mut self, addr: IpAddr) {
+        Arc::make_mut(&mut self.addrs).push(addr);
+    }
+
+    fn is_trusted(&self, addr: IpAddr) -> bool {
+        self.addrs.contains(&addr)
+    }
+
+    /// Returns the address of the client that sent a request through trusted proxies.
+    ///
+    /// If the request is sent by a trusted proxy, the `Forwarded` header, or the
+    /// `X-Forwarded-For` header if the former is absent, is read from the right and the first
+    /// address that is not a trusted proxy is returned. Addresses that cannot be parsed end the
+    /// search, as proxies that are not trusted may have forged them. Addresses forwarded without a
+    /// port have a port of `0`.
+    pub fn client_addr(&self, peer_addr: SocketAddr, headers: &HeaderMap) -> SocketAddr {
+        if !self.is_trusted(peer_addr.ip()) {
+            return peer_addr;
+        }
+
+        let mut client_addr = peer_addr;
+        for addr in forwarded_for(headers).into_iter().rev() {
+            client_addr = match parse_addr(addr) {
+                Some(m) => m,
+                None => break,
+            };
+
+            if !self.is_trusted(client_addr.ip()) {
+                break;
+            }
+        }
+
+        client_addr
+    }
+}
+
+/// Returns the forwarded addresses of a request, from the client to the last proxy.
+fn forwarded_for(headers: &HeaderMap) -> Vec<&str> {
+    let header_values = |name: &str| {
+        headers
+            .get_all(name)
+            .iter()
+            .filter_map(|m| m.to_str().ok())
+            .flat_map(|m| m.split(','))
+    };
+
+    if headers.contains_key(FORWARDED) {
+        return header_values(FORWARDED.as_str())
+            .map(|element| {
+                element
+                    .split(';')
+                    .filter_map(|m| m.trim().split_once('='))
+                    .find(|(name, _)| name.eq_ignore_ascii_case("for"))
+                    .map(|(_, value)| value.trim_matches('"'))
+                    // Elements without a `for` parameter cannot be traced to a client.
+                    .unwrap_or_default()
+            })
+            .collect();
+    }
+
+    header_values(X_FORWARDED_FOR).map(str::trim).collect()
+}
+
+/// Parses a forwarded address, which may be an ip address or a socket address.
+fn parse_addr(addr: &str) -> Option<SocketAddr> {
+    if let Ok(m) = addr.parse::<SocketAddr>() {
+        return Some(m);
+    }
+
+    // Ipv6 addresses are enclosed in brackets in the `Forwarded` header.
+    let ip = addr
+        .strip_prefix('[')
+        .and_then(|m| m.strip_suffix(']'))
+        .unwrap_or(addr);
+
+    ip.parse::<IpAddr>().ok().map(|m| SocketAddr::new(m, 0))
+}
+
+#[cfg(test)]
+mod tests {
+    use http::HeaderValue;
+
+    use super::*;
+
+    const PROXY: [u8; 4] = [10, 0, 0, 1];
+
+    fn headers(entries: &[(&'static str, &str)]) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        for (name, value) in entries {
+            headers.append(*name, HeaderValue::from_str(value).unwrap());
+        }
+
+        headers
+    }
+
+    fn proxies() -> TrustedProxies {
+        let mut proxies = TrustedProxies::default();
+        proxies.push(PROXY.into());
+        proxies.push([10, 0, 0, 2].into());
+
+        proxies
+    }
+
+    fn client_addr(peer_addr: [u8; 4], entries: &[(&'static str, &str)]) -> SocketAddr {
+        proxies().client_addr((peer_addr, 8080).into(), &headers(entries))
+    }
+
+    #[test]
+    fn ignores_untrusted_peers() {
+        let forwarded = [("x-forwarded-for", "192.0.2.1")];
+
+        assert_eq!(
+            client_addr([192, 0, 2, 9], &forwarded),
+            ([192, 0, 2, 9], 8080).into()
+        );
+        assert_eq!(
+            TrustedProxies::default().client_addr((PROXY, 8080).into(), &headers(&forwarded)),
+            (PROXY, 8080).into()
+        );
+    }
+
+    #[test]
+    fn reads_x_forwarded_for() {
+        assert_eq!(
+            client_addr(PROXY, &[("x-forwarded-for", "192.0.2.1")]),
+            ([192, 0, 2, 1], 0).into()
+        );
+        // Addresses are read from the right, addresses added by clients are ignored.
+        assert_eq!(
+            client_addr(
+                PROXY,
+                &[("x-forwarded-for", "203.0.113.7, 192.0.2.1, 10.0.0.2")]
+            ),
+            ([192, 0, 2, 1], 0).into()
+        );
+        assert_eq!(
+            client_addr(
+                PROXY,
+                &[
+                    ("x-forwarded-for", "203.0.113.7"),
+                    ("x-forwarded-for", "192.0.2.1")
+                ]
+            ),
+            ([192, 0, 2, 1], 0).into()
+        );
+        assert_eq!(
+            client_addr(PROXY, &[("x-forwarded-for", "2001:db8::1")]),
+            "[2001:db8::1]:0".parse().unwrap()
+        );
+    }
+
+    #[test]
+    fn reads_forwarded() {
+        assert_eq!(
+            client_addr(PROXY, &[("forwarded", "for=192.0.2.1;proto=https")]),
+            ([192, 0, 2, 1], 0).into()
+        );
+        assert_eq!(
+            client_addr(
+                PROXY,
+                &[("forwarded", r#"For="[2001:db8::1]:4711", for=10.0.0.2"#)]
+            ),
+            "[2001:db8::1]:4711".parse().unwrap()
+        );
+        assert_eq!(
+            client_addr(PROXY, &[("forwarded", r#"for="[2001:db8::1]""#)]),
+            "[2001:db8::1]:0".parse().unwrap()
+        );
+        // The forwarded header takes precedence over x-forwarded-for.
+        assert_eq!(
+            client_addr(
+                PROXY,
+                &[
+                    ("forwarded", "for=192.0.2.1"),
+                    ("x-forwarded-for", "203.0.113.7")
+                ]
+            ),
+            ([192, 0, 2, 1], 0).into()
+        );
+    }
+
+    #[test]
+    fn stops_at_invalid_addresses() {
+        // The last trusted proxy is the client if it forwards an address that cannot be parsed.
+        assert_eq!(
+            client_addr(
+                PROXY,
+                &[("x-forwarded-for", "192.0.2.1, unknown, 10.0.0.2")]
+            ),
+            ([10, 0, 0, 2], 0).into()
+        );
+        assert_eq!(
+            client_addr(PROXY, &[("forwarded", "for=_hidden")]),
+            (PROXY, 8080).into()
+        );
+        assert_eq!(
+            client_addr(PROXY, &[("forwarded", "proto=https")]),
+            (PROXY, 8080).into()
+        );
+        assert_eq!(client_addr(PROXY, &[]), (PROXY, 8080).into());
+    }
+}
@@ -0,0 +1,70 @@
+//! The protocol of auto refresh.
+//!
+//! Pages send their refresh id to the refresh endpoint every second, which replies with a json
+//! message.
+
+use std::path::Path;
+
+use serde::Serialize;
+use stellation_core::dev::BuildStatus;
+use tokio::fs;
+use warp::ws::Message;
+
+use crate::frontend::IndexHtml;
+use crate::{html, SERVER_ID};
+
+/// A message sent to pages by the refresh endpoint.
+#[derive(Debug, Serialize)]
+#[serde(tag = "type", rename_all = "kebab-case")]
+enum RefreshMessage {
+    /// The server has restarted, the page needs to be reloaded.
+    Reload,
+    /// The frontend has been replaced.
+    ///
+    /// Pages replace stylesheets and assets, and are only reloaded if the application has
+    /// changed.
+    Update {
+        /// The refresh id of the new frontend.
+        id: String,
+    },
+    /// The status of the latest build.
+    Status(BuildStatus),
+}
+
+/// Returns the message to send to a page that sent its refresh id.
+pub(crate) async fn refresh_message(
+    refresh_id: &str,
+    index_html: Option<&IndexHtml>,
+    build_status_file: Option<&Path>,
+) -> Message {
+    let build_id = match index_html {
+        Some(m) => m.build_id().await,
+        None => None,
+    };
+    let current_id = html::refresh_id(build_id.as_deref());
+
+    let message = if refresh_id != current_id {
+        // Pages can be updated in place if the server has not been restarted.
+        let is_same_server = refresh_id
+            .strip_prefix(SERVER_ID.as_str())
+            .map(|m| m.starts_with('-'))
+            .unwrap_or(false);
+
+        match build_id {
+            Some(_) if is_same_server => RefreshMessage::Update { id: current_id },
+            _ => RefreshMessage::Reload,
+        }
+    } else {
+        let status = match build_status_file {
+            Some(m) => fs::read_to_string(m)
+                .await
+                .ok()
+                .and_then(|m| BuildStatus::from_json(&m).ok()),
+            None => None,
+        };
+
+        RefreshMessage::Status(status.unwrap_or(BuildStatus::Ready))
+    };
+
+    Message::text(serde_json::to_string(&message).expect("failed to serialize message"))
+}
@@ -1,7 +1,10 @@
+use std::net::SocketAddr;
 use std::sync::Arc;
 
 use http::HeaderMap;
 use stellation_backend::{RenderRequest, Request};
+use stellation_bridge::routines::FormSubmission;
+use stellation_bridge::Identity;
 use warp::path::FullPath;
 
 /// A stellation request with information extracted from a warp request, used by
@@ -11,6 +14,7 @@ pub struct WarpRenderRequest<CTX> {
     pub(crate) inner: WarpRequest<CTX>,
     pub(crate) template: Arc<str>,
     pub(crate) is_client_only: bool,
+    pub(crate) form_submission: Option<FormSubmission>,
 }
 
 impl<CTX> Clone for WarpRenderRequest<CTX> {
@@ -19,6 +23,7 @@ impl<CTX> Clone for WarpRenderRequest<CTX> {
             inner: self.inner.clone(),
             template: self.template.clone(),
             is_client_only: self.is_client_only,
+            form_submission: self.form_submission.clone(),
         }
     }
 }
@@ -41,6 +46,14 @@ impl<CTX> Request for WarpRenderRequest<CTX> {
     fn headers(&self) -> &HeaderMap {
         self.inner.headers()
     }
+
+    fn base_path(&self) -> &str {
+        self.inner.base_path()
+    }
+
+    fn identity(&self) -> Option<&Identity> {
+        self.inner.identity()
+    }
 }
 
 impl<CTX> RenderRequest for WarpRenderRequest<CTX> {
@@ -51,6 +64,10 @@ impl<CTX> RenderRequest for WarpRenderRequest<CTX> {
     fn is_client_only(&self) -> bool {
         self.is_client_only
     }
+
+    fn form_submission(&self) -> Option<&FormSubmission> {
+        self.form_submission.as_ref()
+    }
 }
 
 impl<CTX> WarpRenderRequest<CTX> {
@@ -60,13 +77,37 @@ impl<CTX> WarpRenderRequest<CTX> {
             template: self.template,
             inner: self.inner.with_context(context),
             is_client_only: self.is_client_only,
+            form_submission: self.form_submission,
         }
     }
 
+    /// Returns the address of the client.
+    pub fn remote_addr(&self) -> Option<SocketAddr> {
+        self.inner.remote_addr()
+    }
+
     pub(crate) fn into_inner(self) -> WarpRequest<CTX> {
         self.inner
     }
 
+    /// Removes the queries, headers and client information of this request, so the page is the
+    /// same for all requests.
+    pub(crate) fn into_static(mut self) -> Self {
+        self.inner.raw_queries = "".into();
+        self.inner.headers = HeaderMap::new();
+        self.inner.identity = None;
+        self.inner.remote_addr = None;
+
+        self
+    }
+
+    /// Renders this request with the result of a submitted form.
+    pub(crate) fn with_form_submission(mut self, form_submission: FormSubmission) -> Self {
+        self.form_submission = Some(form_submission);
+
+        self
+    }
+
     /// Marks this request to be rendered at the client side.
     pub fn client_only(mut self) -> Self {
         self.is_client_only = true;
@@ -83,6 +124,9 @@ pub struct WarpRequest<CTX> {
     pub(crate) raw_queries: Arc<str>,
     pub(crate) context: Arc<CTX>,
     pub(crate) headers: HeaderMap,
+    pub(crate) base_path: Arc<str>,
+    pub(crate) identity: Option<Identity>,
+    pub(crate) remote_addr: Option<SocketAddr>,
 }
 
 impl<CTX> Clone for WarpRequest<CTX> {
@@ -92,6 +136,9 @@ impl<CTX> Clone for WarpRequest<CTX> {
             raw_queries: self.raw_queries.clone(),
             context: self.context.clone(),
             headers: self.headers.clone(),
+            base_path: self.base_path.clone(),
+            identity: self.identity.clone(),
+            remote_addr: self.remote_addr,
         }
     }
 }
@@ -114,6 +161,14 @@ impl<CTX> Request for WarpRequest<CTX> {
     fn headers(&self) -> &HeaderMap {
         &self.headers
     }
+
+    fn base_path(&self) -> &str {
+        &self.base_path
+    }
+
+    fn identity(&self) -> Option<&Identity> {
+        self.identity.as_ref()
+    }
 }
 
 impl<CTX> WarpRequest<CTX> {
@@ -123,7 +178,70 @@ impl<CTX> WarpRequest<CTX> {
             path: self.path,
             raw_queries: self.raw_queries,
             headers: self.headers,
+            base_path: self.base_path,
+            identity: self.identity,
+            remote_addr: self.remote_addr,
             context: context.into(),
         }
     }
+
+    /// Returns the address of the client.
+    ///
+    /// Returns `None` if the address is not provided by the server, see [`RemoteAddr`].
+    pub fn remote_addr(&self) -> Option<SocketAddr> {
+        self.remote_addr
+    }
+}
+
+/// The address of the client, stored in the extensions of a request.
+///
+/// Requests served with a warp server have the address of the client. Servers that serve the
+/// endpoint as a service need to insert this extension to make it available to
+/// [`WarpRequest::remote_addr`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RemoteAddr(pub SocketAddr);
+
+#[cfg(test)]
+mod tests {
+    use http::header::COOKIE;
+    use http::HeaderValue;
+
+    use super::*;
+
+    async fn create_request() -> WarpRenderRequest<()> {
+        let path = warp::test::request()
+            .path("/page?q=1")
+            .filter(&warp::path::full())
+            .await
+            .expect("failed to read path");
+
+        let mut headers = HeaderMap::new();
+        headers.insert(COOKIE, HeaderValue::from_static("session=secret"));
+
+        WarpRenderRequest {
+            inner: WarpRequest {
+                path: path.into(),
+                raw_queries: "q=1".into(),
+                context: ().into(),
+                headers,
+                base_path: "/".into(),
+                identity: Some(Identity::new(()).with_subject("user-1")),
+                remote_addr: Some(([127, 0, 0, 1], 8080).into()),
+            },
+            template: "".into(),
+            is_client_only: false,
+            form_submission: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn static_request_has_no_client_information() {
+        let req = create_request().await.into_static();
+
+        assert_eq!(req.path(), "/page");
+        assert_eq!(req.raw_queries(), "");
+        assert!(req.headers().is_empty());
+        assert!(req.identity().is_none());
+        assert!(req.remote_addr().is_none());
+    }
 }
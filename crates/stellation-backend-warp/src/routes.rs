@@ -0,0 +1,178 @@
+//! Render modes of routes.
+
+/// How a page is rendered by the endpoint.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RenderMode {
+    /// The page is rendered at the server side for every request.
+    #[default]
+    ServerSide,
+    /// The template is served without rendering and the page is rendered at the client side.
+    ClientOnly,
+    /// The page is rendered at the server side once and served from memory afterwards.
+    ///
+    /// The page is rendered without the queries, headers and identity of the request, so it is the
+    /// same for all visitors. The number of pages kept in memory is limited with
+    /// [`WarpEndpoint::with_max_static_pages`](crate::WarpEndpoint::with_max_static_pages).
+    Static,
+    /// The page is not rendered, the endpoint responds with `404 Not Found`.
+    NotFound,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Segment {
+    Exact(String),
+    Any,
+    Rest,
+}
+
+/// A path pattern that matches the path of a request.
+#[derive(Debug, Clone)]
+struct RoutePattern {
+    segments: Vec<Segment>,
+}
+
+impl RoutePattern {
+    fn parse(pattern: &str) -> Self {
+        let segments = pattern
+            .split('/')
+            .filter(|m| !m.is_empty())
+            .map(|m| match m {
+                "*" => Segment::Any,
+                "**" => Segment::Rest,
+                m => Segment::Exact(m.to_string()),
+            })
+            .collect();
+
+        Self { segments }
+    }
+
+    fn matches(&self, path: &str) -> bool {
+        let mut path_segments = path.split('/').filter(|m| !m.is_empty());
+
+        for segment in self.segments.iter() {
+            match segment {
+                Segment::Rest => return true,
+                Segment::Any => {
+                    if path_segments.next().is_none() {
+                        return false;
+                    }
+                }
+                Segment::Exact(m) => {
+                    if path_segments.next() != Some(m.as_str()) {
+                        return false;
+                    }
+                }
+            }
+        }
+
+        path_segments.next().is_none()
+    }
+}
+
+/// Returns the path relative to the base path.
+///
+/// Paths outside of the base path are returned as is.
+pub(crate) fn relative_path<'a>(base_path: &str, path: &'a str) -> &'a str {
+    let base_path = base_path.trim_end_matches('/');
+
+    match path.strip_prefix(base_path) {
+        Some(m) if m.is_empty() || m.starts_with('/') => m,
+        _ => path,
+    }
+}
+
+/// A table that maps path patterns to render modes.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct RouteTable {
+    routes: Vec<(RoutePattern, RenderMode)>,
+}
+
+impl RouteTable {
+    pub fn push(&mut self, pattern: &str, mode: RenderMode) {
+        self.routes.push((RoutePattern::parse(pattern), mode));
+    }
+
+    /// Returns the render mode of the first route that matches the path.
+    pub fn render_mode(&self, path: &str) -> RenderMode {
+        self.routes
+            .iter()
+            .find(|(pattern, _)| pattern.matches(path))
+            .map(|(_, mode)| *mode)
+            .unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn matches(pattern: &str, path: &str) -> bool {
+        RoutePattern::parse(pattern).matches(path)
+    }
+
+    #[test]
+    fn matches_exact_segments() {
+        assert!(matches("/about", "/about"));
+        assert!(matches("/", "/"));
+        assert!(matches("/docs/intro", "/docs/intro"));
+
+        assert!(!matches("/about", "/"));
+        assert!(!matches("/about", "/about/team"));
+        assert!(!matches("/docs/intro", "/docs"));
+        assert!(!matches("/", "/about"));
+    }
+
+    #[test]
+    fn matches_single_segment() {
+        assert!(matches("/posts/*", "/posts/1"));
+        assert!(matches("/*/edit", "/posts/edit"));
+
+        assert!(!matches("/posts/*", "/posts"));
+        assert!(!matches("/posts/*", "/posts/1/comments"));
+    }
+
+    #[test]
+    fn matches_remaining_segments() {
+        assert!(matches("/admin/**", "/admin"));
+        assert!(matches("/admin/**", "/admin/users"));
+        assert!(matches("/admin/**", "/admin/users/1"));
+        assert!(matches("/**", "/"));
+        assert!(matches("/**", "/any/path"));
+
+        assert!(!matches("/admin/**", "/administrator"));
+        assert!(!matches("/admin/**", "/"));
+    }
+
+    #[test]
+    fn ignores_trailing_slashes() {
+        assert!(matches("/about", "/about/"));
+        assert!(matches("/about/", "/about"));
+        assert!(matches("/posts/*", "/posts/1/"));
+    }
+
+    #[test]
+    fn strips_base_path() {
+        assert_eq!(relative_path("/", "/about"), "/about");
+        assert_eq!(relative_path("/app/", "/app/about"), "/about");
+        assert_eq!(relative_path("/app/", "/app"), "");
+        assert_eq!(relative_path("/app/", "/app/"), "/");
+        assert_eq!(relative_path("/app/", "/application"), "/application");
+        assert_eq!(relative_path("/app/", "/other"), "/other");
+    }
+
+    #[test]
+    fn uses_first_matching_route() {
+        let mut routes = RouteTable::default();
+        routes.push("/admin/**", RenderMode::ClientOnly);
+        routes.push("/admin/login", RenderMode::Static);
+        routes.push("/hidden", RenderMode::NotFound);
+
+        assert_eq!(routes.render_mode("/admin/login"), RenderMode::ClientOnly);
+        assert_eq!(routes.render_mode("/hidden"), RenderMode::NotFound);
+        assert_eq!(routes.render_mode("/other"), RenderMode::ServerSide);
+        assert_eq!(
+            routes.render_mode(relative_path("/app/", "/app/admin")),
+            RenderMode::ClientOnly
+        );
+    }
+}
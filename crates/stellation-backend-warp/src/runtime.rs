@@ -0,0 +1,175 @@
+//! Task spawning and runtime metrics.
+
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use futures::Future;
+use yew::platform::{LocalHandle, Runtime};
+
+#[derive(Debug, Default)]
+struct Counters {
+    queued: AtomicUsize,
+    running: AtomicUsize,
+    completed: AtomicU64,
+}
+
+/// Metrics of tasks spawned by an endpoint.
+///
+/// Server-side rendering and bridge requests are processed by tasks pinned to a worker thread of
+/// the runtime. A growing queue depth indicates that the runtime needs more workers.
+///
+/// This type can be cloned cheaply, all clones share the same counters.
+#[derive(Debug, Clone, Default)]
+pub struct TaskMetrics {
+    inner: Arc<Counters>,
+}
+
+impl TaskMetrics {
+    /// Returns the number of tasks waiting to be started by a worker.
+    pub fn queue_depth(&self) -> usize {
+        self.inner.queued.load(Ordering::Relaxed)
+    }
+
+    /// Returns the number of tasks being processed by workers.
+    pub fn in_flight(&self) -> usize {
+        self.inner.running.load(Ordering::Relaxed)
+    }
+
+    /// Returns the number of tasks that have completed since the endpoint was created.
+    pub fn completed(&self) -> u64 {
+        self.inner.completed.load(Ordering::Relaxed)
+    }
+
+    /// Marks a task as queued.
+    ///
+    /// The task leaves the queue when the returned guard is started or dropped, so tasks that are
+    /// dropped by the runtime without being started are not counted forever.
+    fn queue(self) -> QueuedGuard {
+        self.inner.queued.fetch_add(1, Ordering::Relaxed);
+
+        QueuedGuard { metrics: self }
+    }
+}
+
+struct QueuedGuard {
+    metrics: TaskMetrics,
+}
+
+impl QueuedGuard {
+    /// Marks the queued task as started.
+    ///
+    /// The task is marked as completed when the returned guard is dropped, including when the task
+    /// panics.
+    fn start(self) -> RunningGuard {
+        let metrics = self.metrics.clone();
+        drop(self);
+        metrics.inner.running.fetch_add(1, Ordering::Relaxed);
+
+        RunningGuard { metrics }
+    }
+}
+
+impl Drop for QueuedGuard {
+    fn drop(&mut self) {
+        self.metrics.inner.queued.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+struct RunningGuard {
+    metrics: TaskMetrics,
+}
+
+impl Drop for RunningGuard {
+    fn drop(&mut self) {
+        let inner = &self.metrics.inner;
+
+        inner.running.fetch_sub(1, Ordering::Relaxed);
+        inner.completed.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+/// Spawns tasks of an endpoint and records their metrics.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct TaskSpawner {
+    runtime: Option<Runtime>,
+    metrics: TaskMetrics,
+}
+
+impl TaskSpawner {
+    /// Sets the runtime that tasks are spawned onto.
+    pub fn with_runtime(self, runtime: Runtime) -> Self {
+        Self {
+            runtime: Some(runtime),
+            metrics: self.metrics,
+        }
+    }
+
+    /// Returns the metrics of tasks spawned by the current spawner.
+    pub fn metrics(&self) -> TaskMetrics {
+        self.metrics.clone()
+    }
+
+    /// Spawns a task pinned to a worker thread.
+    ///
+    /// If no runtime is set and the current thread is a worker thread, the task is spawned onto
+    /// the current thread.
+    pub fn spawn_pinned_or_local<F, Fut>(&self, create_task: F)
+    where
+        F: FnOnce() -> Fut,
+        F: Send + 'static,
+        Fut: Future<Output = ()> + 'static,
+    {
+        let queued = self.metrics.clone().queue();
+
+        let create_task = move || {
+            let guard = queued.start();
+            let task = create_task();
+
+            async move {
+                let _guard = guard;
+                task.await;
+            }
+        };
+
+        match self.runtime {
+            Some(ref m) => m.spawn_pinned(create_task),
+            // We spawn into a local runtime early for higher efficiency.
+            None => match LocalHandle::try_current() {
+                Some(handle) => handle.spawn_local(create_task()),
+                None => Runtime::default().spawn_pinned(create_task),
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn counts_task_states() {
+        let metrics = TaskMetrics::default();
+
+        let queued = metrics.clone().queue();
+        assert_eq!(metrics.queue_depth(), 1);
+
+        let running = queued.start();
+        assert_eq!(metrics.queue_depth(), 0);
+        assert_eq!(metrics.in_flight(), 1);
+
+        drop(running);
+        assert_eq!(metrics.in_flight(), 0);
+        assert_eq!(metrics.completed(), 1);
+    }
+
+    #[test]
+    fn dequeues_dropped_tasks() {
+        let metrics = TaskMetrics::default();
+
+        drop(metrics.clone().queue());
+
+        assert_eq!(metrics.queue_depth(), 0);
+        assert_eq!(metrics.in_flight(), 0);
+        assert_eq!(metrics.completed(), 0);
+    }
+}
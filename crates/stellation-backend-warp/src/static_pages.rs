@@ -0,0 +1,87 @@
+//! Pages rendered once and served from memory.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+/// The default number of static pages kept in memory.
+pub(crate) const DEFAULT_MAX_STATIC_PAGES: usize = 1024;
+
+/// A rendered page, stored with the template it was rendered from.
+#[derive(Debug)]
+struct StaticPage {
+    template: Arc<str>,
+    html: Arc<str>,
+    /// The value of the use counter when the page was last served.
+    last_used: u64,
+}
+
+#[derive(Debug, Default)]
+struct Pages {
+    pages: HashMap<String, StaticPage>,
+    counter: u64,
+}
+
+/// Rendered static pages by path.
+///
+/// The number of pages is limited, so paths matched by patterns like `/blog/**` cannot fill the
+/// memory of the server. The least recently used page is removed when the limit is reached.
+#[derive(Debug)]
+pub(crate) struct StaticPages {
+    inner: Mutex<Pages>,
+    max_pages: usize,
+}
+
+impl StaticPages {
+    pub fn new(max_pages: usize) -> Self {
+        Self {
+            inner: Mutex::default(),
+            max_pages,
+        }
+    }
+
+    /// Returns the page of a path if it was rendered from the same template.
+    pub fn get(&self, path: &str, template: &str) -> Option<Arc<str>> {
+        let mut inner = self.inner.lock().expect("failed to lock static pages");
+        inner.counter += 1;
+        let counter = inner.counter;
+
+        let page = inner
+            .pages
+            .get_mut(path)
+            .filter(|m| &*m.template == template)?;
+        page.last_used = counter;
+
+        Some(page.html.clone())
+    }
+
+    pub fn insert(&self, path: String, template: Arc<str>, html: Arc<str>) {
+        if self.max_pages == 0 {
+            return;
+        }
+
+        let mut inner = self.inner.lock().expect("failed to lock static pages");
+        inner.counter += 1;
+        let last_used = inner.counter;
+
+        if !inner.pages.contains_key(&path) && inner.pages.len() >= self.max_pages {
+            let least_used = inner
+                .pages
+                .iter()
+                .min_by_key(|(_, m)| m.last_used)
+                .map(|(path, _)| path.clone());
+
+            if let Some(m) = least_used {
+                inner.pages.remove(&m);
+            }
+        }
+
+        inner.pages.insert(
+            path,
+            StaticPage {
+                template,
+                html,
+                last_used,
+            },
+        );
+    }
+}
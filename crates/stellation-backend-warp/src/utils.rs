@@ -1,7 +1,6 @@
 //! Warp utilities.
 
-use futures::Future;
-use yew::platform::{LocalHandle, Runtime};
+use std::any::Any;
 
 /// Creates a random string.
 pub(crate) fn random_str() -> String {
@@ -15,16 +14,13 @@ pub(crate) fn random_str() -> String {
         .collect()
 }
 
-pub(crate) fn spawn_pinned_or_local<F, Fut>(create_task: F)
-where
-    F: FnOnce() -> Fut,
-    F: Send + 'static,
-    Fut: Future<Output = ()> + 'static,
-{
-    // We spawn into a local runtime early for higher efficiency.
-    match LocalHandle::try_current() {
-        Some(handle) => handle.spawn_local(create_task()),
-        // TODO: Allow Overriding Runtime with Endpoint.
-        None => Runtime::default().spawn_pinned(create_task),
+/// Returns the message of a panic payload.
+pub(crate) fn panic_message(payload: Box<dyn Any + Send>) -> String {
+    match payload.downcast::<String>() {
+        Ok(m) => *m,
+        Err(payload) => payload
+            .downcast_ref::<&str>()
+            .map(|m| m.to_string())
+            .unwrap_or_else(|| "unknown panic".to_string()),
     }
 }
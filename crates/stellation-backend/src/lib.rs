@@ -20,6 +20,8 @@ pub use error::{ServerAppError, ServerAppResult};
 pub use props::ServerAppProps;
 mod request;
 pub use request::{RenderRequest, Request};
+#[doc(inline)]
+pub use stellation_bridge::Identity;
 mod renderer;
 pub use renderer::ServerRenderer;
 pub mod hooks;
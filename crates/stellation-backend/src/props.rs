@@ -3,7 +3,9 @@ use std::rc::Rc;
 
 use http::HeaderMap;
 use serde::{Deserialize, Serialize};
+use stellation_bridge::Identity;
 use yew::Properties;
+use yew_router::Routable;
 
 use crate::error::ServerAppResult;
 use crate::Request;
@@ -47,6 +49,21 @@ where
         self.request.context()
     }
 
+    /// Returns the identity of the client, if the client is authenticated.
+    pub fn identity(&self) -> Option<&Identity> {
+        self.request.identity()
+    }
+
+    /// Returns the route of current request.
+    ///
+    /// Returns `None` if the path does not match any route.
+    pub fn route<R>(&self) -> Option<R>
+    where
+        R: Routable,
+    {
+        self.request.route()
+    }
+
     pub(crate) fn from_request(request: Rc<REQ>) -> Self {
         Self {
             request,
@@ -5,6 +5,7 @@ use std::rc::Rc;
 
 use bounce::helmet::render_static;
 use stellation_bridge::links::{Link, PhantomLink};
+use stellation_bridge::state::FormSubmissionState;
 use stellation_bridge::Bridge;
 use yew::BaseComponent;
 
@@ -92,6 +93,31 @@ where
 
         let request: Rc<_> = request.into();
 
+        // The frontend reads the base path to route under the same path.
+        let basename = match request.base_path() {
+            "/" => None,
+            m => {
+                let _ = write!(
+                    &mut head_s,
+                    r#"<meta name="stellation-base-path" content="{}">"#,
+                    m.replace('&', "&amp;").replace('"', "&quot;")
+                );
+
+                Some(m.to_owned().into())
+            }
+        };
+
+        // The frontend restores the submitted form to hydrate the same result.
+        let form_submission = request.form_submission().cloned().map(|m| {
+            let _ = write!(
+                &mut head_s,
+                r#"<meta name="stellation-form-submission" content="{}">"#,
+                m.encode()
+            );
+
+            FormSubmissionState::from_submission(m)
+        });
+
         if !request.is_client_only() {
             let head_contents = HeadContents::new();
 
@@ -105,6 +131,8 @@ where
                     helmet_writer: writer,
                     bridge,
                     head_contents: head_contents.clone(),
+                    basename,
+                    form_submission,
                 },
             )
             .render()
@@ -1,5 +1,8 @@
 use http::HeaderMap;
 use serde::{Deserialize, Serialize};
+use stellation_bridge::routines::FormSubmission;
+use stellation_bridge::Identity;
+use yew_router::Routable;
 
 use crate::ServerAppResult;
 
@@ -27,6 +30,44 @@ pub trait Request {
 
     /// Returns the current request context.
     fn context(&self) -> &Self::Context;
+
+    /// Returns the path that the application is mounted at.
+    ///
+    /// This path starts and ends with `/`, defaults to `/`.
+    fn base_path(&self) -> &str {
+        "/"
+    }
+
+    /// Returns the identity of the client that sent current request.
+    ///
+    /// Returns `None` if the client is not authenticated.
+    fn identity(&self) -> Option<&Identity> {
+        None
+    }
+
+    /// Returns the route of current request.
+    ///
+    /// The path is matched relative to the base path. If the path does not match any route,
+    /// `None` is returned instead of the not found route of the routable.
+    fn route<R>(&self) -> Option<R>
+    where
+        R: Routable,
+    {
+        let path = match self
+            .path()
+            .strip_prefix(self.base_path().trim_end_matches('/'))
+        {
+            Some("") => "/",
+            Some(m) => m,
+            None => return None,
+        };
+
+        match R::recognize(path) {
+            // The not found route is returned by routables if no route matches the path.
+            Some(m) if R::not_found_route().as_ref() == Some(&m) && m.to_path() != path => None,
+            m => m,
+        }
+    }
 }
 
 /// A trait that describes a request for server-side rendering.
@@ -36,4 +77,11 @@ pub trait RenderRequest: Request {
 
     /// Returns true if this request should be rendered at the client side.
     fn is_client_only(&self) -> bool;
+
+    /// Returns the form submitted with this request, if any.
+    ///
+    /// The result of the submission is provided to the application during rendering.
+    fn form_submission(&self) -> Option<&FormSubmission> {
+        None
+    }
 }
@@ -4,7 +4,7 @@ use anymap2::AnyMap;
 use bounce::helmet::{HelmetBridge, StaticWriter};
 use bounce::BounceRoot;
 use stellation_bridge::links::Link;
-use stellation_bridge::state::BridgeState;
+use stellation_bridge::state::{BridgeState, FormSubmissionState};
 use stellation_bridge::Bridge;
 use yew::prelude::*;
 use yew_router::history::{AnyHistory, History, MemoryHistory};
@@ -23,6 +23,8 @@ where
     pub server_app_props: ServerAppProps<CTX, REQ>,
     pub bridge: Option<Bridge<L>>,
     pub head_contents: HeadContents,
+    pub basename: Option<AttrValue>,
+    pub form_submission: Option<FormSubmissionState>,
 }
 
 impl<CTX, REQ, L> PartialEq for StellationRootProps<CTX, REQ, L>
@@ -33,6 +35,8 @@ where
         self.helmet_writer == other.helmet_writer
             && self.server_app_props == other.server_app_props
             && self.bridge == other.bridge
+            && self.basename == other.basename
+            && self.form_submission == other.form_submission
     }
 }
 
@@ -46,6 +50,8 @@ where
             server_app_props: self.server_app_props.clone(),
             bridge: self.bridge.clone(),
             head_contents: self.head_contents.clone(),
+            basename: self.basename.clone(),
+            form_submission: self.form_submission.clone(),
         }
     }
 }
@@ -63,20 +69,25 @@ where
         server_app_props,
         bridge,
         head_contents,
+        basename,
+        form_submission,
         ..
     } = props.clone();
 
     let get_init_states = use_callback(
-        move |_, bridge| {
+        move |_, (bridge, form_submission)| {
             let mut states = AnyMap::new();
             states.insert(head_contents.clone());
             if let Some(m) = bridge.clone().map(BridgeState::from_bridge) {
                 states.insert(m);
             }
+            if let Some(m) = form_submission.clone() {
+                states.insert(m);
+            }
 
             states
         },
-        bridge,
+        (bridge, form_submission),
     );
 
     let history: AnyHistory = MemoryHistory::new().into();
@@ -94,7 +105,7 @@ where
     html! {
         <BounceRoot {get_init_states}>
             <HelmetBridge writer={helmet_writer} />
-            <Router {history}>
+            <Router {history} {basename}>
                 {children}
             </Router>
         </BounceRoot>
@@ -5,8 +5,9 @@ use yew::prelude::*;
 use yew::suspense::SuspensionResult;
 
 use crate::hooks::{
-    use_bridged_mutation, use_bridged_query, use_bridged_query_value, UseBridgedMutationHandle,
-    UseBridgedQueryHandle, UseBridgedQueryValueHandle,
+    use_bridged_form, use_bridged_mutation, use_bridged_query, use_bridged_query_value,
+    UseBridgedFormHandle, UseBridgedMutationHandle, UseBridgedQueryHandle,
+    UseBridgedQueryValueHandle,
 };
 use crate::links::Link;
 use crate::routines::{BridgedMutation, BridgedQuery};
@@ -66,6 +67,15 @@ where
         use_bridged_mutation()
     }
 
+    /// Bridges a mutation submitted with a HTML form.
+    pub fn use_form<T>() -> impl Hook<Output = UseBridgedFormHandle<T, L>>
+    where
+        T: 'static + BridgedMutation,
+        L: 'static,
+    {
+        use_bridged_form()
+    }
+
     /// Bridges a query.
     pub fn use_query<T>(
         input: Rc<T::Input>,
@@ -1,5 +1,7 @@
 use std::any::TypeId;
+use std::time::Duration;
 
+use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
 /// The bridge error type.
@@ -13,6 +15,48 @@ pub enum BridgeError {
     #[error("failed to encode / decode content")]
     Encoding(#[from] bincode::Error),
 
+    /// The bridge failed to decode a submitted form.
+    #[error("failed to decode form")]
+    InvalidForm(#[from] serde_urlencoded::de::Error),
+
+    /// The body of a streaming routine cannot be read.
+    #[error("failed to read body")]
+    Body(#[source] Box<dyn std::error::Error + Send + Sync>),
+
+    /// The body exceeds the size limit.
+    #[error("body exceeds the size limit of {} bytes", .0)]
+    TooLarge(u64),
+
+    /// The input of a routine is rejected by its validation.
+    #[error("invalid input: {}", .0)]
+    Validation(ValidationError),
+
+    /// The routine requires an authenticated client.
+    #[error("authentication is required")]
+    Unauthenticated,
+
+    /// The server refused the request, e.g.: the CSRF token of the request is missing or invalid.
+    #[error("request is forbidden")]
+    Forbidden,
+
+    /// The server responded with an unexpected status code, e.g.: the body exceeds the size limit
+    /// of the server or the server failed to process the request.
+    #[error("server responded with status {}", .0)]
+    Status(u16),
+
+    /// The routine has a rate limit, but the client has neither an identity with a subject nor a
+    /// known address to tell it apart from other clients.
+    #[error("failed to identify the client to apply the rate limit")]
+    UnknownClient,
+
+    /// The client has exceeded the rate limit of the routine.
+    #[error("rate limit exceeded, retry after {:?}", .0)]
+    RateLimited(Duration),
+
+    /// The link does not support this kind of routine.
+    #[error("routine is not supported by the link")]
+    Unsupported,
+
     /// The type does not have a valid index.
     #[error("failed to find type with index: {}", .0)]
     InvalidIndex(usize),
@@ -24,3 +68,48 @@ pub enum BridgeError {
 
 /// The bridge result type.
 pub type BridgeResult<T> = Result<T, BridgeError>;
+
+/// The error returned when the input of a routine fails to validate.
+///
+/// This error is sent to the client with a `400 Bad Request` response.
+#[derive(Error, Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[error("{}", self.display_errors())]
+pub struct ValidationError {
+    field_errors: Vec<(String, String)>,
+}
+
+impl ValidationError {
+    /// Creates an empty validation error.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Appends an error message for a field of the input.
+    pub fn with_field_error<F, M>(mut self, field: F, message: M) -> Self
+    where
+        F: Into<String>,
+        M: Into<String>,
+    {
+        self.field_errors.push((field.into(), message.into()));
+
+        self
+    }
+
+    /// Returns the errors of each field, in the order they are appended.
+    pub fn field_errors(&self) -> &[(String, String)] {
+        &self.field_errors
+    }
+
+    /// Returns `true` if no field has an error.
+    pub fn is_empty(&self) -> bool {
+        self.field_errors.is_empty()
+    }
+
+    fn display_errors(&self) -> String {
+        self.field_errors
+            .iter()
+            .map(|(field, message)| format!("{field}: {message}"))
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+}
@@ -1,9 +1,11 @@
 //! Hooks used to resolve requests.
 
+mod use_bridged_form;
 mod use_bridged_mutation;
 mod use_bridged_query;
 mod use_bridged_query_value;
 
+pub use use_bridged_form::{use_bridged_form, UseBridgedFormHandle};
 pub use use_bridged_mutation::{
     use_bridged_mutation, BridgedMutationState, UseBridgedMutationHandle,
 };
@@ -0,0 +1,123 @@
+use std::fmt;
+use std::rc::Rc;
+
+use bounce::{use_atom_value, use_selector_value};
+use yew::prelude::*;
+
+use super::{use_bridged_mutation, BridgedMutationState, UseBridgedMutationHandle};
+use crate::links::Link;
+use crate::routines::{BridgedMutation, MutationResult};
+use crate::state::{BridgeSelector, FormSubmissionState};
+use crate::BridgeError;
+
+/// A handle returned by [`use_bridged_form`].
+///
+/// This can be used to submit a form or access the result of the last submission.
+pub struct UseBridgedFormHandle<T, L>
+where
+    T: BridgedMutation + 'static,
+    L: 'static + Link,
+{
+    inner: UseBridgedMutationHandle<T, L>,
+    mutation_index: Option<usize>,
+    submitted: Rc<Option<MutationResult<T>>>,
+}
+
+impl<T, L> UseBridgedFormHandle<T, L>
+where
+    T: BridgedMutation + 'static,
+    L: 'static + Link,
+{
+    /// Returns the index of the mutation, which identifies the mutation of a submitted form.
+    ///
+    /// Returns `None` if the mutation is not registered to the link.
+    pub fn mutation_index(&self) -> Option<usize> {
+        self.mutation_index
+    }
+
+    /// Runs the mutation with an urlencoded form.
+    pub async fn submit(&self, form: &str) -> MutationResult<T> {
+        let input = serde_urlencoded::from_str::<T::Input>(form)
+            .map_err(BridgeError::InvalidForm)
+            .map_err(T::into_mutation_error)?;
+
+        self.inner.run(input).await
+    }
+
+    /// Returns the state of current mutation.
+    ///
+    /// Forms submitted before the application is loaded are not reflected in the state.
+    pub fn state(&self) -> &BridgedMutationState<T> {
+        self.inner.state()
+    }
+
+    /// Returns the result of last finished submission (if any).
+    ///
+    /// If the form has not been submitted since the application is loaded, this returns the result
+    /// of the form submitted before the application is loaded.
+    pub fn result(&self) -> Option<&MutationResult<T>> {
+        match self.inner.state() {
+            BridgedMutationState::Idle => self.submitted.as_ref().as_ref(),
+            _ => self.inner.result(),
+        }
+    }
+}
+
+impl<T, L> fmt::Debug for UseBridgedFormHandle<T, L>
+where
+    T: BridgedMutation + fmt::Debug + 'static,
+    L: 'static + Link,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("UseBridgedFormHandle")
+            .field("state", &self.state())
+            .field("result", &self.result())
+            .finish()
+    }
+}
+
+impl<T, L> Clone for UseBridgedFormHandle<T, L>
+where
+    T: BridgedMutation + 'static,
+    L: 'static + Link,
+{
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            mutation_index: self.mutation_index,
+            submitted: self.submitted.clone(),
+        }
+    }
+}
+
+/// Bridges a mutation submitted with a HTML form.
+///
+/// In addition to [`use_bridged_mutation`], this hook restores the result of the form submitted
+/// before the application is loaded.
+#[hook]
+pub fn use_bridged_form<T, L>() -> UseBridgedFormHandle<T, L>
+where
+    T: 'static + BridgedMutation,
+    L: 'static + Link,
+{
+    let inner = use_bridged_mutation::<T, L>();
+    let bridge = use_selector_value::<BridgeSelector<L>>();
+    let mutation_index = bridge.link().mutation_index::<T>().ok();
+
+    let submission = use_atom_value::<FormSubmissionState>();
+    let submitted = use_memo(
+        |(submission, mutation_index)| {
+            submission
+                .submission()
+                .filter(|m| Some(m.mutation_index()) == *mutation_index)
+                .map(|m| m.result::<T>())
+        },
+        (submission, mutation_index),
+    );
+
+    UseBridgedFormHandle {
+        inner,
+        mutation_index,
+        submitted,
+    }
+}
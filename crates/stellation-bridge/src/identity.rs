@@ -0,0 +1,68 @@
+use std::any::Any;
+use std::fmt;
+use std::sync::Arc;
+
+/// The identity of an authenticated client.
+///
+/// An identity can hold any value that describes the client, e.g.: the claims of a verified token
+/// or a session loaded from a cookie. The value can be retrieved with [`Identity::get`] by its
+/// type.
+#[derive(Clone)]
+pub struct Identity {
+    inner: Arc<dyn Any + Send + Sync>,
+    subject: Option<Arc<str>>,
+}
+
+impl fmt::Debug for Identity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Identity")
+            .field("subject", &self.subject)
+            .finish_non_exhaustive()
+    }
+}
+
+impl PartialEq for Identity {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.inner, &other.inner)
+    }
+}
+
+impl Eq for Identity {}
+
+impl Identity {
+    /// Creates an identity from a value.
+    pub fn new<T>(value: T) -> Self
+    where
+        T: 'static + Send + Sync,
+    {
+        Self {
+            inner: Arc::new(value),
+            subject: None,
+        }
+    }
+
+    /// Sets the subject of the identity, e.g.: the id of a user.
+    ///
+    /// The subject is used to tell clients apart, such as the key of rate limits.
+    pub fn with_subject<S>(mut self, subject: S) -> Self
+    where
+        S: Into<Arc<str>>,
+    {
+        self.subject = Some(subject.into());
+
+        self
+    }
+
+    /// Returns the subject of the identity, if any.
+    pub fn subject(&self) -> Option<&str> {
+        self.subject.as_deref()
+    }
+
+    /// Returns the value of the identity if it has the type `T`.
+    pub fn get<T>(&self) -> Option<&T>
+    where
+        T: 'static,
+    {
+        self.inner.downcast_ref()
+    }
+}
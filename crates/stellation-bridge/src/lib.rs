@@ -29,11 +29,15 @@
 mod bridge;
 mod error;
 pub mod hooks;
+mod identity;
 pub mod links;
+pub mod meta;
+pub mod rate_limit;
 pub mod registry;
 pub mod resolvers;
 pub mod routines;
 pub mod state;
 
 pub use bridge::Bridge;
-pub use error::{BridgeError, BridgeResult};
+pub use error::{BridgeError, BridgeResult, ValidationError};
+pub use identity::Identity;
@@ -1,19 +1,86 @@
-use std::cell::Cell;
+use std::cell::{Cell, RefCell};
+use std::time::Duration;
 
 use async_trait::async_trait;
-use futures::{future, FutureExt, TryFutureExt};
-use gloo_net::http::Request;
-use js_sys::Uint8Array;
+use futures::channel::oneshot;
+use futures::{future, stream, FutureExt, StreamExt, TryFutureExt};
+use gloo_net::http::{Request, Response};
+use js_sys::{Array, Reflect, Uint8Array};
 use typed_builder::TypedBuilder;
+use wasm_bindgen::closure::Closure;
+use wasm_bindgen::{JsCast, JsValue};
+use wasm_bindgen_futures::JsFuture;
+use web_sys::{
+    Blob, ProgressEvent, ReadableStream, ReadableStreamDefaultReader, XmlHttpRequest,
+    XmlHttpRequestResponseType,
+};
 
 use super::Link;
 use crate::registry::RoutineRegistry;
-use crate::routines::{BridgedMutation, BridgedQuery, MutationResult, QueryResult};
-use crate::{BridgeError, BridgeResult};
+use crate::routines::{
+    BridgedDownload, BridgedMutation, BridgedQuery, BridgedUpload, ByteStream, DownloadResult,
+    MutationResult, QueryResult,
+};
+use crate::{meta, BridgeError, BridgeResult, ValidationError};
+
+const BINCODE_CONTENT_TYPE: &str = "application/x-bincode";
+const STREAM_CONTENT_TYPE: &str = "application/octet-stream";
+const CSRF_HEADER_NAME: &str = "x-stellation-csrf-token";
+
+/// Returns the CSRF token issued to the current page by the server, if any.
+fn csrf_token() -> Option<String> {
+    meta::page_meta("csrf-token")
+}
+
+/// Creates the error of a rate limited request with the value of the `Retry-After` header.
+fn rate_limited(retry_after: Option<String>) -> BridgeError {
+    let retry_after = retry_after
+        .and_then(|m| m.trim().parse::<u64>().ok())
+        .unwrap_or(1);
+
+    BridgeError::RateLimited(Duration::from_secs(retry_after))
+}
+
+/// Returns the error of a response status, `None` if the request is successful.
+fn status_error(status: u16, retry_after: Option<String>) -> Option<BridgeError> {
+    let e = match status {
+        200..=299 => return None,
+        401 => BridgeError::Unauthenticated,
+        403 => BridgeError::Forbidden,
+        429 => rate_limited(retry_after),
+        m => BridgeError::Status(m),
+    };
+
+    Some(e)
+}
+
+/// Reads the content of a bridge response.
+///
+/// Inputs rejected by the server are responded with `400 Bad Request` and an encoded
+/// [`ValidationError`].
+async fn read_encoded(resp: Response) -> BridgeResult<Vec<u8>> {
+    let is_encoded_error = resp.status() == 400
+        && resp.headers().get("content-type").as_deref() == Some(BINCODE_CONTENT_TYPE);
+
+    if is_encoded_error {
+        let content = resp.binary().await.map_err(BridgeError::Network)?;
+        let e: ValidationError = bincode::deserialize(&content)?;
+        return Err(BridgeError::Validation(e));
+    }
+
+    if let Some(e) = status_error(resp.status(), resp.headers().get("retry-after")) {
+        return Err(e);
+    }
+
+    resp.binary().await.map_err(BridgeError::Network)
+}
 
 /// A Link implemented with `fetch`, this requires a WebAssembly target with available global
 /// `fetch`.
 ///
+/// If the server issues a CSRF token to the current page, the token is attached to all requests
+/// sent by this link.
+///
 /// # Example
 ///
 /// ```
@@ -21,15 +88,16 @@ use crate::{BridgeError, BridgeResult};
 /// # use crate::registry::RoutineRegistry;
 /// # let routines = RoutineRegistry::builder().build();
 /// let link = FetchLink::builder()
-///     .url("/_bridge") // Defaults to `/_bridge`, which is also default on most first party implementations.
+///     .url("/_bridge") // Defaults to `_bridge` under the base path of the application.
 ///     .routines(routines)
 ///     .build();
 /// ```
 #[derive(TypedBuilder, Debug, Clone)]
 pub struct FetchLink {
-    /// The bridge URL, defaults to `/_bridge`, which is also the default used by official backend
+    /// The bridge URL, defaults to `_bridge` under the base path of the application (`/_bridge` if
+    /// the application is mounted at `/`), which is also the default used by official backend
     /// implementations.
-    #[builder(setter(into), default_code = r#""/_bridge".to_string()"#)]
+    #[builder(setter(into), default_code = r#"FetchLink::default_url()"#)]
     url: String,
     /// The routine registry for all registered routines.
     routines: RoutineRegistry,
@@ -60,6 +128,210 @@ impl FetchLink {
             m.get()
         })
     }
+
+    /// Returns the bridge url under the base path provided by the server.
+    fn default_url() -> String {
+        let base_path = meta::base_path().unwrap_or_else(|| "/".to_string());
+
+        format!("{base_path}_bridge")
+    }
+
+    /// Returns the url that receives uploads and downloads.
+    fn stream_url(&self) -> String {
+        format!("{}/stream", self.url.trim_end_matches('/'))
+    }
+
+    /// Uploads a body with an upload routine.
+    ///
+    /// The progress of the upload is reported with `on_progress`.
+    pub async fn upload<T, F>(
+        &self,
+        input: &T::Input,
+        body: &Blob,
+        on_progress: F,
+    ) -> MutationResult<T>
+    where
+        T: 'static + BridgedUpload,
+        F: 'static + Fn(UploadProgress),
+    {
+        let input = self
+            .routines
+            .encode_upload_input::<T>(input)
+            .map_err(T::into_mutation_error)?;
+
+        let output = self
+            .send_upload(&input, body, on_progress)
+            .await
+            .map_err(T::into_mutation_error)?;
+
+        self.routines.decode_mutation_output::<T>(&output)
+    }
+
+    /// Sends an upload with `XMLHttpRequest`, as `fetch` does not report the progress of uploads.
+    async fn send_upload<F>(
+        &self,
+        input: &[u8],
+        body: &Blob,
+        on_progress: F,
+    ) -> BridgeResult<Vec<u8>>
+    where
+        F: 'static + Fn(UploadProgress),
+    {
+        let body = Blob::new_with_blob_sequence(&Array::of2(&Uint8Array::from(input), body))
+            .map_err(js_error)?;
+
+        let xhr = XmlHttpRequest::new().map_err(js_error)?;
+        xhr.open("POST", &self.stream_url()).map_err(js_error)?;
+        xhr.set_request_header("content-type", STREAM_CONTENT_TYPE)
+            .map_err(js_error)?;
+        if let Some(ref m) = self.token {
+            xhr.set_request_header("authorization", &format!("Bearer {m}"))
+                .map_err(js_error)?;
+        }
+        if let Some(m) = csrf_token() {
+            xhr.set_request_header(CSRF_HEADER_NAME, &m)
+                .map_err(js_error)?;
+        }
+        xhr.set_response_type(XmlHttpRequestResponseType::Arraybuffer);
+
+        // The length of the input is excluded from the progress.
+        let input_len = input.len() as f64;
+        let on_progress = Closure::<dyn Fn(ProgressEvent)>::new(move |e: ProgressEvent| {
+            on_progress(UploadProgress {
+                loaded: (e.loaded() - input_len).max(0.0) as u64,
+                total: e
+                    .length_computable()
+                    .then(|| (e.total() - input_len).max(0.0) as u64),
+            })
+        });
+        xhr.upload()
+            .map_err(js_error)?
+            .set_onprogress(Some(on_progress.as_ref().unchecked_ref()));
+
+        let (tx, rx) = oneshot::channel();
+        let tx = RefCell::new(Some(tx));
+        let on_loadend = Closure::<dyn Fn()>::new(move || {
+            if let Some(m) = tx.borrow_mut().take() {
+                let _ = m.send(());
+            }
+        });
+        xhr.set_onloadend(Some(on_loadend.as_ref().unchecked_ref()));
+
+        xhr.send_with_opt_blob(Some(&body)).map_err(js_error)?;
+        let _ = rx.await;
+
+        let status = xhr.status().map_err(js_error)?;
+        // The status is 0 if the request is not sent.
+        if status == 0 {
+            return Err(BridgeError::Network(gloo_net::Error::GlooError(
+                "failed to send upload".to_string(),
+            )));
+        }
+
+        let retry_after = xhr.get_response_header("retry-after").map_err(js_error)?;
+        if let Some(e) = status_error(status, retry_after) {
+            return Err(e);
+        }
+
+        xhr.response()
+            .map(|m| Uint8Array::new(&m).to_vec())
+            .map_err(js_error)
+    }
+
+    /// Downloads the body resolved by a download routine.
+    pub async fn download<T>(&self, input: &T::Input) -> DownloadResult<T>
+    where
+        T: 'static + BridgedDownload,
+    {
+        let input = self
+            .routines
+            .encode_download_input::<T>(input)
+            .map_err(T::into_download_error)?;
+
+        let resp = future::ready(self.stream_url())
+            .map(|m| Request::post(&m))
+            .map(|m| m.header("content-type", STREAM_CONTENT_TYPE))
+            .map(|req| {
+                if let Some(ref m) = self.token {
+                    return req.header("authorization", &format!("Bearer {m}"));
+                }
+
+                req
+            })
+            .map(|req| match csrf_token() {
+                Some(m) => req.header(CSRF_HEADER_NAME, &m),
+                None => req,
+            })
+            .map(|m| m.body(Uint8Array::from(input.as_slice())))
+            .and_then(|m| m.send())
+            .await
+            .map_err(BridgeError::Network)
+            .map_err(T::into_download_error)?;
+
+        // Errors of the download are encoded with bincode.
+        if resp.headers().get("content-type").as_deref() == Some(BINCODE_CONTENT_TYPE) {
+            let output = resp
+                .binary()
+                .await
+                .map_err(BridgeError::Network)
+                .map_err(T::into_download_error)?;
+
+            return Err(self.routines.decode_download_error::<T>(&output));
+        }
+
+        if let Some(e) = status_error(resp.status(), resp.headers().get("retry-after")) {
+            return Err(T::into_download_error(e));
+        }
+
+        Ok(match resp.body() {
+            Some(m) => read_stream(m),
+            None => stream::empty().boxed_local(),
+        })
+    }
+}
+
+/// The progress of an upload.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UploadProgress {
+    /// The number of bytes sent.
+    pub loaded: u64,
+    /// The total number of bytes to send, if known.
+    pub total: Option<u64>,
+}
+
+fn js_error(e: JsValue) -> BridgeError {
+    BridgeError::Network(gloo_net::Error::GlooError(format!("{e:?}")))
+}
+
+/// Reads a readable stream of the browser into a byte stream.
+fn read_stream(body: ReadableStream) -> ByteStream {
+    let reader = body
+        .get_reader()
+        .unchecked_into::<ReadableStreamDefaultReader>();
+
+    stream::unfold(Some(reader), |reader| async move {
+        let reader = reader?;
+
+        let result = match JsFuture::from(reader.read()).await {
+            Ok(m) => m,
+            Err(e) => return Some((Err(js_error(e)), None)),
+        };
+
+        let done = Reflect::get(&result, &"done".into())
+            .ok()
+            .and_then(|m| m.as_bool())
+            .unwrap_or(true);
+        if done {
+            return None;
+        }
+
+        let chunk = Reflect::get(&result, &"value".into())
+            .map(|m| Uint8Array::new(&m).to_vec())
+            .map_err(js_error);
+
+        Some((chunk, Some(reader)))
+    })
+    .boxed_local()
 }
 
 #[async_trait(?Send)]
@@ -67,7 +339,7 @@ impl Link for FetchLink {
     async fn resolve_encoded(&self, input_buf: &[u8]) -> BridgeResult<Vec<u8>> {
         future::ready(self.url.as_str())
             .map(Request::post)
-            .map(|m| m.header("content-type", "application/x-bincode"))
+            .map(|m| m.header("content-type", BINCODE_CONTENT_TYPE))
             .map(|req| {
                 if let Some(ref m) = self.token {
                     return req.header("authorization", &format!("Bearer {m}"));
@@ -75,13 +347,24 @@ impl Link for FetchLink {
 
                 req
             })
+            .map(|req| match csrf_token() {
+                Some(m) => req.header(CSRF_HEADER_NAME, &m),
+                None => req,
+            })
             .map(move |m| m.body(&Uint8Array::from(input_buf)))
             .and_then(|m| m.send())
-            .and_then(|m| async move { m.binary().await })
             .map_err(BridgeError::Network)
+            .and_then(read_encoded)
             .await
     }
 
+    fn mutation_index<T>(&self) -> BridgeResult<usize>
+    where
+        T: 'static + BridgedMutation,
+    {
+        self.routines.mutation_index::<T>()
+    }
+
     async fn resolve_query<T>(&self, input: &T::Input) -> QueryResult<T>
     where
         T: 'static + BridgedQuery,
@@ -106,3 +389,38 @@ impl Link for FetchLink {
             .await
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn maps_status_to_error() {
+        assert!(status_error(200, None).is_none());
+        assert!(status_error(204, None).is_none());
+
+        assert!(matches!(
+            status_error(401, None),
+            Some(BridgeError::Unauthenticated)
+        ));
+        assert!(matches!(
+            status_error(403, None),
+            Some(BridgeError::Forbidden)
+        ));
+        assert!(matches!(
+            status_error(429, Some("30".to_string())),
+            Some(BridgeError::RateLimited(m)) if m == Duration::from_secs(30)
+        ));
+        assert!(matches!(
+            status_error(429, Some("soon".to_string())),
+            Some(BridgeError::RateLimited(m)) if m == Duration::from_secs(1)
+        ));
+
+        for status in [400, 404, 413, 500, 502, 503] {
+            assert!(matches!(
+                status_error(status, None),
+                Some(BridgeError::Status(m)) if m == status
+            ));
+        }
+    }
+}
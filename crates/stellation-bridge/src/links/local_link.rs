@@ -1,14 +1,19 @@
+use std::any::TypeId;
+use std::net::IpAddr;
 use std::sync::atomic::AtomicUsize;
 use std::sync::Arc;
 
 use async_trait::async_trait;
-use futures::{future, FutureExt, TryFutureExt};
+use futures::{future, stream, FutureExt, StreamExt, TryFutureExt};
 use typed_builder::TypedBuilder;
 
 use super::Link;
-use crate::registry::{ResolverRegistry, RoutineRegistry};
-use crate::routines::{BridgedMutation, BridgedQuery, MutationResult, QueryResult};
-use crate::BridgeResult;
+use crate::registry::{Client, ResolverRegistry, RoutineRegistry, StreamOutput};
+use crate::routines::{
+    BridgedDownload, BridgedMutation, BridgedQuery, BridgedUpload, ByteStream, DownloadResult,
+    FormSubmission, MutationResult, QueryResult,
+};
+use crate::{BridgeError, BridgeResult, Identity};
 
 /// A Link that resolves routine with local resolvers.
 ///
@@ -24,6 +29,17 @@ pub struct LocalLink<CTX = ()> {
     #[builder(setter(into))]
     context: Arc<CTX>,
 
+    /// The identity of the client, routines that require authentication are rejected without an
+    /// identity.
+    ///
+    /// Resolvers can read it with [`ResolverContext::identity`](crate::resolvers::ResolverContext::identity).
+    #[builder(default, setter(into))]
+    identity: Option<Identity>,
+
+    /// The ip address of the client, which is used to apply rate limits to anonymous clients.
+    #[builder(default, setter(into))]
+    remote_addr: Option<IpAddr>,
+
     /// The link equity tracker.
     #[builder(setter(skip), default_code = r#"LocalLink::<()>::next_id()"#)]
     id: usize,
@@ -41,6 +57,8 @@ impl<CTX> Clone for LocalLink<CTX> {
             routines: self.routines.clone(),
             resolvers: self.resolvers.clone(),
             context: self.context.clone(),
+            identity: self.identity.clone(),
+            remote_addr: self.remote_addr,
             id: self.id,
         }
     }
@@ -52,14 +70,101 @@ impl<CTX> LocalLink<CTX> {
 
         ID.fetch_add(1, std::sync::atomic::Ordering::SeqCst)
     }
+
+    /// Returns the identity of the client, if any.
+    pub fn identity(&self) -> Option<&Identity> {
+        self.identity.as_ref()
+    }
+
+    fn client(&self) -> Client {
+        Client::new()
+            .with_identity(self.identity.clone())
+            .with_remote_addr(self.remote_addr)
+    }
+
+    /// Resolves an upload with a body.
+    pub async fn resolve_upload<T>(&self, input: &T::Input, body: ByteStream) -> MutationResult<T>
+    where
+        T: 'static + BridgedUpload,
+    {
+        let input = self
+            .routines
+            .encode_upload_input::<T>(input)
+            .map_err(T::into_mutation_error)?;
+        let body = stream::once(future::ready(Ok(input)))
+            .chain(body)
+            .boxed_local();
+
+        match self
+            .resolvers
+            .resolve_stream(&self.context, &self.client(), body)
+            .await
+            .map_err(T::into_mutation_error)?
+        {
+            StreamOutput::Encoded(m) => self.routines.decode_mutation_output::<T>(&m),
+            StreamOutput::Stream(_) => Err(T::into_mutation_error(BridgeError::InvalidType(
+                TypeId::of::<T>(),
+            ))),
+        }
+    }
+
+    /// Resolves a download.
+    pub async fn resolve_download<T>(&self, input: &T::Input) -> DownloadResult<T>
+    where
+        T: 'static + BridgedDownload,
+    {
+        let input = self
+            .routines
+            .encode_download_input::<T>(input)
+            .map_err(T::into_download_error)?;
+        let body = stream::once(future::ready(Ok(input))).boxed_local();
+
+        match self
+            .resolvers
+            .resolve_stream(&self.context, &self.client(), body)
+            .await
+            .map_err(T::into_download_error)?
+        {
+            StreamOutput::Stream(m) => Ok(m),
+            StreamOutput::Encoded(m) => Err(self.routines.decode_download_error::<T>(&m)),
+        }
+    }
 }
 
 #[async_trait(?Send)]
 impl<CTX> Link for LocalLink<CTX> {
     async fn resolve_encoded(&self, input_buf: &[u8]) -> BridgeResult<Vec<u8>> {
         self.resolvers
-            .resolve_encoded(&self.context, input_buf)
+            .resolve_encoded(&self.context, &self.client(), input_buf)
+            .await
+    }
+
+    async fn resolve_stream(&self, body: ByteStream) -> BridgeResult<StreamOutput> {
+        self.resolvers
+            .resolve_stream(&self.context, &self.client(), body)
+            .await
+    }
+
+    fn is_mutation(&self, input_buf: &[u8]) -> BridgeResult<bool> {
+        self.resolvers.is_mutation(input_buf)
+    }
+
+    fn mutation_index<T>(&self) -> BridgeResult<usize>
+    where
+        T: 'static + BridgedMutation,
+    {
+        self.routines.mutation_index::<T>()
+    }
+
+    async fn resolve_form(
+        &self,
+        mutation_index: usize,
+        form: &[u8],
+    ) -> BridgeResult<FormSubmission> {
+        self.resolvers
+            .resolve_form(&self.context, &self.client(), mutation_index, form)
             .await
+            .map(|m| FormSubmission::new(mutation_index, m))
     }
 
     async fn resolve_query<T>(&self, input: &T::Input) -> QueryResult<T>
@@ -68,7 +173,11 @@ impl<CTX> Link for LocalLink<CTX> {
     {
         future::ready(input)
             .map(|m| self.routines.encode_query_input::<T>(m))
-            .and_then(|m| async move { self.resolvers.resolve_encoded(&self.context, &m).await })
+            .and_then(|m| async move {
+                self.resolvers
+                    .resolve_encoded(&self.context, &self.client(), &m)
+                    .await
+            })
             .map_err(T::into_query_error)
             .and_then(|m| async move { self.routines.decode_query_output::<T>(&m) })
             .await
@@ -80,7 +189,11 @@ impl<CTX> Link for LocalLink<CTX> {
     {
         future::ready(input)
             .map(|m| self.routines.encode_mutation_input::<T>(m))
-            .and_then(|m| async move { self.resolvers.resolve_encoded(&self.context, &m).await })
+            .and_then(|m| async move {
+                self.resolvers
+                    .resolve_encoded(&self.context, &self.client(), &m)
+                    .await
+            })
             .map_err(T::into_mutation_error)
             .and_then(|m| async move { self.routines.decode_mutation_output::<T>(&m) })
             .await
@@ -2,15 +2,20 @@
 //!
 //! For server-sided links, a new link should be created for each connection.
 
+use std::any::TypeId;
+
 use async_trait::async_trait;
 
-use crate::routines::{BridgedMutation, BridgedQuery, MutationResult, QueryResult};
-use crate::BridgeResult;
+use crate::registry::StreamOutput;
+use crate::routines::{
+    BridgedMutation, BridgedQuery, ByteStream, FormSubmission, MutationResult, QueryResult,
+};
+use crate::{BridgeError, BridgeResult};
 mod fetch_link;
 mod local_link;
 mod phantom_link;
 
-pub use fetch_link::FetchLink;
+pub use fetch_link::{FetchLink, UploadProgress};
 pub use local_link::LocalLink;
 pub use phantom_link::PhantomLink;
 
@@ -31,4 +36,40 @@ pub trait Link: PartialEq + Clone {
     ///
     /// Returns `BridgeError` when a malformed input is provided.
     async fn resolve_encoded(&self, input_buf: &[u8]) -> BridgeResult<Vec<u8>>;
+
+    /// Returns `true` if a routine with encoded input incurs side-effects on the server.
+    ///
+    /// Links that cannot tell the kind of a routine treat all routines as mutations.
+    fn is_mutation(&self, _input_buf: &[u8]) -> BridgeResult<bool> {
+        Ok(true)
+    }
+
+    /// Returns the index of a mutation.
+    ///
+    /// This is used to identify the mutation of a submitted form.
+    fn mutation_index<T>(&self) -> BridgeResult<usize>
+    where
+        T: 'static + BridgedMutation,
+    {
+        Err(BridgeError::InvalidType(TypeId::of::<T>()))
+    }
+
+    /// Resolves a mutation with an urlencoded form.
+    ///
+    /// Returns `BridgeError` when the form cannot be decoded into the input of the mutation or the
+    /// link cannot resolve mutations locally.
+    async fn resolve_form(
+        &self,
+        mutation_index: usize,
+        _form: &[u8],
+    ) -> BridgeResult<FormSubmission> {
+        Err(BridgeError::InvalidIndex(mutation_index))
+    }
+
+    /// Resolves an upload or a download with a streamed body.
+    ///
+    /// Returns `BridgeError::Unsupported` if the link cannot resolve routines locally.
+    async fn resolve_stream(&self, _body: ByteStream) -> BridgeResult<StreamOutput> {
+        Err(BridgeError::Unsupported)
+    }
 }
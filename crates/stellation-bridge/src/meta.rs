@@ -0,0 +1,24 @@
+//! Values provided to the page by the server with `<meta name="stellation-*">` tags.
+
+/// Returns the content of the `<meta name="stellation-{name}">` tag of the current page.
+///
+/// This always returns `None` outside of the browser.
+pub fn page_meta(name: &str) -> Option<String> {
+    if !cfg!(target_arch = "wasm32") {
+        return None;
+    }
+
+    web_sys::window()
+        .and_then(|m| m.document())
+        .and_then(|m| {
+            m.query_selector(&format!(r#"meta[name="stellation-{name}"]"#))
+                .ok()
+                .flatten()
+        })
+        .and_then(|m| m.get_attribute("content"))
+}
+
+/// Returns the path that the application is mounted at, if provided by the server.
+pub fn base_path() -> Option<String> {
+    page_meta("base-path")
+}
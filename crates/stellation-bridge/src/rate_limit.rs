@@ -0,0 +1,269 @@
+//! Rate limiting for routines.
+//!
+//! Rate limits are configured per routine with
+//! [`ResolverRegistryBuilder::with_rate_limit`](crate::registry::ResolverRegistryBuilder::with_rate_limit).
+//! Each client has a token bucket for each limited routine, which is stored in a
+//! [`RateLimitStore`]. Buckets are stored in a [`MemoryStore`] shared by the process unless another
+//! store is provided.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use async_trait::async_trait;
+
+/// Buckets are pruned at most once in this interval.
+const PRUNE_INTERVAL: Duration = Duration::from_secs(60);
+
+/// The rate limit of a routine.
+///
+/// A client can resolve the routine up to `burst` times at once, after which the requests are
+/// limited to the rate of the limit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RateLimit {
+    burst: u32,
+    interval: Duration,
+}
+
+impl RateLimit {
+    /// Creates a rate limit that allows a request every `interval`.
+    ///
+    /// The burst defaults to 1.
+    pub fn new(interval: Duration) -> Self {
+        Self { burst: 1, interval }
+    }
+
+    /// Creates a rate limit that allows `requests` requests per second.
+    ///
+    /// The burst defaults to the number of requests.
+    pub fn per_second(requests: u32) -> Self {
+        Self::per_period(requests, Duration::from_secs(1))
+    }
+
+    /// Creates a rate limit that allows `requests` requests per minute.
+    ///
+    /// The burst defaults to the number of requests.
+    pub fn per_minute(requests: u32) -> Self {
+        Self::per_period(requests, Duration::from_secs(60))
+    }
+
+    fn per_period(requests: u32, period: Duration) -> Self {
+        let requests = requests.max(1);
+
+        Self {
+            burst: requests,
+            interval: period / requests,
+        }
+    }
+
+    /// Sets the number of requests that can be sent at once.
+    pub fn with_burst(mut self, burst: u32) -> Self {
+        self.burst = burst.max(1);
+
+        self
+    }
+
+    /// Returns the number of requests that can be sent at once.
+    pub fn burst(&self) -> u32 {
+        self.burst
+    }
+
+    /// Returns the interval in which a request is allowed after the burst is used.
+    pub fn interval(&self) -> Duration {
+        self.interval
+    }
+}
+
+/// The key of a token bucket.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct RateLimitKey {
+    routine: &'static str,
+    client: String,
+}
+
+impl RateLimitKey {
+    pub(crate) fn new(routine: &'static str, client: String) -> Self {
+        Self { routine, client }
+    }
+
+    /// Returns the type name of the routine.
+    ///
+    /// Routines are identified by their types rather than their indices, so registries with
+    /// different routines do not share buckets.
+    pub fn routine(&self) -> &str {
+        self.routine
+    }
+
+    /// Returns the key of the client.
+    ///
+    /// This is the subject of the identity for authenticated clients and the ip address
+    /// otherwise.
+    pub fn client(&self) -> &str {
+        &self.client
+    }
+}
+
+/// A store of token buckets.
+///
+/// Implement this trait to share rate limits between multiple servers, e.g.: with a key-value
+/// store.
+#[async_trait]
+pub trait RateLimitStore: fmt::Debug + Send + Sync {
+    /// Takes a token from the bucket of a key.
+    ///
+    /// Returns the duration after which a token is available if the bucket is empty.
+    async fn acquire(&self, key: &RateLimitKey, limit: &RateLimit) -> Result<(), Duration>;
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Bucket {
+    tokens: f64,
+    updated_at: Instant,
+}
+
+impl Bucket {
+    /// Refills the bucket with tokens accumulated since the last update.
+    fn refill(&mut self, limit: &RateLimit, now: Instant) {
+        let elapsed = now.saturating_duration_since(self.updated_at);
+        let refilled = elapsed.as_secs_f64() / limit.interval.as_secs_f64().max(f64::EPSILON);
+
+        self.tokens = (self.tokens + refilled).min(f64::from(limit.burst));
+        self.updated_at = now;
+    }
+}
+
+#[derive(Debug)]
+struct Buckets {
+    inner: HashMap<RateLimitKey, (Bucket, RateLimit)>,
+    pruned_at: Option<Instant>,
+}
+
+/// A store that keeps token buckets in memory.
+///
+/// Buckets are not shared between servers or processes.
+#[derive(Debug)]
+pub struct MemoryStore {
+    buckets: Mutex<Buckets>,
+}
+
+impl Default for MemoryStore {
+    fn default() -> Self {
+        Self {
+            buckets: Mutex::new(Buckets {
+                inner: HashMap::new(),
+                pruned_at: None,
+            }),
+        }
+    }
+}
+
+impl MemoryStore {
+    /// Creates a memory store.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the store shared by registries without a store.
+    ///
+    /// Registries are usually created for each request, so the buckets need to outlive them.
+    /// Buckets are keyed by the type of the routine, so registries only share the buckets of the
+    /// routines they have in common.
+    pub(crate) fn shared() -> Arc<Self> {
+        static STORE: Mutex<Option<Arc<MemoryStore>>> = Mutex::new(None);
+
+        STORE
+            .lock()
+            .expect("failed to lock store")
+            .get_or_insert_with(Default::default)
+            .clone()
+    }
+}
+
+#[async_trait]
+impl RateLimitStore for MemoryStore {
+    async fn acquire(&self, key: &RateLimitKey, limit: &RateLimit) -> Result<(), Duration> {
+        let now = Instant::now();
+        let mut buckets = self.buckets.lock().expect("failed to lock buckets");
+
+        // Full buckets are the same as buckets that do not exist, so they can be removed.
+        let pruned_at = *buckets.pruned_at.get_or_insert(now);
+        if now.saturating_duration_since(pruned_at) >= PRUNE_INTERVAL {
+            buckets.inner.retain(|_, (bucket, limit)| {
+                bucket.refill(limit, now);
+                bucket.tokens < f64::from(limit.burst)
+            });
+            buckets.pruned_at = Some(now);
+        }
+
+        let (bucket, _) = buckets.inner.entry(key.clone()).or_insert_with(|| {
+            (
+                Bucket {
+                    tokens: f64::from(limit.burst),
+                    updated_at: now,
+                },
+                *limit,
+            )
+        });
+        bucket.refill(limit, now);
+
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            return Ok(());
+        }
+
+        Err(limit.interval.mul_f64(1.0 - bucket.tokens))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::executor::block_on;
+
+    use super::*;
+
+    fn key(routine: &'static str, client: &str) -> RateLimitKey {
+        RateLimitKey::new(routine, client.to_owned())
+    }
+
+    #[test]
+    fn limits_requests_after_burst() {
+        let store = MemoryStore::new();
+        let limit = RateLimit::per_minute(2);
+        let key = key("Routine", "ip:127.0.0.1");
+
+        assert_eq!(block_on(store.acquire(&key, &limit)), Ok(()));
+        assert_eq!(block_on(store.acquire(&key, &limit)), Ok(()));
+
+        let retry_after = block_on(store.acquire(&key, &limit)).expect_err("should be limited");
+        assert!(retry_after > Duration::ZERO && retry_after <= limit.interval());
+    }
+
+    #[test]
+    fn separates_routines_and_clients() {
+        let store = MemoryStore::new();
+        let limit = RateLimit::per_minute(1);
+
+        assert_eq!(block_on(store.acquire(&key("A", "ip:1"), &limit)), Ok(()));
+        assert_eq!(block_on(store.acquire(&key("B", "ip:1"), &limit)), Ok(()));
+        assert_eq!(block_on(store.acquire(&key("A", "ip:2"), &limit)), Ok(()));
+        assert!(block_on(store.acquire(&key("A", "ip:1"), &limit)).is_err());
+    }
+
+    #[test]
+    fn refills_bucket_over_time() {
+        let limit = RateLimit::new(Duration::from_secs(10)).with_burst(3);
+        let now = Instant::now();
+        let mut bucket = Bucket {
+            tokens: 0.0,
+            updated_at: now,
+        };
+
+        bucket.refill(&limit, now + Duration::from_secs(15));
+        assert_eq!(bucket.tokens, 1.5);
+
+        // Tokens do not accumulate beyond the burst.
+        bucket.refill(&limit, now + Duration::from_secs(100));
+        assert_eq!(bucket.tokens, 3.0);
+    }
+}
@@ -1,15 +1,156 @@
 //! Registries for Routines and their Resolvers.
 
+use std::fmt;
+use std::io;
+use std::net::IpAddr;
+
+use bincode::Options;
+use futures::{future, stream, StreamExt};
 use serde::{Deserialize, Serialize};
 
+use crate::routines::ByteStream;
+use crate::{BridgeError, BridgeResult, Identity};
+
 mod routine;
 pub use routine::*;
 
 mod resolver;
 pub use resolver::*;
 
+/// The maximum size of the encoded input of a streaming routine.
+const MAX_STREAM_INPUT_SIZE: usize = 1024 * 1024;
+
+/// The client that sent a request to the resolver registry.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Client {
+    identity: Option<Identity>,
+    remote_addr: Option<IpAddr>,
+}
+
+impl Client {
+    /// Creates an anonymous client with an unknown address.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the identity of the client.
+    pub fn with_identity(mut self, identity: Option<Identity>) -> Self {
+        self.identity = identity;
+
+        self
+    }
+
+    /// Sets the ip address of the client.
+    pub fn with_remote_addr(mut self, remote_addr: Option<IpAddr>) -> Self {
+        self.remote_addr = remote_addr;
+
+        self
+    }
+
+    /// Returns the identity of the client, if it is authenticated.
+    pub fn identity(&self) -> Option<&Identity> {
+        self.identity.as_ref()
+    }
+
+    /// Returns the ip address of the client, if it is known.
+    pub fn remote_addr(&self) -> Option<IpAddr> {
+        self.remote_addr
+    }
+
+    /// Returns the key that tells the client apart from other clients.
+    ///
+    /// The subject of the identity is preferred over the ip address. Clients without either
+    /// cannot be told apart and do not have a key.
+    fn key(&self) -> Option<String> {
+        match (self.identity().and_then(|m| m.subject()), self.remote_addr) {
+            (Some(m), _) => Some(format!("subject:{m}")),
+            (None, Some(m)) => Some(format!("ip:{m}")),
+            (None, None) => None,
+        }
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 struct Incoming<'a> {
     query_index: usize,
     input: &'a [u8],
 }
+
+/// Decodes a value with the same options as [`bincode::deserialize`].
+///
+/// Decoding fails before more than `limit` bytes are allocated.
+fn decode<'a, T>(buf: &'a [u8], limit: u64) -> bincode::Result<T>
+where
+    T: Deserialize<'a>,
+{
+    bincode::DefaultOptions::new()
+        .with_fixint_encoding()
+        .allow_trailing_bytes()
+        .with_limit(limit)
+        .deserialize(buf)
+}
+
+/// The output of a streaming routine.
+pub enum StreamOutput {
+    /// The encoded result of an upload or the encoded error of a failed download.
+    Encoded(Vec<u8>),
+    /// The content of a download.
+    Stream(ByteStream),
+}
+
+impl fmt::Debug for StreamOutput {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Encoded(m) => f.debug_tuple("Encoded").field(m).finish(),
+            Self::Stream(_) => f.write_str("Stream(_)"),
+        }
+    }
+}
+
+/// Prepends the length of an encoded input, so it can be read from the start of a stream.
+fn encode_stream_input(incoming: &[u8]) -> Vec<u8> {
+    let len = u32::try_from(incoming.len()).expect("input is too large");
+
+    let mut buf = Vec::with_capacity(incoming.len() + 4);
+    buf.extend_from_slice(&len.to_le_bytes());
+    buf.extend_from_slice(incoming);
+
+    buf
+}
+
+/// Reads an encoded input from the start of a stream.
+///
+/// Returns the input and the remaining content of the stream.
+async fn read_stream_input(mut body: ByteStream) -> BridgeResult<(Vec<u8>, ByteStream)> {
+    let mut buf = Vec::new();
+
+    loop {
+        if let Some(len) = buf.get(..4) {
+            let len = u32::from_le_bytes(len.try_into().expect("slice has 4 bytes")) as usize;
+
+            if len > MAX_STREAM_INPUT_SIZE {
+                return Err(BridgeError::TooLarge(MAX_STREAM_INPUT_SIZE as u64));
+            }
+
+            if buf.len() >= len + 4 {
+                let rest = buf.split_off(len + 4);
+                buf.drain(..4);
+
+                let body = stream::once(future::ready(Ok(rest)))
+                    .chain(body)
+                    .boxed_local();
+
+                return Ok((buf, body));
+            }
+        }
+
+        match body.next().await {
+            Some(m) => buf.extend_from_slice(&m?),
+            None => {
+                return Err(BridgeError::Encoding(
+                    bincode::ErrorKind::Io(io::ErrorKind::UnexpectedEof.into()).into(),
+                ))
+            }
+        }
+    }
+}
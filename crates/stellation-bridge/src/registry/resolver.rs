@@ -1,26 +1,100 @@
+use std::any::{type_name, TypeId};
 use std::fmt;
 use std::sync::Arc;
 
 use futures::future::{self, LocalBoxFuture};
 use futures::{FutureExt, TryFutureExt};
 
-use super::Incoming;
-use crate::resolvers::{MutationResolver, QueryResolver};
-use crate::{BridgeError, BridgeResult};
+use super::{decode, read_stream_input, Client, Incoming, StreamOutput};
+use crate::rate_limit::{MemoryStore, RateLimit, RateLimitKey, RateLimitStore};
+use crate::resolvers::{
+    DownloadResolver, MutationResolver, QueryResolver, ResolverContext, UploadResolver,
+};
+use crate::routines::{ByteStream, Validate};
+use crate::{BridgeError, BridgeResult, ValidationError};
 
-pub(super) type Resolver<CTX> =
-    Arc<dyn Send + Sync + Fn(&Arc<CTX>, &[u8]) -> LocalBoxFuture<'static, BridgeResult<Vec<u8>>>>;
+/// The default number of bytes that can be allocated when decoding an input.
+const DEFAULT_MAX_INPUT_SIZE: u64 = 2 * 1024 * 1024;
 
-pub(super) type Resolvers<CTX> = Vec<Resolver<CTX>>;
+pub(super) type Resolver<CTX> = Arc<
+    dyn Send
+        + Sync
+        + Fn(ResolverContext<CTX>, &[u8], u64) -> LocalBoxFuture<'static, BridgeResult<Vec<u8>>>,
+>;
+
+pub(super) type FormResolver<CTX> = Arc<
+    dyn Send
+        + Sync
+        + Fn(ResolverContext<CTX>, &[u8]) -> LocalBoxFuture<'static, BridgeResult<Vec<u8>>>,
+>;
+
+pub(super) type StreamResolver<CTX> = Arc<
+    dyn Send
+        + Sync
+        + Fn(
+            ResolverContext<CTX>,
+            &[u8],
+            u64,
+            ByteStream,
+        ) -> LocalBoxFuture<'static, BridgeResult<StreamOutput>>,
+>;
+
+/// Resolvers that accept encoded inputs, streaming routines cannot be resolved with an encoded
+/// input.
+pub(super) type Resolvers<CTX> = Vec<Option<Resolver<CTX>>>;
+
+/// Resolvers that accept urlencoded forms, only mutations can be resolved with a form.
+pub(super) type FormResolvers<CTX> = Vec<Option<FormResolver<CTX>>>;
+
+/// Resolvers that accept a streamed body, only uploads and downloads can be resolved with a body.
+pub(super) type StreamResolvers<CTX> = Vec<Option<StreamResolver<CTX>>>;
+
+/// A function that validates an input before it is resolved.
+type ValidateFn<I> = fn(&I) -> Result<(), ValidationError>;
+
+fn skip_validation<I>(_input: &I) -> Result<(), ValidationError> {
+    Ok(())
+}
+
+fn validate_input<I>(input: &I) -> Result<(), ValidationError>
+where
+    I: Validate,
+{
+    input.validate()
+}
+
+/// Validates a decoded input, returns a future that resolves into the error if it is rejected.
+fn check_input<I, T>(
+    input: BridgeResult<I>,
+    validate: ValidateFn<I>,
+) -> Result<I, LocalBoxFuture<'static, BridgeResult<T>>>
+where
+    T: 'static,
+{
+    input
+        .and_then(|m| validate(&m).map(|_| m).map_err(BridgeError::Validation))
+        .map_err(|e| future::err(e).boxed_local())
+}
 
 /// The Registry Builder for Resolver Registry
 pub struct ResolverRegistryBuilder<CTX = ()> {
     resolvers: Resolvers<CTX>,
+    form_resolvers: FormResolvers<CTX>,
+    stream_resolvers: StreamResolvers<CTX>,
+    /// Whether each routine incurs side-effects on the server.
+    mutations: Vec<bool>,
+    /// Whether each routine requires an authenticated client.
+    requires_auth: Vec<bool>,
+    rate_limits: Vec<Option<(&'static str, RateLimit)>>,
+    type_ids: Vec<TypeId>,
+    rate_limit_store: Option<Arc<dyn RateLimitStore>>,
+    max_input_size: u64,
 }
 
 impl fmt::Debug for ResolverRegistryBuilder {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.debug_struct("ResolverRegistryBuilder")
+            .field("max_input_size", &self.max_input_size)
             .finish_non_exhaustive()
     }
 }
@@ -29,6 +103,14 @@ impl<CTX> Default for ResolverRegistryBuilder<CTX> {
     fn default() -> Self {
         Self {
             resolvers: Vec::new(),
+            form_resolvers: Vec::new(),
+            stream_resolvers: Vec::new(),
+            mutations: Vec::new(),
+            requires_auth: Vec::new(),
+            rate_limits: Vec::new(),
+            type_ids: Vec::new(),
+            rate_limit_store: None,
+            max_input_size: DEFAULT_MAX_INPUT_SIZE,
         }
     }
 }
@@ -43,24 +125,145 @@ where
     }
 
     /// Creates a registry.
-    pub fn build(self) -> ResolverRegistry<CTX> {
+    pub fn build(mut self) -> ResolverRegistry<CTX> {
+        if self.rate_limit_store.is_none() && self.rate_limits.iter().any(|m| m.is_some()) {
+            self.rate_limit_store = Some(MemoryStore::shared());
+        }
+
         ResolverRegistry {
             inner: Arc::new(self),
         }
     }
 
+    /// Sets the maximum number of bytes that can be allocated when decoding an input.
+    ///
+    /// Inputs that exceed this limit are rejected with an encoding error before they are fully
+    /// decoded, which prevents a small request from claiming a large amount of memory with a forged
+    /// length prefix.
+    ///
+    /// Defaults to 2 MiB.
+    pub fn with_max_input_size(mut self, max_input_size: u64) -> Self {
+        self.max_input_size = max_input_size;
+
+        self
+    }
+
+    /// Requires an authenticated client to resolve a routine.
+    ///
+    /// Requests without an identity are rejected with [`BridgeError::Unauthenticated`]. The index of
+    /// the routine is read from the request first, the check runs before the input of the routine
+    /// is decoded and validated.
+    ///
+    /// Resolvers can read the identity with [`ResolverContext::identity`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if the routine is not registered before calling this method.
+    pub fn require_auth<T>(mut self) -> Self
+    where
+        T: 'static,
+    {
+        let index = self.routine_index::<T>();
+        self.requires_auth[index] = true;
+
+        self
+    }
+
+    /// Limits the rate a client can resolve a routine.
+    ///
+    /// Clients are told apart by the subject of their identity or their ip address, requests from
+    /// clients without either are rejected with [`BridgeError::UnknownClient`]. Requests exceeding
+    /// the limit are rejected with [`BridgeError::RateLimited`]. The index of the routine is read
+    /// from the request first, the limit is checked before the input of the routine is decoded and
+    /// validated.
+    ///
+    /// Clients behind a reverse proxy share the address of the proxy unless the endpoint trusts
+    /// the proxy to forward their addresses.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the routine is not registered before calling this method.
+    pub fn with_rate_limit<T>(mut self, limit: RateLimit) -> Self
+    where
+        T: 'static,
+    {
+        let index = self.routine_index::<T>();
+        self.rate_limits[index] = Some((type_name::<T>(), limit));
+
+        self
+    }
+
+    /// Sets the store of rate limits.
+    ///
+    /// Defaults to a [`MemoryStore`] shared by all registries in the process, buckets are keyed by
+    /// the type of the routine and the client.
+    pub fn with_rate_limit_store<S>(mut self, store: S) -> Self
+    where
+        S: 'static + RateLimitStore,
+    {
+        self.rate_limit_store = Some(Arc::new(store));
+
+        self
+    }
+
+    fn routine_index<T>(&self) -> usize
+    where
+        T: 'static,
+    {
+        self.type_ids
+            .iter()
+            .position(|m| *m == TypeId::of::<T>())
+            .expect("routine is not registered")
+    }
+
     /// Adds a Query Resolver
-    pub fn add_query<T>(mut self) -> Self
+    pub fn add_query<T>(self) -> Self
     where
         T: 'static + QueryResolver<Context = CTX>,
     {
-        let resolver = Arc::new(|ctx: &Arc<CTX>, input: &[u8]| {
-            let ctx = ctx.clone();
-            let input = match bincode::deserialize::<T::Input>(input)
-                .map_err(BridgeError::Encoding)
-                .map_err(future::err)
-                .map_err(|e| e.boxed_local())
-            {
+        self.push_query::<T>(skip_validation)
+    }
+
+    /// Adds a Query Resolver with an input that is validated before the query is resolved.
+    ///
+    /// Inputs that fail to validate are rejected with [`BridgeError::Validation`].
+    pub fn add_validated_query<T>(self) -> Self
+    where
+        T: 'static + QueryResolver<Context = CTX>,
+        T::Input: Validate,
+    {
+        self.push_query::<T>(validate_input)
+    }
+
+    /// Adds a Mutation Resolver
+    pub fn add_mutation<T>(self) -> Self
+    where
+        T: 'static + MutationResolver<Context = CTX>,
+    {
+        self.push_mutation::<T>(skip_validation)
+    }
+
+    /// Adds a Mutation Resolver with an input that is validated before the mutation is resolved.
+    ///
+    /// Inputs that fail to validate are rejected with [`BridgeError::Validation`], this applies to
+    /// mutations submitted with a form as well.
+    pub fn add_validated_mutation<T>(self) -> Self
+    where
+        T: 'static + MutationResolver<Context = CTX>,
+        T::Input: Validate,
+    {
+        self.push_mutation::<T>(validate_input)
+    }
+
+    fn push_query<T>(mut self, validate: ValidateFn<T::Input>) -> Self
+    where
+        T: 'static + QueryResolver<Context = CTX>,
+    {
+        let resolver = Arc::new(move |ctx: ResolverContext<CTX>, input: &[u8], limit: u64| {
+            let input = match check_input(
+                decode::<T::Input>(input, limit).map_err(BridgeError::Encoding),
+                validate,
+            ) {
                 Ok(m) => m,
                 Err(e) => return e,
             };
@@ -70,22 +273,39 @@ where
                 .boxed_local()
         });
 
-        self.resolvers.push(resolver);
+        self.resolvers.push(Some(resolver));
+        self.form_resolvers.push(None);
+        self.stream_resolvers.push(None);
+        self.mutations.push(false);
+        self.requires_auth.push(false);
+        self.rate_limits.push(None);
+        self.type_ids.push(TypeId::of::<T>());
         self
     }
 
-    /// Adds a Mutation Resolver
-    pub fn add_mutation<T>(mut self) -> Self
+    fn push_mutation<T>(mut self, validate: ValidateFn<T::Input>) -> Self
     where
         T: 'static + MutationResolver<Context = CTX>,
     {
-        let resolver = Arc::new(|ctx: &Arc<CTX>, input: &[u8]| {
-            let ctx = ctx.clone();
-            let input = match bincode::deserialize::<T::Input>(input)
+        let resolver = Arc::new(move |ctx: ResolverContext<CTX>, input: &[u8], limit: u64| {
+            let input = match check_input(
+                decode::<T::Input>(input, limit).map_err(BridgeError::Encoding),
+                validate,
+            ) {
+                Ok(m) => m,
+                Err(e) => return e,
+            };
+            async move { T::resolve(&ctx, &input).await }
+                .map(|m| bincode::serialize(&m.as_deref()))
                 .map_err(BridgeError::Encoding)
-                .map_err(future::err)
-                .map_err(|e| e.boxed_local())
-            {
+                .boxed_local()
+        });
+
+        let form_resolver = Arc::new(move |ctx: ResolverContext<CTX>, form: &[u8]| {
+            let input = match check_input(
+                serde_urlencoded::from_bytes::<T::Input>(form).map_err(BridgeError::InvalidForm),
+                validate,
+            ) {
                 Ok(m) => m,
                 Err(e) => return e,
             };
@@ -95,7 +315,83 @@ where
                 .boxed_local()
         });
 
-        self.resolvers.push(resolver);
+        self.resolvers.push(Some(resolver));
+        self.form_resolvers.push(Some(form_resolver));
+        self.stream_resolvers.push(None);
+        self.mutations.push(true);
+        self.requires_auth.push(false);
+        self.rate_limits.push(None);
+        self.type_ids.push(TypeId::of::<T>());
+        self
+    }
+
+    /// Adds an Upload Resolver
+    pub fn add_upload<T>(mut self) -> Self
+    where
+        T: 'static + UploadResolver<Context = CTX>,
+    {
+        let resolver = Arc::new(
+            |ctx: ResolverContext<CTX>, input: &[u8], limit: u64, body: ByteStream| {
+                let input = match decode::<T::Input>(input, limit)
+                    .map_err(BridgeError::Encoding)
+                    .map_err(future::err)
+                    .map_err(|e| e.boxed_local())
+                {
+                    Ok(m) => m,
+                    Err(e) => return e,
+                };
+                async move { T::resolve(&ctx, &input, body).await }
+                    .map(|m| bincode::serialize(&m.as_deref()))
+                    .map_ok(StreamOutput::Encoded)
+                    .map_err(BridgeError::Encoding)
+                    .boxed_local()
+            },
+        );
+
+        self.resolvers.push(None);
+        self.form_resolvers.push(None);
+        self.stream_resolvers.push(Some(resolver));
+        self.mutations.push(true);
+        self.requires_auth.push(false);
+        self.rate_limits.push(None);
+        self.type_ids.push(TypeId::of::<T>());
+        self
+    }
+
+    /// Adds a Download Resolver
+    pub fn add_download<T>(mut self) -> Self
+    where
+        T: 'static + DownloadResolver<Context = CTX>,
+    {
+        let resolver = Arc::new(
+            |ctx: ResolverContext<CTX>, input: &[u8], limit: u64, _body: ByteStream| {
+                let input = match decode::<T::Input>(input, limit)
+                    .map_err(BridgeError::Encoding)
+                    .map_err(future::err)
+                    .map_err(|e| e.boxed_local())
+                {
+                    Ok(m) => m,
+                    Err(e) => return e,
+                };
+                async move {
+                    match T::resolve(&ctx, &input).await {
+                        Ok(m) => Ok(StreamOutput::Stream(m)),
+                        Err(e) => bincode::serialize(&e)
+                            .map(StreamOutput::Encoded)
+                            .map_err(BridgeError::Encoding),
+                    }
+                }
+                .boxed_local()
+            },
+        );
+
+        self.resolvers.push(None);
+        self.form_resolvers.push(None);
+        self.stream_resolvers.push(Some(resolver));
+        self.mutations.push(false);
+        self.requires_auth.push(false);
+        self.rate_limits.push(None);
+        self.type_ids.push(TypeId::of::<T>());
         self
     }
 }
@@ -129,20 +425,259 @@ impl<CTX> Eq for ResolverRegistry<CTX> {}
 
 impl<CTX> ResolverRegistry<CTX> {
     /// Creates a Builder for remote registry.
-    pub fn builder() -> ResolverRegistryBuilder {
+    pub fn builder() -> ResolverRegistryBuilder<CTX>
+    where
+        CTX: 'static,
+    {
         ResolverRegistryBuilder::new()
     }
 
+    /// Returns an error if the client is not allowed to resolve a routine.
+    ///
+    /// This checks whether the routine requires an authenticated client and the rate limit of the
+    /// routine.
+    async fn authorize(&self, index: usize, client: &Client) -> BridgeResult<()> {
+        if self
+            .inner
+            .requires_auth
+            .get(index)
+            .copied()
+            .unwrap_or(false)
+            && client.identity().is_none()
+        {
+            return Err(BridgeError::Unauthenticated);
+        }
+
+        let limit = self.inner.rate_limits.get(index).and_then(|m| m.as_ref());
+        if let (Some((routine, limit)), Some(store)) = (limit, self.inner.rate_limit_store.as_ref())
+        {
+            let client = client.key().ok_or(BridgeError::UnknownClient)?;
+            let key = RateLimitKey::new(routine, client);
+
+            store
+                .acquire(&key, limit)
+                .await
+                .map_err(BridgeError::RateLimited)?;
+        }
+
+        Ok(())
+    }
+
     /// Resolves an encoded request.
-    pub async fn resolve_encoded(&self, ctx: &Arc<CTX>, incoming: &[u8]) -> BridgeResult<Vec<u8>> {
-        let incoming: Incoming<'_> = bincode::deserialize(incoming)?;
+    ///
+    /// The client is the client that sent the request.
+    pub async fn resolve_encoded(
+        &self,
+        ctx: &Arc<CTX>,
+        client: &Client,
+        incoming: &[u8],
+    ) -> BridgeResult<Vec<u8>> {
+        let limit = self.inner.max_input_size;
+        let incoming: Incoming<'_> = decode(incoming, limit)?;
+        self.authorize(incoming.query_index, client).await?;
 
         let resolver = self
             .inner
             .resolvers
             .get(incoming.query_index)
+            .and_then(|m| m.as_ref())
             .ok_or(BridgeError::InvalidIndex(incoming.query_index))?;
 
-        resolver(ctx, incoming.input).await
+        resolver(
+            ResolverContext::new(ctx.clone(), client.clone()),
+            incoming.input,
+            limit,
+        )
+        .await
+    }
+
+    /// Returns `true` if an encoded request resolves a mutation.
+    pub fn is_mutation(&self, incoming: &[u8]) -> BridgeResult<bool> {
+        let incoming: Incoming<'_> = decode(incoming, self.inner.max_input_size)?;
+
+        self.inner
+            .mutations
+            .get(incoming.query_index)
+            .copied()
+            .ok_or(BridgeError::InvalidIndex(incoming.query_index))
+    }
+
+    /// Resolves a mutation with an urlencoded form.
+    ///
+    /// The output is encoded in the same way as [`resolve_encoded`](Self::resolve_encoded).
+    pub async fn resolve_form(
+        &self,
+        ctx: &Arc<CTX>,
+        client: &Client,
+        mutation_index: usize,
+        form: &[u8],
+    ) -> BridgeResult<Vec<u8>> {
+        self.authorize(mutation_index, client).await?;
+
+        let resolver = self
+            .inner
+            .form_resolvers
+            .get(mutation_index)
+            .and_then(|m| m.as_ref())
+            .ok_or(BridgeError::InvalidIndex(mutation_index))?;
+
+        resolver(ResolverContext::new(ctx.clone(), client.clone()), form).await
+    }
+
+    /// Resolves an upload or a download with a streamed body.
+    ///
+    /// The body starts with the encoded input of the routine, which is followed by the content of
+    /// an upload.
+    pub async fn resolve_stream(
+        &self,
+        ctx: &Arc<CTX>,
+        client: &Client,
+        body: ByteStream,
+    ) -> BridgeResult<StreamOutput> {
+        let (incoming, body) = read_stream_input(body).await?;
+        let limit = self.inner.max_input_size;
+        let incoming: Incoming<'_> = decode(&incoming, limit)?;
+        self.authorize(incoming.query_index, client).await?;
+
+        let resolver = self
+            .inner
+            .stream_resolvers
+            .get(incoming.query_index)
+            .and_then(|m| m.as_ref())
+            .ok_or(BridgeError::InvalidIndex(incoming.query_index))?;
+
+        resolver(
+            ResolverContext::new(ctx.clone(), client.clone()),
+            incoming.input,
+            limit,
+            body,
+        )
+        .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::IpAddr;
+
+    use async_trait::async_trait;
+    use futures::executor::block_on;
+    use serde::{Deserialize, Serialize};
+
+    use super::*;
+    use crate::links::{Link, LocalLink};
+    use crate::rate_limit::{MemoryStore, RateLimit};
+    use crate::registry::RoutineRegistry;
+    use crate::routines::{BridgedMutation, MutationResult};
+    use crate::Identity;
+
+    #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, thiserror::Error)]
+    enum Error {
+        #[error("unauthenticated")]
+        Unauthenticated,
+        #[error("unknown client")]
+        UnknownClient,
+        #[error("rate limited")]
+        RateLimited,
+        #[error("bridge error")]
+        Bridge,
+    }
+
+    #[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+    struct Whoami {
+        subject: Option<String>,
+    }
+
+    impl BridgedMutation for Whoami {
+        type Error = Error;
+        type Input = ();
+
+        fn into_mutation_error(e: BridgeError) -> Self::Error {
+            match e {
+                BridgeError::Unauthenticated => Error::Unauthenticated,
+                BridgeError::UnknownClient => Error::UnknownClient,
+                BridgeError::RateLimited(_) => Error::RateLimited,
+                _ => Error::Bridge,
+            }
+        }
+    }
+
+    #[async_trait(?Send)]
+    impl MutationResolver for Whoami {
+        type Context = ();
+
+        async fn resolve(ctx: &ResolverContext, _input: &()) -> MutationResult<Self> {
+            Ok(Self {
+                subject: ctx.identity().and_then(|m| m.subject()).map(str::to_owned),
+            }
+            .into())
+        }
+    }
+
+    fn create_link(identity: Option<Identity>) -> LocalLink {
+        LocalLink::builder()
+            .routines(RoutineRegistry::builder().add_mutation::<Whoami>().build())
+            .resolvers(
+                ResolverRegistry::<()>::builder()
+                    .add_mutation::<Whoami>()
+                    .require_auth::<Whoami>()
+                    .build(),
+            )
+            .context(())
+            .identity(identity)
+            .build()
+    }
+
+    fn create_limited_link(remote_addr: Option<IpAddr>) -> LocalLink {
+        LocalLink::builder()
+            .routines(RoutineRegistry::builder().add_mutation::<Whoami>().build())
+            .resolvers(
+                ResolverRegistry::<()>::builder()
+                    .add_mutation::<Whoami>()
+                    .with_rate_limit::<Whoami>(RateLimit::per_minute(1))
+                    .with_rate_limit_store(MemoryStore::new())
+                    .build(),
+            )
+            .context(())
+            .remote_addr(remote_addr)
+            .build()
+    }
+
+    #[test]
+    fn rejects_anonymous_clients() {
+        let link = create_link(None);
+
+        let result = block_on(link.resolve_mutation::<Whoami>(&()));
+
+        assert_eq!(result, Err(Error::Unauthenticated));
+    }
+
+    #[test]
+    fn resolves_with_identity() {
+        let link = create_link(Some(Identity::new(()).with_subject("user-1")));
+
+        let result = block_on(link.resolve_mutation::<Whoami>(&())).map(|m| m.subject.clone());
+
+        assert_eq!(result, Ok(Some("user-1".to_owned())));
+    }
+
+    #[test]
+    fn rejects_rate_limited_clients_without_key() {
+        let link = create_limited_link(None);
+
+        let result = block_on(link.resolve_mutation::<Whoami>(&()));
+
+        assert_eq!(result, Err(Error::UnknownClient));
+    }
+
+    #[test]
+    fn rejects_clients_exceeding_rate_limit() {
+        let link = create_limited_link(Some([127, 0, 0, 1].into()));
+
+        assert!(block_on(link.resolve_mutation::<Whoami>(&())).is_ok());
+        assert_eq!(
+            block_on(link.resolve_mutation::<Whoami>(&())),
+            Err(Error::RateLimited)
+        );
     }
 }
@@ -3,8 +3,10 @@ use std::fmt;
 use std::rc::Rc;
 use std::sync::Arc;
 
-use super::Incoming;
-use crate::routines::{BridgedMutation, BridgedQuery, MutationResult, QueryResult};
+use super::{encode_stream_input, Incoming};
+use crate::routines::{
+    BridgedDownload, BridgedMutation, BridgedQuery, BridgedUpload, MutationResult, QueryResult,
+};
 use crate::{BridgeError, BridgeResult};
 
 /// The Registry Builder for Routine Registry
@@ -44,6 +46,28 @@ impl RoutineRegistryBuilder {
         self
     }
 
+    /// Adds an upload.
+    pub fn add_upload<T>(mut self) -> Self
+    where
+        T: 'static + BridgedUpload,
+    {
+        let type_id = TypeId::of::<T>();
+        self.query_ids.push(type_id);
+
+        self
+    }
+
+    /// Adds a download.
+    pub fn add_download<T>(mut self) -> Self
+    where
+        T: 'static + BridgedDownload,
+    {
+        let type_id = TypeId::of::<T>();
+        self.query_ids.push(type_id);
+
+        self
+    }
+
     /// Adds a query.
     pub fn add_query<T>(mut self) -> Self
     where
@@ -95,16 +119,7 @@ impl RoutineRegistry {
         T: 'static + BridgedQuery,
     {
         let input = bincode::serialize(&input).map_err(BridgeError::Encoding)?;
-        let type_id = TypeId::of::<T>();
-
-        let query_index = self
-            .inner
-            .query_ids
-            .iter()
-            .enumerate()
-            .find(|(_, m)| **m == type_id)
-            .ok_or(BridgeError::InvalidType(type_id))?
-            .0;
+        let query_index = self.routine_index(TypeId::of::<T>())?;
 
         let incoming = Incoming {
             query_index,
@@ -125,22 +140,30 @@ impl RoutineRegistry {
             .map(Rc::new)
     }
 
+    /// Returns the index of a routine.
+    fn routine_index(&self, type_id: TypeId) -> BridgeResult<usize> {
+        self.inner
+            .query_ids
+            .iter()
+            .position(|m| *m == type_id)
+            .ok_or(BridgeError::InvalidType(type_id))
+    }
+
+    /// Returns the index of a mutation.
+    pub(crate) fn mutation_index<T>(&self) -> BridgeResult<usize>
+    where
+        T: 'static + BridgedMutation,
+    {
+        self.routine_index(TypeId::of::<T>())
+    }
+
     /// The method to encode the mutation input for a remote link.
     pub(crate) fn encode_mutation_input<T>(&self, input: &T::Input) -> BridgeResult<Vec<u8>>
     where
         T: 'static + BridgedMutation,
     {
         let input = bincode::serialize(&input).map_err(BridgeError::Encoding)?;
-        let type_id = TypeId::of::<T>();
-
-        let query_index = self
-            .inner
-            .query_ids
-            .iter()
-            .enumerate()
-            .find(|(_, m)| **m == type_id)
-            .ok_or(BridgeError::InvalidType(type_id))?
-            .0;
+        let query_index = self.mutation_index::<T>()?;
 
         let incoming = Incoming {
             query_index,
@@ -160,4 +183,44 @@ impl RoutineRegistry {
             .map_err(T::into_mutation_error)?
             .map(Rc::new)
     }
+
+    /// The method to encode the upload input for a remote link.
+    ///
+    /// The encoded input is sent ahead of the body of the upload.
+    pub(crate) fn encode_upload_input<T>(&self, input: &T::Input) -> BridgeResult<Vec<u8>>
+    where
+        T: 'static + BridgedUpload,
+    {
+        self.encode_mutation_input::<T>(input)
+            .map(|m| encode_stream_input(&m))
+    }
+
+    /// The method to encode the download input for a remote link.
+    ///
+    /// The encoded input is sent as the body of the download request.
+    pub(crate) fn encode_download_input<T>(&self, input: &T::Input) -> BridgeResult<Vec<u8>>
+    where
+        T: 'static + BridgedDownload,
+    {
+        let input = bincode::serialize(&input).map_err(BridgeError::Encoding)?;
+        let query_index = self.routine_index(TypeId::of::<T>())?;
+
+        let incoming = Incoming {
+            query_index,
+            input: &input,
+        };
+
+        bincode::serialize(&incoming)
+            .map_err(BridgeError::Encoding)
+            .map(|m| encode_stream_input(&m))
+    }
+
+    /// The method to decode the error of a failed download for a remote link.
+    pub(crate) fn decode_download_error<T>(&self, output: &[u8]) -> T::Error
+    where
+        T: 'static + BridgedDownload,
+    {
+        bincode::deserialize::<T::Error>(output)
+            .unwrap_or_else(|e| T::into_download_error(BridgeError::Encoding(e)))
+    }
 }
@@ -1,8 +1,73 @@
 //! Bridge resolvers.
 
+use std::fmt;
+use std::net::IpAddr;
+use std::ops::Deref;
+use std::sync::Arc;
+
 use async_trait::async_trait;
 
-use crate::routines::{BridgedMutation, BridgedQuery, MutationResult, QueryResult};
+use crate::registry::Client;
+use crate::routines::{
+    BridgedDownload, BridgedMutation, BridgedQuery, BridgedUpload, ByteStream, DownloadResult,
+    MutationResult, QueryResult,
+};
+use crate::Identity;
+
+/// The context passed to resolvers.
+///
+/// This dereferences to the context of the bridge and carries the client that sent the request.
+pub struct ResolverContext<CTX = ()> {
+    context: Arc<CTX>,
+    client: Client,
+}
+
+impl<CTX> fmt::Debug for ResolverContext<CTX> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ResolverContext")
+            .field("client", &self.client)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<CTX> Clone for ResolverContext<CTX> {
+    fn clone(&self) -> Self {
+        Self {
+            context: self.context.clone(),
+            client: self.client.clone(),
+        }
+    }
+}
+
+impl<CTX> ResolverContext<CTX> {
+    /// Creates a resolver context.
+    pub fn new(context: Arc<CTX>, client: Client) -> Self {
+        Self { context, client }
+    }
+
+    /// Returns the context of the bridge.
+    pub fn context(&self) -> &CTX {
+        &self.context
+    }
+
+    /// Returns the identity of the client, if it is authenticated.
+    pub fn identity(&self) -> Option<&Identity> {
+        self.client.identity()
+    }
+
+    /// Returns the ip address of the client, if it is known.
+    pub fn remote_addr(&self) -> Option<IpAddr> {
+        self.client.remote_addr()
+    }
+}
+
+impl<CTX> Deref for ResolverContext<CTX> {
+    type Target = CTX;
+
+    fn deref(&self) -> &Self::Target {
+        &self.context
+    }
+}
 
 /// The resolver of a bridge query.
 ///
@@ -16,7 +81,10 @@ pub trait QueryResolver: BridgedQuery {
     type Context: 'static;
 
     /// Resolves the current query.
-    async fn resolve(meta: &Self::Context, input: &Self::Input) -> QueryResult<Self>;
+    async fn resolve(
+        ctx: &ResolverContext<Self::Context>,
+        input: &Self::Input,
+    ) -> QueryResult<Self>;
 }
 
 /// The resolver of a bridge mutation.
@@ -31,5 +99,47 @@ pub trait MutationResolver: BridgedMutation {
     type Context: 'static;
 
     /// Resolves the current mutation.
-    async fn resolve(meta: &Self::Context, input: &Self::Input) -> MutationResult<Self>;
+    async fn resolve(
+        ctx: &ResolverContext<Self::Context>,
+        input: &Self::Input,
+    ) -> MutationResult<Self>;
+}
+
+/// The resolver of a bridge upload.
+///
+/// This type is required to be implemented for `LocalLink`.
+/// Please refer to the crate implementation for more information.
+#[async_trait(?Send)]
+pub trait UploadResolver: BridgedUpload {
+    /// The context type.
+    ///
+    /// This type needs to match the `CTX` type parameter of the bridge it is added.
+    type Context: 'static;
+
+    /// Resolves the current upload.
+    ///
+    /// The body yields an error if it cannot be read or exceeds the size limit of the endpoint.
+    async fn resolve(
+        ctx: &ResolverContext<Self::Context>,
+        input: &Self::Input,
+        body: ByteStream,
+    ) -> MutationResult<Self>;
+}
+
+/// The resolver of a bridge download.
+///
+/// This type is required to be implemented for `LocalLink`.
+/// Please refer to the crate implementation for more information.
+#[async_trait(?Send)]
+pub trait DownloadResolver: BridgedDownload {
+    /// The context type.
+    ///
+    /// This type needs to match the `CTX` type parameter of the bridge it is added.
+    type Context: 'static;
+
+    /// Resolves the current download.
+    async fn resolve(
+        ctx: &ResolverContext<Self::Context>,
+        input: &Self::Input,
+    ) -> DownloadResult<Self>;
 }
@@ -4,10 +4,12 @@ use std::error::Error;
 use std::hash::Hash;
 use std::marker::PhantomData;
 use std::rc::Rc;
+use std::time::Duration;
 
+use futures::stream::LocalBoxStream;
 use serde::{Deserialize, Serialize};
 
-use crate::error::BridgeError;
+use crate::error::{BridgeError, BridgeResult, ValidationError};
 
 #[cold]
 fn panic_network_error(e: BridgeError) -> ! {
@@ -63,7 +65,158 @@ pub trait BridgedMutation: Serialize + for<'de> Deserialize<'de> + PartialEq {
 /// The mutation result type.
 pub type MutationResult<T> = std::result::Result<Rc<T>, <T as BridgedMutation>::Error>;
 
+/// Validates the input of a routine before it is resolved.
+///
+/// Routines registered with [`add_validated_query`] or [`add_validated_mutation`] reject inputs
+/// that fail to validate with a [`BridgeError::Validation`] and their resolvers are not called.
+///
+/// [`add_validated_query`]: crate::registry::ResolverRegistryBuilder::add_validated_query
+/// [`add_validated_mutation`]: crate::registry::ResolverRegistryBuilder::add_validated_mutation
+pub trait Validate {
+    /// Validates the current value.
+    fn validate(&self) -> Result<(), ValidationError>;
+}
+
+/// A Bridged Upload.
+///
+/// This types defines a mutation that receives a stream of bytes in addition to its input, which
+/// can be used to upload files.
+pub trait BridgedUpload: BridgedMutation {}
+
+/// A Bridged Download.
+///
+/// This types defines a request that resolves into a stream of bytes, which can be used to
+/// download files.
+pub trait BridgedDownload {
+    /// The Download Input.
+    type Input: 'static + Serialize + for<'de> Deserialize<'de>;
+    /// The Download Error.
+    type Error: 'static + Serialize + for<'de> Deserialize<'de> + Error + PartialEq + Clone;
+
+    /// Converts a BridgeError into the error type of current download.
+    ///
+    /// # Panics
+    ///
+    /// The default behaviour of a network error is panic.
+    /// Override this method to make the error fallible.
+    #[cold]
+    fn into_download_error(e: BridgeError) -> Self::Error {
+        panic_network_error(e);
+    }
+}
+
+/// A stream of bytes, used as the body of uploads and downloads.
+pub type ByteStream = LocalBoxStream<'static, BridgeResult<Vec<u8>>>;
+
+/// The download result type.
+pub type DownloadResult<T> = std::result::Result<ByteStream, <T as BridgedDownload>::Error>;
+
 /// A placeholder type until never type lands in std.
 #[derive(thiserror::Error, Debug, Serialize, Deserialize, PartialEq, Eq, Clone)]
 #[error("this never happens")]
 pub struct Never(PhantomData<()>);
+
+/// A bridge error of a submitted form, which can be embedded into the page.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+enum SubmissionError {
+    InvalidForm(String),
+    TooLarge(u64),
+    Validation(ValidationError),
+    Unauthenticated,
+    RateLimited(Duration),
+    InvalidIndex(usize),
+}
+
+impl SubmissionError {
+    fn into_bridge_error(self) -> BridgeError {
+        match self {
+            Self::InvalidForm(m) => BridgeError::InvalidForm(serde::de::Error::custom(m)),
+            Self::TooLarge(m) => BridgeError::TooLarge(m),
+            Self::Validation(m) => BridgeError::Validation(m),
+            Self::Unauthenticated => BridgeError::Unauthenticated,
+            Self::RateLimited(m) => BridgeError::RateLimited(m),
+            Self::InvalidIndex(m) => BridgeError::InvalidIndex(m),
+        }
+    }
+}
+
+/// The result of a mutation submitted with a HTML form.
+///
+/// When a form is submitted before the application is loaded, the server resolves the mutation and
+/// renders the page with this result, which is later restored by the frontend. If the submission
+/// is rejected, e.g.: by validation, the page is rendered with the error instead.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FormSubmission {
+    mutation_index: usize,
+    output: std::result::Result<Vec<u8>, SubmissionError>,
+}
+
+impl FormSubmission {
+    pub(crate) fn new(mutation_index: usize, output: Vec<u8>) -> Self {
+        Self {
+            mutation_index,
+            output: Ok(output),
+        }
+    }
+
+    /// Creates a submission of a mutation that failed with a bridge error.
+    ///
+    /// Errors that are caused by the submitted form or the client are kept, so the page can be
+    /// rendered with them. Other errors are returned.
+    pub fn from_error(mutation_index: usize, error: BridgeError) -> BridgeResult<Self> {
+        let error = match error {
+            BridgeError::InvalidForm(m) => SubmissionError::InvalidForm(m.to_string()),
+            BridgeError::TooLarge(m) => SubmissionError::TooLarge(m),
+            BridgeError::Validation(m) => SubmissionError::Validation(m),
+            BridgeError::Unauthenticated => SubmissionError::Unauthenticated,
+            BridgeError::RateLimited(m) => SubmissionError::RateLimited(m),
+            BridgeError::InvalidIndex(m) => SubmissionError::InvalidIndex(m),
+            m => return Err(m),
+        };
+
+        Ok(Self {
+            mutation_index,
+            output: Err(error),
+        })
+    }
+
+    /// Returns the index of the submitted mutation.
+    pub fn mutation_index(&self) -> usize {
+        self.mutation_index
+    }
+
+    /// Returns the result of the submitted mutation.
+    pub fn result<T>(&self) -> MutationResult<T>
+    where
+        T: 'static + BridgedMutation,
+    {
+        let output = self
+            .output
+            .as_ref()
+            .map_err(|e| T::into_mutation_error(e.clone().into_bridge_error()))?;
+
+        bincode::deserialize::<std::result::Result<T, T::Error>>(output)
+            .map_err(BridgeError::Encoding)
+            .map_err(T::into_mutation_error)?
+            .map(Rc::new)
+    }
+
+    /// Encodes the submission into a string that can be embedded into html.
+    pub fn encode(&self) -> String {
+        bincode::serialize(self)
+            .expect("failed to encode submission")
+            .into_iter()
+            .map(|m| format!("{m:02x}"))
+            .collect()
+    }
+
+    /// Decodes a submission encoded with [`encode`](Self::encode).
+    pub fn decode(s: &str) -> Option<Self> {
+        let buf = (0..s.len())
+            .step_by(2)
+            .map(|i| s.get(i..i + 2).and_then(|m| u8::from_str_radix(m, 16).ok()))
+            .collect::<Option<Vec<_>>>()?;
+
+        bincode::deserialize(&buf).ok()
+    }
+}
@@ -9,6 +9,7 @@ use std::rc::Rc;
 use bounce::{Atom, BounceStates, Selector};
 
 use crate::links::Link;
+use crate::routines::FormSubmission;
 use crate::Bridge;
 
 type SelectBridge<L> = Rc<dyn Fn(&BounceStates) -> Bridge<L>>;
@@ -168,3 +169,23 @@ where
         &self.inner
     }
 }
+
+/// The state of a form submitted before the application is loaded.
+#[derive(Atom, Debug, Default, Clone, PartialEq, Eq)]
+pub struct FormSubmissionState {
+    inner: Option<Rc<FormSubmission>>,
+}
+
+impl FormSubmissionState {
+    /// Creates a Form Submission State from a submission.
+    pub fn from_submission(submission: FormSubmission) -> Self {
+        Self {
+            inner: Some(submission.into()),
+        }
+    }
+
+    /// Returns the submission stored in the state, if any.
+    pub fn submission(&self) -> Option<&FormSubmission> {
+        self.inner.as_deref()
+    }
+}
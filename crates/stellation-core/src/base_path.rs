@@ -0,0 +1,25 @@
+//! The path that an application is mounted at.
+
+/// Normalises a base path to start and end with `/`, e.g.: `app` becomes `/app/`.
+///
+/// Empty paths and `/` are normalised to `/`.
+pub fn normalize(base_path: &str) -> String {
+    let segments = base_path
+        .split('/')
+        .filter(|m| !m.is_empty())
+        .collect::<Vec<_>>();
+
+    match segments.is_empty() {
+        true => "/".to_string(),
+        false => format!("/{}/", segments.join("/")),
+    }
+}
+
+/// Returns the base path that the backend is built with.
+///
+/// `stctl build` sets the base path of `stellation.toml` in `STELLATION_BUILD_BASE_PATH` when the
+/// backend is compiled, so the backend serves the same path as the frontend without additional
+/// configuration. Other builds use `/`.
+pub fn build_default() -> String {
+    normalize(option_env!("STELLATION_BUILD_BASE_PATH").unwrap_or("/"))
+}
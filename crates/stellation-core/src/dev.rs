@@ -14,6 +14,52 @@ pub struct StctlMetadata {
     pub listen_addr: String,
     /// The directory that contains the development build of frontend artifact.
     pub frontend_dev_build_dir: PathBuf,
+    /// A file that contains the path of the latest development build of frontend artifact.
+    ///
+    /// stctl writes the directory of a new build to this file when only the frontend is rebuilt,
+    /// the server should serve the frontend from the directory in this file if it is available.
+    #[serde(default)]
+    pub frontend_dev_build_file: Option<PathBuf>,
+    /// A file that contains the [`BuildStatus`] of the latest build.
+    ///
+    /// stctl updates this file when a build starts and completes, the server should display the
+    /// status in pages.
+    #[serde(default)]
+    pub build_status_file: Option<PathBuf>,
+    /// The path that the application is mounted at.
+    #[serde(default = "default_base_path")]
+    pub base_path: String,
+}
+
+fn default_base_path() -> String {
+    "/".to_string()
+}
+
+/// The status of the latest build of the development server.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "status", rename_all = "kebab-case")]
+pub enum BuildStatus {
+    /// A build is in progress.
+    Building,
+    /// The latest build has failed.
+    Failed {
+        /// The diagnostics of the failed build.
+        message: String,
+    },
+    /// The latest build has succeeded.
+    Ready,
+}
+
+impl BuildStatus {
+    /// Parses the status from a json string.
+    pub fn from_json(s: &str) -> serde_json::Result<Self> {
+        serde_json::from_str(s)
+    }
+
+    /// Serialises the status to a json string.
+    pub fn to_json(&self) -> serde_json::Result<String> {
+        serde_json::to_string(self)
+    }
 }
 
 impl StctlMetadata {
@@ -0,0 +1,84 @@
+use stellation_bridge::hooks::use_bridged_form;
+use stellation_bridge::links::Link;
+use stellation_bridge::routines::{BridgedMutation, MutationResult};
+use web_sys::{FormData, HtmlFormElement, UrlSearchParams};
+use yew::platform::spawn_local;
+use yew::prelude::*;
+
+/// The name of the field that identifies the mutation of a submitted form.
+const MUTATION_INDEX_FIELD: &str = "_stellation_mutation";
+
+/// Properties of [`BridgedForm`].
+#[derive(Properties, PartialEq, Debug)]
+pub struct BridgedFormProps<M>
+where
+    M: 'static + BridgedMutation,
+{
+    /// The fields of the form.
+    ///
+    /// The names of the fields need to match the fields of the mutation input.
+    #[prop_or_default]
+    pub children: Children,
+    /// Renders the result of the last submission after the fields.
+    #[prop_or_default]
+    pub render_result: Option<Callback<MutationResult<M>, Html>>,
+    /// Classes of the form element.
+    #[prop_or_default]
+    pub class: Classes,
+}
+
+/// A form that submits a mutation.
+///
+/// This component renders a HTML form that posts to the current page, so it works before the
+/// application is loaded. The backend decodes the form into the input of the mutation, resolves it
+/// and renders the page with the result.
+///
+/// After the application is loaded, the form is submitted with the bridge instead.
+#[function_component]
+pub fn BridgedForm<M, L>(props: &BridgedFormProps<M>) -> Html
+where
+    M: 'static + BridgedMutation,
+    L: 'static + Link,
+{
+    let handle = use_bridged_form::<M, L>();
+
+    let onsubmit = {
+        let handle = handle.clone();
+        Callback::from(move |e: SubmitEvent| {
+            let form = match e
+                .target_dyn_into::<HtmlFormElement>()
+                .and_then(|m| FormData::new_with_form(&m).ok())
+                .and_then(|m| UrlSearchParams::new_with_str_sequence_sequence(&m).ok())
+            {
+                Some(m) => m,
+                // Let the browser submit the form if it cannot be read.
+                None => return,
+            };
+            e.prevent_default();
+
+            form.delete(MUTATION_INDEX_FIELD);
+            let form = String::from(form.to_string());
+
+            let handle = handle.clone();
+            spawn_local(async move {
+                let _ = handle.submit(&form).await;
+            });
+        })
+    };
+
+    let result = props
+        .render_result
+        .as_ref()
+        .zip(handle.result())
+        .map(|(render, result)| render.emit(result.clone()));
+
+    html! {
+        <form method="post" class={props.class.clone()} {onsubmit}>
+            if let Some(m) = handle.mutation_index() {
+                <input type="hidden" name={MUTATION_INDEX_FIELD} value={m.to_string()} />
+            }
+            {props.children.clone()}
+            {result}
+        </form>
+    }
+}
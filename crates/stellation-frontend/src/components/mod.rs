@@ -1,5 +1,7 @@
 //! Stellation helper components.
 
+mod bridged_form;
 mod client_only;
 
+pub use bridged_form::{BridgedForm, BridgedFormProps};
 pub use client_only::ClientOnly;
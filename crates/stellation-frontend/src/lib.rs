@@ -16,8 +16,9 @@ use std::marker::PhantomData;
 
 use bounce::Selector;
 use stellation_bridge::links::{Link, PhantomLink};
-use stellation_bridge::state::BridgeState;
-use stellation_bridge::Bridge;
+use stellation_bridge::routines::FormSubmission;
+use stellation_bridge::state::{BridgeState, FormSubmissionState};
+use stellation_bridge::{meta, Bridge};
 use yew::prelude::*;
 
 use crate::root::{StellationRoot, StellationRootProps};
@@ -109,9 +110,19 @@ where
             <COMP ..props />
         };
 
+        // The base path is provided by the server if the application is not mounted at `/`.
+        let basename = meta::base_path().map(AttrValue::from);
+
+        // The result of a form submitted before the application is loaded.
+        let form_submission = meta::page_meta("form-submission")
+            .and_then(|m| FormSubmission::decode(&m))
+            .map(FormSubmissionState::from_submission);
+
         let props = StellationRootProps {
             bridge_state,
             children,
+            basename,
+            form_submission,
         };
 
         yew::Renderer::with_props(props)
@@ -124,15 +135,7 @@ where
     pub fn render(self) {
         let renderer = self.into_yew_renderer();
 
-        if web_sys::window()
-            .and_then(|m| m.document())
-            .and_then(|m| {
-                m.query_selector(r#"meta[name="stellation-mode"][content="hydrate"]"#)
-                    .ok()
-                    .flatten()
-            })
-            .is_some()
-        {
+        if meta::page_meta("mode").as_deref() == Some("hydrate") {
             renderer.hydrate();
         } else {
             renderer.render();
@@ -2,7 +2,7 @@ use anymap2::AnyMap;
 use bounce::helmet::HelmetBridge;
 use bounce::BounceRoot;
 use stellation_bridge::links::Link;
-use stellation_bridge::state::BridgeState;
+use stellation_bridge::state::{BridgeState, FormSubmissionState};
 use yew::prelude::*;
 use yew_router::BrowserRouter;
 
@@ -14,6 +14,8 @@ where
     #[prop_or_default]
     pub children: Html,
     pub bridge_state: Option<BridgeState<L>>,
+    pub basename: Option<AttrValue>,
+    pub form_submission: Option<FormSubmissionState>,
 }
 
 impl<L> PartialEq for StellationRootProps<L>
@@ -21,7 +23,10 @@ where
     L: Link,
 {
     fn eq(&self, other: &Self) -> bool {
-        self.children == other.children && self.bridge_state == other.bridge_state
+        self.children == other.children
+            && self.bridge_state == other.bridge_state
+            && self.basename == other.basename
+            && self.form_submission == other.form_submission
     }
 }
 
@@ -33,6 +38,8 @@ where
         Self {
             children: self.children.clone(),
             bridge_state: self.bridge_state.clone(),
+            basename: self.basename.clone(),
+            form_submission: self.form_submission.clone(),
         }
     }
 }
@@ -45,25 +52,30 @@ where
     let StellationRootProps {
         children,
         bridge_state,
+        basename,
+        form_submission,
     } = props.clone();
 
     let get_init_states = use_callback(
-        move |_, bridge_state| {
+        move |_, (bridge_state, form_submission)| {
             let mut states = AnyMap::new();
 
             if let Some(m) = bridge_state.clone() {
                 states.insert(m);
             }
+            if let Some(m) = form_submission.clone() {
+                states.insert(m);
+            }
 
             states
         },
-        bridge_state,
+        (bridge_state, form_submission),
     );
 
     html! {
         <BounceRoot {get_init_states}>
             <HelmetBridge />
-            <BrowserRouter>
+            <BrowserRouter {basename}>
                 {children}
             </BrowserRouter>
         </BounceRoot>
@@ -1,10 +1,13 @@
 use example_fullstack_api::{create_resolver_registry, create_routine_registry, Bridge, Link};
+use stellation_backend::Request;
 use stellation_backend_tower::TowerRequest;
 
-pub async fn create_backend_bridge(_req: TowerRequest<()>) -> Bridge {
+pub async fn create_backend_bridge(req: TowerRequest<()>) -> Bridge {
     Bridge::new(
         Link::builder()
             .context(())
+            .identity(req.identity().cloned())
+            .remote_addr(req.remote_addr().map(|m| m.ip()))
             .resolvers(create_resolver_registry())
             .routines(create_routine_registry())
             .build(),
@@ -17,9 +17,6 @@ struct Frontend;
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
-    // Configures Logging
-    stellation_backend_cli::trace::init_default("STELLATION_APP_SERVER_LOG");
-
     // Creates Endpoint
     let endpoint = TowerEndpoint::<ServerApp<_>>::new().with_create_bridge(create_backend_bridge);
 
@@ -27,8 +24,13 @@ async fn main() -> anyhow::Result<()> {
     let endpoint =
         endpoint.with_frontend(stellation_backend_tower::Frontend::new_embedded::<Frontend>());
 
-    // Starts Server
-    Cli::builder().endpoint(endpoint).build().run().await?;
+    // Starts Server, logging is configured by the command line
+    Cli::builder()
+        .endpoint(endpoint)
+        .log_filter_var("STELLATION_APP_SERVER_LOG")
+        .build()
+        .run()
+        .await?;
 
     Ok(())
 }
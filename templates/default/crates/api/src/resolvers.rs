@@ -1,7 +1,7 @@
 use async_trait::async_trait;
 use stellation_bridge::links::LocalLink;
 use stellation_bridge::registry::ResolverRegistry;
-use stellation_bridge::resolvers::{MutationResolver, QueryResolver};
+use stellation_bridge::resolvers::{MutationResolver, QueryResolver, ResolverContext};
 use stellation_bridge::routines::{MutationResult, QueryResult};
 use stellation_bridge::Bridge as Bridge_;
 use time::OffsetDateTime;
@@ -12,7 +12,7 @@ pub use crate::routines::*;
 impl QueryResolver for ServerTimeQuery {
     type Context = ();
 
-    async fn resolve(_ctx: &(), _input: &Self::Input) -> QueryResult<Self> {
+    async fn resolve(_ctx: &ResolverContext, _input: &Self::Input) -> QueryResult<Self> {
         Ok(Self {
             value: OffsetDateTime::now_utc(),
         }
@@ -24,7 +24,7 @@ impl QueryResolver for ServerTimeQuery {
 impl MutationResolver for GreetingMutation {
     type Context = ();
 
-    async fn resolve(_ctx: &(), name: &Self::Input) -> MutationResult<Self> {
+    async fn resolve(_ctx: &ResolverContext, name: &Self::Input) -> MutationResult<Self> {
         Ok(Self {
             message: format!("Hello, {name}!"),
         }